@@ -1,2 +1,4 @@
 pub mod errors;
 pub mod nvme;
+pub mod ras;
+pub mod storage;
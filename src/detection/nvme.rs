@@ -3,51 +3,16 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NvmeInfo {
-    pub device_path: String,
-    pub model: String,
-    pub pcie_gen: Option<u32>,
-}
-
-/// Detect NVMe device
-pub fn detect_nvme() -> Option<NvmeInfo> {
-    // Check for NVMe devices in /sys/class/nvme
-    let nvme_class = Path::new("/sys/class/nvme");
-    if !nvme_class.exists() {
-        return None;
-    }
-
-    // Look for nvme0
-    let nvme0 = nvme_class.join("nvme0");
-    if !nvme0.exists() {
-        return None;
-    }
-
-    // Get model name
-    let model = fs::read_to_string(nvme0.join("model"))
-        .map(|s| s.trim().to_string())
-        .unwrap_or_else(|_| "Unknown NVMe".to_string());
-
-    // Get device path
-    let device_path = "/dev/nvme0n1".to_string();
-    if !Path::new(&device_path).exists() {
-        return None;
+/// PCIe link generation of an NVMe controller's PCI function
+pub fn get_pcie_generation(pci_device: &Path) -> Option<u32> {
+    // Try sysfs first: it is per controller
+    if let Ok(speed) = fs::read_to_string(pci_device.join("current_link_speed")) {
+        if let Some(gen) = link_generation(&speed) {
+            return Some(gen);
+        }
     }
 
-    // Get PCIe generation
-    let pcie_gen = get_pcie_generation();
-
-    Some(NvmeInfo {
-        device_path,
-        model,
-        pcie_gen,
-    })
-}
-
-/// Get PCIe generation for NVMe device
-pub fn get_pcie_generation() -> Option<u32> {
-    // Try lspci first
+    // Fall back to the first NVMe controller lspci lists
     if let Ok(output) = Command::new("lspci").args(["-vvv"]).output() {
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -61,16 +26,9 @@ pub fn get_pcie_generation() -> Option<u32> {
                 }
 
                 // Look for LnkSta in NVMe section
+                // Format: LnkSta: Speed 8GT/s, Width x1
                 if in_nvme_section && line.contains("LnkSta:") {
-                    // Format: LnkSta: Speed 8GT/s, Width x1
-                    // Gen 1 = 2.5GT/s, Gen 2 = 5GT/s, Gen 3 = 8GT/s
-                    if line.contains("8GT/s") {
-                        return Some(3);
-                    } else if line.contains("5GT/s") {
-                        return Some(2);
-                    } else if line.contains("2.5GT/s") {
-                        return Some(1);
-                    }
+                    return link_generation(line);
                 }
 
                 // Check for new device section
@@ -81,26 +39,30 @@ pub fn get_pcie_generation() -> Option<u32> {
         }
     }
 
-    // Try reading from sysfs
-    let link_speed_path = "/sys/class/nvme/nvme0/device/current_link_speed";
-    if let Ok(speed) = fs::read_to_string(link_speed_path) {
-        let speed = speed.trim();
-        if speed.contains("8.0 GT/s") || speed.contains("8 GT/s") {
-            return Some(3);
-        } else if speed.contains("5.0 GT/s") || speed.contains("5 GT/s") {
-            return Some(2);
-        } else if speed.contains("2.5 GT/s") {
-            return Some(1);
-        }
-    }
-
     None
 }
 
+/// PCIe generation from a link speed such as "8.0 GT/s PCIe" or "Speed 8GT/s"
+/// Gen 1 = 2.5GT/s, Gen 2 = 5GT/s, Gen 3 = 8GT/s, Gen 4 = 16GT/s
+fn link_generation(speed: &str) -> Option<u32> {
+    let before = &speed[..speed.find("GT/s")?];
+    let number = before
+        .trim_end()
+        .rsplit(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    match number.parse::<f32>().ok()? {
+        s if s >= 16.0 => Some(4),
+        s if s >= 8.0 => Some(3),
+        s if s >= 5.0 => Some(2),
+        s if s >= 2.5 => Some(1),
+        _ => None,
+    }
+}
+
 /// Get NVMe temperature
 pub fn get_nvme_temp(device_path: &str) -> Option<f32> {
     // Try hwmon first (most reliable)
-    if let Some(temp) = get_nvme_temp_hwmon() {
+    if let Some(temp) = get_nvme_temp_hwmon(device_path) {
         return Some(temp);
     }
 
@@ -117,29 +79,24 @@ pub fn get_nvme_temp(device_path: &str) -> Option<f32> {
     None
 }
 
-/// Get NVMe temperature from hwmon
-fn get_nvme_temp_hwmon() -> Option<f32> {
-    let hwmon_path = Path::new("/sys/class/hwmon");
-    if !hwmon_path.exists() {
-        return None;
-    }
+/// Get NVMe temperature from the hwmon device of the drive's controller
+fn get_nvme_temp_hwmon(device_path: &str) -> Option<f32> {
+    let name = Path::new(device_path).file_name()?;
+    let controller = Path::new("/sys/block").join(name).join("device");
 
-    // Iterate through hwmon devices
-    if let Ok(entries) = fs::read_dir(hwmon_path) {
+    // Registered under the controller, or its PCI function on older kernels
+    for parent in [controller.clone(), controller.join("device")] {
+        let Ok(entries) = fs::read_dir(&parent) else {
+            continue;
+        };
         for entry in entries.flatten() {
-            let path = entry.path();
-
-            // Check if this is an NVMe hwmon device
-            let name_path = path.join("name");
-            if let Ok(name) = fs::read_to_string(&name_path) {
-                if name.trim().contains("nvme") {
-                    // Read temperature
-                    let temp_path = path.join("temp1_input");
-                    if let Ok(temp_str) = fs::read_to_string(&temp_path) {
-                        if let Ok(temp_millic) = temp_str.trim().parse::<i32>() {
-                            return Some(temp_millic as f32 / 1000.0);
-                        }
-                    }
+            if !entry.file_name().to_string_lossy().starts_with("hwmon") {
+                continue;
+            }
+            let temp_path = entry.path().join("temp1_input");
+            if let Ok(temp_str) = fs::read_to_string(&temp_path) {
+                if let Ok(temp_millic) = temp_str.trim().parse::<i32>() {
+                    return Some(temp_millic as f32 / 1000.0);
                 }
             }
         }
@@ -284,14 +241,21 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_detect_nvme() {
-        // Just ensure it doesn't panic
-        let _ = detect_nvme();
+    fn test_get_pcie_generation() {
+        let _ = get_pcie_generation(Path::new("/sys/class/nvme/nvme0/device"));
     }
 
     #[test]
-    fn test_get_pcie_generation() {
-        let _ = get_pcie_generation();
+    fn test_link_generation() {
+        assert_eq!(link_generation("8.0 GT/s PCIe\n"), Some(3));
+        assert_eq!(link_generation("5.0 GT/s PCIe"), Some(2));
+        assert_eq!(link_generation("2.5 GT/s PCIe"), Some(1));
+        assert_eq!(link_generation("16.0 GT/s PCIe"), Some(4));
+        assert_eq!(
+            link_generation("\t\tLnkSta:\tSpeed 8GT/s, Width x1 (downgraded)"),
+            Some(3)
+        );
+        assert_eq!(link_generation("Unknown"), None);
     }
 
     #[test]
@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::system::memlimit::read_u64;
+
+/// Kernel log messages raised by arm64 RAS (SError, APEI/GHES, external aborts)
+const RAS_LOG_PATTERNS: &[&str] = &[
+    "serror interrupt",
+    "[hardware error]",
+    "synchronous external abort",
+];
+
+/// Longest record /dev/kmsg hands out in one read
+const KMSG_RECORD_MAX: usize = 8192;
+
+/// Hardware error counters, None where the kernel does not expose them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RasCounts {
+    /// EDAC memory controllers found
+    pub controllers: usize,
+    /// Corrected errors (ce_count) over all memory controllers
+    pub corrected: Option<u64>,
+    /// Uncorrected errors (ue_count) over all memory controllers
+    pub uncorrected: Option<u64>,
+    /// RAS error reports logged by the kernel since the monitor started
+    pub kernel_reports: Option<u64>,
+}
+
+impl RasCounts {
+    /// EDAC counts that went up since `baseline`; a counter missing from
+    /// either sample stays unavailable
+    fn since(&self, baseline: &RasCounts) -> RasCounts {
+        let delta = |now: Option<u64>, then: Option<u64>| Some(now?.saturating_sub(then?));
+        RasCounts {
+            corrected: delta(self.corrected, baseline.corrected),
+            uncorrected: delta(self.uncorrected, baseline.uncorrected),
+            ..*self
+        }
+    }
+
+    /// Any hardware error was counted
+    pub fn any(&self) -> bool {
+        [self.corrected, self.uncorrected, self.kernel_reports]
+            .iter()
+            .any(|count| count.unwrap_or(0) > 0)
+    }
+
+    /// Correctable/uncorrectable part only, for the compact live display
+    pub fn edac_summary(&self) -> String {
+        match (self.corrected, self.uncorrected) {
+            (Some(ce), Some(ue)) => format!("CE {} / UE {}", ce, ue),
+            _ => "not available".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RasCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count =
+            |value: Option<u64>| value.map_or("not available".to_string(), |v| v.to_string());
+        write!(
+            f,
+            "corrected {}, uncorrected {}, kernel RAS reports {}",
+            count(self.corrected),
+            count(self.uncorrected),
+            count(self.kernel_reports)
+        )
+    }
+}
+
+/// Watches EDAC counters and kernel RAS reports for changes during the run
+pub struct RasMonitor {
+    edac_root: PathBuf,
+    baseline: RasCounts,
+    kernel_log: Option<KernelLog>,
+}
+
+impl RasMonitor {
+    /// Take the baseline from the live system
+    pub fn start() -> Self {
+        Self::start_at(
+            Path::new("/sys/devices/system/edac"),
+            KernelLog::open(Path::new("/dev/kmsg")),
+        )
+    }
+
+    /// Take the baseline from an alternative EDAC sysfs directory (for tests)
+    fn start_at(edac_root: &Path, kernel_log: Option<KernelLog>) -> Self {
+        let edac_root = edac_root.to_path_buf();
+        let baseline = read_counts(&edac_root);
+        Self {
+            edac_root,
+            baseline,
+            kernel_log,
+        }
+    }
+
+    /// Errors counted since the monitor started
+    pub fn sample(&mut self) -> RasCounts {
+        RasCounts {
+            kernel_reports: self.kernel_log.as_mut().map(KernelLog::poll),
+            ..read_counts(&self.edac_root).since(&self.baseline)
+        }
+    }
+}
+
+/// Kernel log records as they are written, counting the RAS reports
+/// Only records logged after opening are read, so the count cannot be
+/// thrown off by old records rotating out of the ring buffer.
+struct KernelLog {
+    records: Box<dyn Read + Send>,
+    reports: u64,
+}
+
+impl KernelLog {
+    /// Open /dev/kmsg at its end; None when it cannot be read
+    /// (dmesg_restrict without root)
+    fn open(path: &Path) -> Option<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .ok()?;
+        file.seek(SeekFrom::End(0)).ok()?;
+        Some(Self {
+            records: Box::new(file),
+            reports: 0,
+        })
+    }
+
+    /// Read the records logged since the last poll; returns the RAS
+    /// reports seen so far
+    fn poll(&mut self) -> u64 {
+        let mut record = vec![0u8; KMSG_RECORD_MAX];
+        loop {
+            match self.records.read(&mut record) {
+                Ok(0) => break,
+                Ok(len) => {
+                    if is_ras_report(&record[..len]) {
+                        self.reports += 1;
+                    }
+                }
+                // Records were overwritten before being read; go on from the oldest left
+                Err(e) if e.raw_os_error() == Some(libc::EPIPE) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                // WouldBlock: nothing new yet
+                Err(_) => break,
+            }
+        }
+        self.reports
+    }
+}
+
+/// Sum ce_count/ue_count over edac/mc/mc*
+fn read_counts(edac_root: &Path) -> RasCounts {
+    let mut counts = RasCounts::default();
+
+    let Ok(entries) = fs::read_dir(edac_root.join("mc")) else {
+        return counts;
+    };
+    let mut controllers: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("mc"))
+        })
+        .collect();
+    controllers.sort();
+
+    let add = |total: Option<u64>, path: PathBuf| match read_u64(&path) {
+        Some(count) => Some(total.unwrap_or(0) + count),
+        None => total,
+    };
+    for controller in &controllers {
+        counts.corrected = add(counts.corrected, controller.join("ce_count"));
+        counts.uncorrected = add(counts.uncorrected, controller.join("ue_count"));
+    }
+    counts.controllers = controllers.len();
+    counts
+}
+
+/// Whether a /dev/kmsg record ("prio,seq,usec,flags;message") is a RAS report
+fn is_ras_report(record: &[u8]) -> bool {
+    let record = String::from_utf8_lossy(record);
+    let message = record
+        .lines()
+        .next()
+        .and_then(|line| line.split_once(';'))
+        .map_or("", |(_, message)| message)
+        .to_lowercase();
+    RAS_LOG_PATTERNS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    fn fake_edac(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        crate::testing::fake_tree(&format!("ras-{}", name), files)
+    }
+
+    /// Records handed out one per read like /dev/kmsg, or an errno such as
+    /// EPIPE for records lost to rotation
+    type Records = Arc<Mutex<VecDeque<Result<&'static str, i32>>>>;
+
+    struct FakeKmsg(Records);
+
+    impl Read for FakeKmsg {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.lock().unwrap().pop_front() {
+                Some(Ok(record)) => {
+                    buf[..record.len()].copy_from_slice(record.as_bytes());
+                    Ok(record.len())
+                }
+                Some(Err(errno)) => Err(io::Error::from_raw_os_error(errno)),
+                None => Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    fn fake_kernel_log(records: &Records) -> KernelLog {
+        KernelLog {
+            records: Box::new(FakeKmsg(records.clone())),
+            reports: 0,
+        }
+    }
+
+    #[test]
+    fn test_counts_increase_over_baseline() {
+        let root = fake_edac(
+            "increase",
+            &[
+                ("mc/mc0/ce_count", "3\n"),
+                ("mc/mc0/ue_count", "0\n"),
+                ("mc/mc1/ce_count", "1\n"),
+                ("mc/mc1/ue_count", "0\n"),
+            ],
+        );
+        let records = Records::default();
+        let mut monitor = RasMonitor::start_at(&root, Some(fake_kernel_log(&records)));
+        assert_eq!(monitor.baseline.controllers, 2);
+        assert_eq!(monitor.baseline.corrected, Some(4));
+
+        records
+            .lock()
+            .unwrap()
+            .push_back(Ok("6,1201,1000000,-;usb 1-1: new device\n"));
+        let quiet = monitor.sample();
+        assert!(!quiet.any());
+        assert_eq!(quiet.edac_summary(), "CE 0 / UE 0");
+
+        fs::write(root.join("mc/mc1/ce_count"), "3\n").unwrap();
+        fs::write(root.join("mc/mc0/ue_count"), "1\n").unwrap();
+        records.lock().unwrap().push_back(Ok(
+            "0,1202,9900000,-;SError Interrupt on CPU2, code 0xbf000002 -- SError\n SUBSYSTEM=cpu\n",
+        ));
+        let errors = monitor.sample();
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(errors.corrected, Some(2));
+        assert_eq!(errors.uncorrected, Some(1));
+        assert_eq!(errors.kernel_reports, Some(1));
+        assert!(errors.any());
+    }
+
+    #[test]
+    fn test_missing_counters_are_not_available() {
+        let root = fake_edac("missing", &[]);
+        let mut monitor = RasMonitor::start_at(&root, None);
+        let counts = monitor.sample();
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(counts.controllers, 0);
+        assert_eq!(counts.corrected, None);
+        assert_eq!(counts.kernel_reports, None);
+        assert!(!counts.any());
+        assert_eq!(counts.edac_summary(), "not available");
+        assert_eq!(
+            counts.to_string(),
+            "corrected not available, uncorrected not available, kernel RAS reports not available"
+        );
+    }
+
+    #[test]
+    fn test_reports_survive_ring_buffer_rotation() {
+        let records = Records::default();
+        let mut log = fake_kernel_log(&records);
+        assert_eq!(log.poll(), 0);
+
+        // The ring buffer wraps before the next poll: old records are lost
+        // (EPIPE), but every report still readable after that is counted
+        records.lock().unwrap().extend([
+            Ok("6,1300,2000000,-;usb 1-1: new device\n"),
+            Err(libc::EPIPE),
+            Ok("3,1452,3000000,-;{1}[Hardware Error]: event severity: recoverable\n"),
+            Ok("6,1453,3000001,-;mmc0: new ultra high speed SDR104 card\n"),
+            Ok("0,1454,3000002,-;SError Interrupt on CPU1, code 0xbe000011 -- SError\n"),
+        ]);
+        assert_eq!(log.poll(), 2);
+        assert_eq!(log.poll(), 2);
+
+        records.lock().unwrap().push_back(Ok(
+            "0,1460,4000000,-;Internal error: synchronous external abort\n",
+        ));
+        assert_eq!(log.poll(), 3);
+    }
+}
@@ -0,0 +1,637 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::nvme;
+
+/// Kind of block device, by the bus it sits on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageKind {
+    /// NVMe namespace behind a PCIe controller
+    Nvme,
+    /// SD card (or eMMC) on the SoC's SDIO controller
+    SdCard,
+    /// USB mass storage, on the RP1 xHCI controllers on a Pi 5
+    Usb,
+}
+
+impl StorageKind {
+    /// Name accepted by --storage for every device of this kind
+    fn selector(&self) -> &'static str {
+        match self {
+            StorageKind::Nvme => "nvme",
+            StorageKind::SdCard => "sd",
+            StorageKind::Usb => "usb",
+        }
+    }
+}
+
+impl fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageKind::Nvme => write!(f, "NVMe"),
+            StorageKind::SdCard => write!(f, "SD card"),
+            StorageKind::Usb => write!(f, "USB"),
+        }
+    }
+}
+
+/// A whole-disk block device that can be stress tested
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageDevice {
+    /// Kernel name, e.g. nvme0n1, mmcblk0 or sda
+    pub name: String,
+    pub device_path: String,
+    pub kind: StorageKind,
+    pub model: String,
+    pub size_bytes: u64,
+    /// PCIe link generation of an NVMe controller
+    pub pcie_gen: Option<u32>,
+}
+
+impl StorageDevice {
+    /// Drive temperature, where the device reports one (NVMe only)
+    pub fn temperature(&self) -> Option<f32> {
+        match self.kind {
+            StorageKind::Nvme => nvme::get_nvme_temp(&self.device_path),
+            StorageKind::SdCard | StorageKind::Usb => None,
+        }
+    }
+}
+
+impl fmt::Display for StorageDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {} GB)",
+            self.model,
+            self.kind,
+            self.size_bytes / 1_000_000_000
+        )
+    }
+}
+
+/// Detect NVMe namespaces, SD cards and USB mass storage
+pub fn detect_storage() -> Vec<StorageDevice> {
+    detect_storage_in(Path::new("/sys"))
+}
+
+fn detect_storage_in(sys: &Path) -> Vec<StorageDevice> {
+    let block = sys.join("block");
+    let Ok(entries) = fs::read_dir(&block) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let dir = block.join(&name);
+            // The link into /sys/devices shows which bus the disk hangs off
+            let sysfs_path = fs::read_link(&dir).unwrap_or_default();
+            let kind = classify(&name, &sysfs_path.to_string_lossy())?;
+            // Empty card readers show up with size 0
+            let size_bytes = read_trimmed(&dir.join("size"))?.parse::<u64>().ok()? * 512;
+            if size_bytes == 0 {
+                return None;
+            }
+
+            let device = dir.join("device");
+            let model = match kind {
+                StorageKind::Usb => [
+                    read_trimmed(&device.join("vendor")),
+                    read_trimmed(&device.join("model")),
+                ]
+                .into_iter()
+                .flatten()
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+                StorageKind::Nvme => read_trimmed(&device.join("model")).unwrap_or_default(),
+                StorageKind::SdCard => read_trimmed(&device.join("name")).unwrap_or_default(),
+            };
+            let model = if model.is_empty() {
+                format!("Unknown {}", kind)
+            } else {
+                model
+            };
+            // namespace -> controller -> PCI function
+            let pcie_gen = (kind == StorageKind::Nvme)
+                .then(|| nvme::get_pcie_generation(&device.join("device")))
+                .flatten();
+
+            Some(StorageDevice {
+                device_path: format!("/dev/{}", name),
+                name,
+                kind,
+                model,
+                size_bytes,
+                pcie_gen,
+            })
+        })
+        .collect()
+}
+
+/// Kind of the block device `name`, or None for anything not worth testing
+/// (partitions, loop and RAM disks, eMMC boot areas, SATA behind PCIe)
+fn classify(name: &str, sysfs_path: &str) -> Option<StorageKind> {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if let Some(rest) = name.strip_prefix("nvme") {
+        // nvme0n1, but not the hidden per-path nvme0c0n1 of multipath setups
+        let (controller, namespace) = rest.split_once('n')?;
+        return (digits(controller) && digits(namespace)).then_some(StorageKind::Nvme);
+    }
+    if let Some(rest) = name.strip_prefix("mmcblk") {
+        return digits(rest).then_some(StorageKind::SdCard);
+    }
+    if let Some(rest) = name.strip_prefix("sd") {
+        let letters = !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_lowercase());
+        return (letters && sysfs_path.contains("/usb")).then_some(StorageKind::Usb);
+    }
+    None
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Pick the devices to stress from `specs`, each a kernel name ("nvme0n1"),
+/// a device path ("/dev/sda"), a kind ("nvme", "sd", "usb") or "all"
+/// Without specs every NVMe namespace is picked. Devices keep their
+/// detection order and are picked once.
+pub fn select(devices: &[StorageDevice], specs: &[String]) -> Result<Vec<StorageDevice>, String> {
+    if specs.is_empty() {
+        return Ok(devices
+            .iter()
+            .filter(|d| d.kind == StorageKind::Nvme)
+            .cloned()
+            .collect());
+    }
+
+    let mut picked = vec![false; devices.len()];
+    for spec in specs {
+        let spec = spec.trim();
+        let matches: Vec<usize> = devices
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| {
+                spec == "all"
+                    || spec == d.kind.selector()
+                    || spec == d.name
+                    || spec == d.device_path
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if matches.is_empty() {
+            let found: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+            return Err(format!(
+                "No storage device '{}' (found: {})",
+                spec,
+                if found.is_empty() {
+                    "none".to_string()
+                } else {
+                    found.join(", ")
+                }
+            ));
+        }
+        for i in matches {
+            picked[i] = true;
+        }
+    }
+    Ok(devices
+        .iter()
+        .zip(picked)
+        .filter(|&(_, picked)| picked)
+        .map(|(d, _)| d.clone())
+        .collect())
+}
+
+/// The device named by `spec`, a kernel name ("nvme1n1") or device path
+pub fn find<'a>(devices: &'a [StorageDevice], spec: &str) -> Option<&'a StorageDevice> {
+    devices
+        .iter()
+        .find(|d| spec == d.name || spec == d.device_path)
+}
+
+/// What is using `device` or any of its partitions, one entry per user:
+/// mounted filesystems, active swap, and holders such as device-mapper,
+/// RAID or LVM. Empty when nothing on this system uses the device.
+pub fn users_of(device: &StorageDevice) -> Vec<String> {
+    let read = |path: &str| fs::read_to_string(path).unwrap_or_default();
+    users_in(
+        device,
+        Path::new("/sys"),
+        &read_mounts(),
+        &read("/proc/self/mountinfo"),
+        &read("/proc/swaps"),
+    )
+}
+
+fn users_in(
+    device: &StorageDevice,
+    sys: &Path,
+    mounts: &str,
+    mountinfo: &str,
+    swaps: &str,
+) -> Vec<String> {
+    let mut users = Vec::new();
+    let mut mount_points = Vec::new();
+    for mount in mounts_of(device, mounts) {
+        users.push(format!(
+            "{} is mounted at {}",
+            mount.source, mount.mount_point
+        ));
+        mount_points.push(mount.mount_point);
+    }
+
+    // The disk and its partitions
+    let disk = sys.join("block").join(&device.name);
+    let mut parts: Vec<(String, PathBuf)> = vec![(device.name.clone(), disk.clone())];
+    if let Ok(entries) = fs::read_dir(&disk) {
+        parts.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.path().join("partition").exists())
+                .map(|entry| {
+                    (
+                        entry.file_name().to_string_lossy().to_string(),
+                        entry.path(),
+                    )
+                }),
+        );
+    }
+    parts.sort();
+
+    // By device number too, which catches mounts listed under another
+    // name, such as /dev/root
+    let numbers: Vec<(String, &str)> = parts
+        .iter()
+        .filter_map(|(name, dir)| Some((read_trimmed(&dir.join("dev"))?, name.as_str())))
+        .collect();
+    for line in mountinfo.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (Some(&number), Some(&point)) = (fields.get(2), fields.get(4)) else {
+            continue;
+        };
+        let point = unescape_mount(point);
+        if let Some((_, name)) = numbers.iter().find(|(n, _)| n == number) {
+            if !mount_points.contains(&point) {
+                users.push(format!("/dev/{} is mounted at {}", name, point));
+                mount_points.push(point);
+            }
+        }
+    }
+
+    for line in swaps.lines().skip(1) {
+        let Some(file) = line.split_whitespace().next() else {
+            continue;
+        };
+        if file
+            .strip_prefix("/dev/")
+            .is_some_and(|name| is_on_device(name, &device.name))
+        {
+            users.push(format!("{} is in use as swap", file));
+        }
+    }
+
+    for (name, dir) in &parts {
+        let Ok(holders) = fs::read_dir(dir.join("holders")) else {
+            continue;
+        };
+        for holder in holders.flatten() {
+            users.push(format!(
+                "/dev/{} is held by {} (device-mapper, RAID or LVM)",
+                name,
+                holder.file_name().to_string_lossy()
+            ));
+        }
+    }
+    users
+}
+
+/// Current mount table, empty if it cannot be read
+pub fn read_mounts() -> String {
+    fs::read_to_string("/proc/mounts").unwrap_or_default()
+}
+
+/// A filesystem on a device or one of its partitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    /// e.g. /dev/nvme0n1p2
+    pub source: String,
+    pub mount_point: String,
+    pub read_only: bool,
+}
+
+/// Filesystems in `mounts` (in /proc/mounts format) that live on `device`
+pub fn mounts_of(device: &StorageDevice, mounts: &str) -> Vec<Mount> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (source, mount_point) = (fields.next()?, fields.next()?);
+            let options = fields.nth(1).unwrap_or("");
+            let name = source.strip_prefix("/dev/")?;
+            is_on_device(name, &device.name).then(|| Mount {
+                source: source.to_string(),
+                mount_point: unescape_mount(mount_point),
+                read_only: options.split(',').any(|o| o == "ro"),
+            })
+        })
+        .collect()
+}
+
+/// Whether block device `name` is `disk` or one of its partitions
+/// (sda1, or nvme0n1p2 and mmcblk0p1 where the disk name ends in a digit)
+fn is_on_device(name: &str, disk: &str) -> bool {
+    let Some(rest) = name.strip_prefix(disk) else {
+        return false;
+    };
+    let partition = if disk.ends_with(|c: char| c.is_ascii_digit()) {
+        match rest.strip_prefix('p') {
+            Some(number) => number,
+            None => return rest.is_empty(),
+        }
+    } else {
+        rest
+    };
+    partition.is_empty() || partition.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Undo the octal escapes /proc/mounts uses for spaces, tabs and newlines
+fn unescape_mount(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 4)
+            .and_then(|o| u8::from_str_radix(o, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// Kernel name, place under /sys/devices and files relative to the disk
+    type FakeDisk<'a> = (&'a str, &'a str, &'a [(&'a str, &'a str)]);
+
+    /// A /sys with block devices linked to their place in /sys/devices
+    fn fake_sys(name: &str, disks: &[FakeDisk]) -> PathBuf {
+        let files: Vec<(PathBuf, &str)> = disks
+            .iter()
+            .flat_map(|(disk, devpath, files)| {
+                let dir = Path::new("devices").join(devpath).join(disk);
+                files
+                    .iter()
+                    .map(move |(file, contents)| (dir.join(file), *contents))
+            })
+            .collect();
+        let root = crate::testing::fake_tree(&format!("storage-{}", name), &files);
+        fs::create_dir_all(root.join("block")).unwrap();
+        for (disk, devpath, _) in disks {
+            let dir = root.join("devices").join(devpath).join(disk);
+            fs::create_dir_all(dir.join("device")).unwrap();
+            symlink(
+                Path::new("../devices").join(devpath).join(disk),
+                root.join("block").join(disk),
+            )
+            .unwrap();
+        }
+        root
+    }
+
+    fn device(name: &str, kind: StorageKind) -> StorageDevice {
+        StorageDevice {
+            name: name.to_string(),
+            device_path: format!("/dev/{}", name),
+            kind,
+            model: "Test".to_string(),
+            size_bytes: 1 << 30,
+            pcie_gen: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_storage() {
+        // Just ensure it doesn't panic
+        let _ = detect_storage();
+    }
+
+    #[test]
+    fn test_detects_nvme_sd_and_usb() {
+        let pcie = "platform/axi/1000110000.pcie/pci0000:00/0000:00:00.0/0000:01:00.0/nvme/nvme0";
+        let usb = "platform/axi/1000120000.pcie/1f00200000.usb/xhci-hcd.0/usb1/1-1/1-1:1.0/host0/target0:0:0/0:0:0:0/block";
+        let root = fake_sys(
+            "detect",
+            &[
+                (
+                    "nvme0n1",
+                    pcie,
+                    &[
+                        ("size", "1953525168\n"),
+                        ("device/model", "Samsung SSD 980 1TB      \n"),
+                    ],
+                ),
+                (
+                    "mmcblk0",
+                    "platform/axi/1000fff000.mmc/mmc_host/mmc0/mmc0:aaaa/block",
+                    &[("size", "62333952\n"), ("device/name", "SD64G\n")],
+                ),
+                (
+                    "sda",
+                    usb,
+                    &[
+                        ("size", "500118192\n"),
+                        ("device/vendor", "Samsung \n"),
+                        ("device/model", "Portable SSD T5  \n"),
+                    ],
+                ),
+                // Not storage worth testing, or not on USB
+                ("loop0", "virtual/block", &[("size", "0\n")]),
+                ("mmcblk0boot0", "platform/mmc", &[("size", "8192\n")]),
+                ("nvme0c0n1", pcie, &[("size", "1953525168\n")]),
+                ("sdb", "platform/ahci/ata1/host1", &[("size", "1000\n")]),
+                // Empty card reader
+                ("sdc", usb, &[("size", "0\n")]),
+            ],
+        );
+        let devices = detect_storage_in(&root);
+        fs::remove_dir_all(&root).ok();
+
+        let found: Vec<_> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind, d.model.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("mmcblk0", StorageKind::SdCard, "SD64G"),
+                ("nvme0n1", StorageKind::Nvme, "Samsung SSD 980 1TB"),
+                ("sda", StorageKind::Usb, "Samsung Portable SSD T5"),
+            ]
+        );
+        assert_eq!(devices[1].device_path, "/dev/nvme0n1");
+        assert_eq!(devices[1].size_bytes, 1953525168 * 512);
+        assert_eq!(
+            devices[2].to_string(),
+            "Samsung Portable SSD T5 (USB, 256 GB)"
+        );
+    }
+
+    #[test]
+    fn test_select_targets() {
+        let devices = [
+            device("mmcblk0", StorageKind::SdCard),
+            device("nvme0n1", StorageKind::Nvme),
+            device("nvme1n1", StorageKind::Nvme),
+            device("sda", StorageKind::Usb),
+        ];
+        let names = |specs: &[&str]| {
+            let specs: Vec<String> = specs.iter().map(|s| s.to_string()).collect();
+            select(&devices, &specs).map(|picked| {
+                picked
+                    .into_iter()
+                    .map(|d| d.name)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+        };
+
+        assert_eq!(names(&[]).unwrap(), "nvme0n1,nvme1n1");
+        assert_eq!(names(&["sda", "nvme1n1"]).unwrap(), "nvme1n1,sda");
+        assert_eq!(names(&["/dev/mmcblk0", "usb"]).unwrap(), "mmcblk0,sda");
+        assert_eq!(names(&["nvme", "nvme0n1"]).unwrap(), "nvme0n1,nvme1n1");
+        assert_eq!(names(&["all"]).unwrap().split(',').count(), 4);
+        assert_eq!(
+            names(&["sdb"]).unwrap_err(),
+            "No storage device 'sdb' (found: mmcblk0, nvme0n1, nvme1n1, sda)"
+        );
+        assert!(select(&[], &["usb".to_string()]).is_err());
+        assert!(select(&[], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mounts_of_device() {
+        let mounts = "\
+/dev/nvme0n1p2 / ext4 rw,noatime 0 0
+/dev/nvme0n1p1 /boot/firmware vfat rw,relatime 0 0
+/dev/nvme1n1 /mnt/fast\\040data ext4 rw 0 0
+/dev/mmcblk0p1 /media/card vfat ro,nosuid 0 0
+/dev/sda1 /media/usb exfat rw 0 0
+/dev/sdaa1 /media/other exfat rw 0 0
+tmpfs /tmp tmpfs rw 0 0
+";
+        let points = |name: &str, kind| -> Vec<(String, bool)> {
+            mounts_of(&device(name, kind), mounts)
+                .into_iter()
+                .map(|m| (m.mount_point, m.read_only))
+                .collect()
+        };
+
+        assert_eq!(
+            points("nvme0n1", StorageKind::Nvme),
+            [
+                ("/".to_string(), false),
+                ("/boot/firmware".to_string(), false)
+            ]
+        );
+        assert_eq!(
+            points("nvme1n1", StorageKind::Nvme),
+            [("/mnt/fast data".to_string(), false)]
+        );
+        assert_eq!(
+            points("mmcblk0", StorageKind::SdCard),
+            [("/media/card".to_string(), true)]
+        );
+        assert_eq!(
+            points("sda", StorageKind::Usb),
+            [("/media/usb".to_string(), false)]
+        );
+        assert!(is_on_device("nvme0n1", "nvme0n1"));
+        assert!(is_on_device("sda1", "sda"));
+        assert!(!is_on_device("nvme0n10", "nvme0n1"));
+        assert!(!is_on_device("mmcblk01", "mmcblk0"));
+    }
+
+    #[test]
+    fn test_users_of_device() {
+        let root = fake_sys(
+            "users",
+            &[
+                (
+                    "nvme1n1",
+                    "nvme/nvme1",
+                    &[
+                        ("dev", "259:0\n"),
+                        ("nvme1n1p1/partition", "1\n"),
+                        ("nvme1n1p1/dev", "259:1\n"),
+                        ("nvme1n1p2/partition", "2\n"),
+                        ("nvme1n1p2/dev", "259:2\n"),
+                        ("nvme1n1p2/holders/dm-0", ""),
+                    ],
+                ),
+                ("nvme2n1", "nvme/nvme2", &[("dev", "259:5\n")]),
+            ],
+        );
+        let mounts = "/dev/nvme1n1p1 /mnt/a ext4 rw 0 0\n/dev/root / ext4 rw 0 0\n";
+        let mountinfo = "\
+36 25 259:1 / /mnt/a rw,relatime - ext4 /dev/nvme1n1p1 rw
+22 1 259:2 / / rw,noatime - ext4 /dev/root rw
+40 22 0:5 / /dev rw - devtmpfs udev rw
+";
+        let swaps = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/nvme1n1p3                          partition\t1048572\t\t0\t\t-2
+/dev/zram0                              partition\t2097148\t\t0\t\t100
+";
+        let users = |name: &str| {
+            users_in(
+                &device(name, StorageKind::Nvme),
+                &root,
+                mounts,
+                mountinfo,
+                swaps,
+            )
+        };
+        let busy = users("nvme1n1");
+        let idle = users("nvme2n1");
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(
+            busy,
+            [
+                "/dev/nvme1n1p1 is mounted at /mnt/a",
+                "/dev/nvme1n1p2 is mounted at /",
+                "/dev/nvme1n1p3 is in use as swap",
+                "/dev/nvme1n1p2 is held by dm-0 (device-mapper, RAID or LVM)",
+            ]
+        );
+        assert!(idle.is_empty());
+
+        let devices = [device("nvme1n1", StorageKind::Nvme)];
+        assert!(find(&devices, "/dev/nvme1n1").is_some());
+        assert!(find(&devices, "nvme1n1").is_some());
+        assert!(find(&devices, "nvme").is_none());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod detection;
 pub mod stress;
 pub mod system;
+#[cfg(test)]
+mod testing;
 pub mod ui;
@@ -6,8 +6,18 @@ use clap::{ArgAction, Parser};
 use tokio::sync::mpsc;
 
 use pi_under_pressure::{
-    detection,
-    stress::{self, StressConfig},
+    detection::{self, storage::StorageDevice},
+    stress::{
+        self,
+        buffer::HugePages,
+        coverage::{parse_bytes, MemorySize},
+        cpu::{self, CpuMode},
+        io_engine::IoEngine,
+        memory::{self, MemoryWorkload},
+        nvme::{self, NvmeOptions},
+        workload::{Workload, WorkloadMix},
+        StressConfig,
+    },
     system,
     ui::{self, UiMode},
 };
@@ -47,18 +57,102 @@ struct Args {
     #[arg(short = 'm', long)]
     memory_only: bool,
 
-    /// Test only NVMe
+    /// Test only NVMe (or the --storage targets)
     #[arg(short = 'n', long)]
     nvme_only: bool,
 
-    /// Custom path for NVMe stress test file
+    /// Storage to stress: a device (nvme1n1, /dev/sda), nvme, sd, usb or all; repeatable [default: nvme]
+    #[arg(long = "storage", value_name = "TARGET", value_delimiter = ',')]
+    storage: Vec<String>,
+
+    /// Custom path for NVMe stress test file (single storage target only)
     #[arg(short = 'p', long)]
     nvme_path: Option<String>,
 
+    /// NVMe test file size (e.g. 4G) [default: half the free space, at most 8G]
+    #[arg(long)]
+    nvme_size: Option<String>,
+
+    /// DESTRUCTIVE: write and verify the whole block device (e.g. /dev/nvme1n1) instead of a test file
+    #[arg(long, value_name = "DEVICE")]
+    raw_device: Option<String>,
+
+    /// Confirm that --raw-device erases everything on the device
+    #[arg(long)]
+    i_understand_data_loss: bool,
+
+    /// Parallel NVMe I/O workers, each on its own slice of the test file
+    #[arg(long, default_value_t = 4)]
+    nvme_workers: usize,
+
+    /// I/O requests each NVMe worker keeps in flight
+    #[arg(long, default_value_t = 8)]
+    nvme_queue_depth: usize,
+
+    /// NVMe I/O engine (io-uring falls back to threads where the kernel lacks it)
+    #[arg(long, value_enum, default_value_t = IoEngine::IoUring)]
+    nvme_engine: IoEngine,
+
+    /// Share of reads in the mixed NVMe workload, in percent
+    #[arg(long, default_value_t = 70)]
+    nvme_read_mix: u8,
+
+    /// Request size of the random NVMe workloads (multiple of 4K, at most 1M)
+    #[arg(long, default_value = "4K")]
+    nvme_block_size: String,
+
+    /// Warn about NVMe requests that take longer than this
+    #[arg(long, default_value = "1s")]
+    nvme_slow_io: String,
+
     /// Number of CPU threads [default: all cores]
     #[arg(short, long)]
     threads: Option<usize>,
 
+    /// CPU verification mode (vote: all cores run the same job and compare results)
+    #[arg(long, value_enum, default_value_t = CpuMode::Rotate)]
+    cpu_mode: CpuMode,
+
+    /// Shortest square-wave period in load-step mode
+    #[arg(long, default_value = "1ms")]
+    step_min: String,
+
+    /// Longest square-wave period in load-step mode (swept from min to max over the run)
+    #[arg(long, default_value = "5s")]
+    step_max: String,
+
+    /// CPU workloads to rotate, with optional weights (e.g. dft:2,matrix,aes; rotate and vote modes) [default: all]
+    #[arg(long)]
+    cpu_workloads: Option<String>,
+
+    /// Memory workloads to rotate, with optional weights (e.g. fill,stream:2) [default: all]
+    #[arg(long)]
+    memory_workloads: Option<String>,
+
+    /// Add the row-hammer disturbance test to the memory workloads (off by default)
+    #[arg(long)]
+    row_hammer: bool,
+
+    /// Memory to test: size (e.g. 2G, 512M) or share of RAM (e.g. 50%, 80%total) [default: 50%]
+    #[arg(long)]
+    memory_size: Option<String>,
+
+    /// Number of memory worker processes [default: min(2, threads)]
+    #[arg(long)]
+    memory_threads: Option<usize>,
+
+    /// Huge page backing for memory test buffers (off, transparent, explicit)
+    #[arg(long, value_enum, default_value_t = HugePages::Transparent)]
+    hugepages: HugePages,
+
+    /// Flag workloads that run this fraction slower than in the first minute without throttling
+    #[arg(long, default_value_t = 0.15)]
+    throughput_drop: f64,
+
+    /// Declare a worker hung after this long without progress
+    #[arg(long, default_value = "60s")]
+    hang_timeout: String,
+
     /// Status update interval in seconds
     #[arg(short, long, default_value = "2")]
     interval: u64,
@@ -80,27 +174,156 @@ fn parse_duration(s: &str) -> Result<Duration, String> {
     humantime::parse_duration(s).map_err(|e| format!("Invalid duration '{}': {}", s, e))
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn parse_workloads<W: Workload>(flag: &str, spec: Option<&str>) -> Result<WorkloadMix<W>, String> {
+    match spec {
+        Some(spec) => spec.parse().map_err(|e| format!("{}: {}", flag, e)),
+        None => Ok(WorkloadMix::all()),
+    }
+}
+
+/// The whole disk named by --raw-device, once the run is confirmed and
+/// nothing on the system uses the disk
+fn raw_device(devices: &[StorageDevice], spec: &str, args: &Args) -> Result<StorageDevice, String> {
+    if !args.i_understand_data_loss {
+        return Err(format!(
+            "--raw-device erases everything on {}; add --i-understand-data-loss to confirm",
+            spec
+        ));
+    }
+    if !args.storage.is_empty()
+        || args.nvme_path.is_some()
+        || args.nvme_size.is_some()
+        || args.cpu_only
+        || args.memory_only
+    {
+        return Err(
+            "--raw-device cannot be combined with --storage, --nvme-path, --nvme-size, -c or -m"
+                .into(),
+        );
+    }
+    let device = detection::storage::find(devices, spec).ok_or_else(|| {
+        let names: Vec<_> = devices.iter().map(|d| d.name.as_str()).collect();
+        format!(
+            "No whole storage device '{}' for --raw-device (found: {})",
+            spec,
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        )
+    })?;
+    let users = detection::storage::users_of(device);
+    if !users.is_empty() {
+        return Err(format!(
+            "Refusing to overwrite {}: {}",
+            device.device_path,
+            users.join(", ")
+        ));
+    }
+    Ok(device.clone())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     // Parse duration
     let duration = parse_duration(&args.duration)?;
+    let load_step_min = parse_duration(&args.step_min)?;
+    let load_step_max = parse_duration(&args.step_max)?;
+    if load_step_min.is_zero() || load_step_min > load_step_max {
+        return Err("--step-min must be non-zero and not longer than --step-max".into());
+    }
+    if !(args.throughput_drop > 0.0 && args.throughput_drop < 1.0) {
+        return Err("--throughput-drop must be between 0 and 1".into());
+    }
+    let hang_timeout = parse_duration(&args.hang_timeout)?;
+    if hang_timeout.is_zero() {
+        return Err("--hang-timeout must be non-zero".into());
+    }
+    let memory_size: MemorySize = match args.memory_size.as_deref() {
+        Some(spec) => spec.parse()?,
+        None => MemorySize::default(),
+    };
+    let nvme_size = match args.nvme_size.as_deref() {
+        Some(spec) => Some(
+            parse_bytes(spec)
+                .ok_or_else(|| format!("Invalid NVMe size '{}' (e.g. 4G, 512M)", spec))?,
+        ),
+        None => None,
+    };
+    if !(1..=nvme::MAX_WORKERS).contains(&args.nvme_workers) {
+        return Err(format!("--nvme-workers must be between 1 and {}", nvme::MAX_WORKERS).into());
+    }
+    if !(1..=nvme::MAX_QUEUE_DEPTH).contains(&args.nvme_queue_depth) {
+        return Err(format!(
+            "--nvme-queue-depth must be between 1 and {}",
+            nvme::MAX_QUEUE_DEPTH
+        )
+        .into());
+    }
+    if args.nvme_read_mix > 100 {
+        return Err("--nvme-read-mix must be a percentage (0-100)".into());
+    }
+    let nvme_slow_io = parse_duration(&args.nvme_slow_io)?;
+    if nvme_slow_io.is_zero() {
+        return Err("--nvme-slow-io must be non-zero".into());
+    }
+    let nvme_block_size = parse_bytes(&args.nvme_block_size)
+        .map(|bytes| bytes as usize)
+        .filter(|&bytes| bytes.is_multiple_of(4096) && bytes <= nvme::MAX_BLOCK_SIZE)
+        .ok_or_else(|| {
+            format!(
+                "Invalid NVMe block size '{}' (a multiple of 4K up to 1M, e.g. 4K, 64K)",
+                args.nvme_block_size
+            )
+        })?;
+    if args.threads == Some(0) {
+        return Err("--threads must be at least 1".into());
+    }
+    if let Some(n) = args
+        .memory_threads
+        .filter(|n| !(1..=memory::MAX_WORKERS).contains(n))
+    {
+        return Err(format!(
+            "--memory-threads must be between 1 and {}, got {}",
+            memory::MAX_WORKERS,
+            n
+        )
+        .into());
+    }
+    let cpu_workloads = parse_workloads("--cpu-workloads", args.cpu_workloads.as_deref())?;
+    let mut memory_workloads =
+        parse_workloads("--memory-workloads", args.memory_workloads.as_deref())?;
+    if args.row_hammer {
+        memory_workloads.include(MemoryWorkload::RowHammer);
+    }
 
     // Setup shutdown signal
     let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-
-    // Handle Ctrl+C
-    tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.ok();
-        r.store(false, Ordering::SeqCst);
-    });
 
     // Collect system information
     let sys_info = system::info::collect_system_info();
     let oc_config = system::config::parse_config();
-    let nvme_info = detection::nvme::detect_nvme();
+    let devices = detection::storage::detect_storage();
+    let raw_device = match args.raw_device.as_deref() {
+        Some(spec) => Some(raw_device(&devices, spec, &args)?),
+        None if args.i_understand_data_loss => {
+            return Err("--i-understand-data-loss only applies to --raw-device".into())
+        }
+        None => None,
+    };
+    let storage = match &raw_device {
+        Some(device) => vec![device.clone()],
+        None => detection::storage::select(&devices, &args.storage)?,
+    };
+    if args.nvme_path.is_some() && storage.len() > 1 {
+        return Err(format!(
+            "--nvme-path needs a single storage target, found {} (pick one with --storage)",
+            storage.len()
+        )
+        .into());
+    }
 
     // Determine UI mode
     let ui_mode = if args.simple {
@@ -116,19 +339,63 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
+    let threads = args.threads.unwrap_or_else(num_cpus);
+    if args.cpu_mode == CpuMode::Vote && threads < cpu::MIN_VOTERS {
+        return Err(format!(
+            "--cpu-mode vote needs at least {} threads to outvote a bad core, got {}",
+            cpu::MIN_VOTERS,
+            threads
+        )
+        .into());
+    }
+
     // Determine what to test
-    // NVMe stress only runs with --extended or --nvme-only flags (not auto-detected)
+    let memory = !args.cpu_only && !args.nvme_only;
+    // Size against RAM and cgroup limits before anything starts; the default
+    // leaves half of available memory for the OS, NVMe test file cache and
+    // safety margin
+    let memory_plan = if memory {
+        let memory_threads = args.memory_threads.unwrap_or(2.min(threads));
+        Some(stress::coverage::plan(
+            memory_size,
+            memory_threads,
+            &system::memlimit::MemoryLimits::read(),
+        )?)
+    } else {
+        None
+    };
+
+    // Storage stress only runs with --extended, --nvme-only or --storage (not auto-detected)
     let stress_config = StressConfig {
         cpu: !args.memory_only && !args.nvme_only,
-        memory: !args.cpu_only && !args.nvme_only,
-        nvme: (args.extended || args.nvme_only)
-            && nvme_info.is_some()
+        memory,
+        nvme: (args.extended || args.nvme_only || !args.storage.is_empty() || raw_device.is_some())
+            && !storage.is_empty()
             && !args.cpu_only
             && !args.memory_only,
         video: args.video,
-        threads: args.threads.unwrap_or_else(num_cpus),
+        threads,
+        memory_plan,
+        cpu_mode: args.cpu_mode,
+        load_step_min,
+        load_step_max,
+        cpu_workloads,
+        memory_workloads,
+        hugepages: args.hugepages,
+        throughput_drop: args.throughput_drop,
+        hang_timeout,
         duration,
-        nvme_path: args.nvme_path,
+        nvme_options: NvmeOptions {
+            path: args.nvme_path,
+            size: nvme_size,
+            workers: args.nvme_workers,
+            queue_depth: args.nvme_queue_depth,
+            engine: args.nvme_engine,
+            read_percent: args.nvme_read_mix,
+            block_size: nvme_block_size,
+            slow_io: nvme_slow_io,
+            raw_device: raw_device.is_some(),
+        },
         video_encoder,
     };
 
@@ -137,53 +404,74 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (event_tx, _event_rx) = mpsc::channel::<String>(100);
 
     // Display system info
-    ui::display_system_info(&sys_info, &oc_config, &nvme_info, ui_mode, args.no_color);
-
-    // Start the UI
-    let ui_handle = if ui_mode == UiMode::Tui {
-        Some(tokio::spawn(ui::tui::run_tui(
-            stats_rx,
-            running.clone(),
-            duration,
-            args.interval,
-        )))
-    } else {
-        None
-    };
-
-    // Run stress test
-    let test_result = stress::run_stress_test(
-        stress_config.clone(),
-        running.clone(),
-        stats_tx,
-        event_tx,
-        nvme_info.clone(),
-    )
-    .await;
-
-    // Wait for UI to finish
-    if let Some(handle) = ui_handle {
-        handle.await.ok();
+    ui::display_system_info(&sys_info, &oc_config, &devices, ui_mode, args.no_color);
+    if let Some(device) = &raw_device {
+        eprintln!(
+            "Warning: --raw-device overwrites all data on {} ({})",
+            device.device_path, device
+        );
     }
 
-    // Collect final errors from dmesg
-    let io_errors = detection::errors::check_io_errors();
-
-    // Generate and display final report
-    let report = stress::generate_report(&test_result, &io_errors, duration);
-
-    if args.json {
-        println!("{}", serde_json::to_string_pretty(&report)?);
-    } else {
-        ui::display_final_report(&report, args.no_color);
-    }
-
-    // Exit with appropriate code
-    if report.passed {
-        Ok(())
-    } else {
-        std::process::exit(1);
-    }
+    // Fork the CPU and memory workers while this is still the only thread;
+    // the async runtime and every other thread start after
+    let workers = stress::start_workers(&stress_config);
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        // Handle Ctrl+C
+        let r = running.clone();
+        tokio::spawn(async move {
+            tokio::signal::ctrl_c().await.ok();
+            r.store(false, Ordering::SeqCst);
+        });
+
+        // Start the UI
+        let ui_handle = if ui_mode == UiMode::Tui {
+            Some(tokio::spawn(ui::tui::run_tui(
+                stats_rx,
+                running.clone(),
+                duration,
+                args.interval,
+            )))
+        } else {
+            None
+        };
+
+        // Run stress test
+        let test_result = stress::run_stress_test(
+            stress_config.clone(),
+            workers,
+            running.clone(),
+            stats_tx,
+            event_tx,
+            storage,
+        )
+        .await;
+
+        // Wait for UI to finish
+        if let Some(handle) = ui_handle {
+            handle.await.ok();
+        }
+
+        // Collect final errors from dmesg
+        let io_errors = detection::errors::check_io_errors();
+
+        // Generate and display final report
+        let report = stress::generate_report(&test_result, &io_errors, duration);
+
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            ui::display_final_report(&report, args.no_color);
+        }
+
+        // Exit with appropriate code
+        if report.passed {
+            Ok(())
+        } else {
+            std::process::exit(1);
+        }
+    })
 }
 
 fn num_cpus() -> usize {
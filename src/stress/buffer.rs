@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::ptr::NonNull;
+
+use crate::system::pagemap::page_size;
+
+/// How memory test buffers are backed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum HugePages {
+    /// Regular pages only
+    Off,
+    /// Ask for transparent huge pages with madvise
+    #[default]
+    Transparent,
+    /// Reserved hugetlbfs pages (vm.nr_hugepages), falling back to regular pages
+    Explicit,
+}
+
+/// Size and backing requested for each memory worker's buffer
+#[derive(Debug, Clone, Copy)]
+pub struct BufferSpec {
+    pub bytes: usize,
+    pub hugepages: HugePages,
+}
+
+/// How much of a buffer the kernel actually locked and backed with huge pages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Residency {
+    pub locked_bytes: u64,
+    /// Transparent huge pages
+    pub thp_bytes: u64,
+    /// Reserved hugetlbfs pages
+    pub hugetlb_bytes: u64,
+}
+
+impl Residency {
+    /// Bytes backed by the kind of huge page that `mode` asked for
+    pub fn huge_bytes(&self, mode: HugePages) -> u64 {
+        match mode {
+            HugePages::Off => 0,
+            HugePages::Transparent => self.thp_bytes,
+            HugePages::Explicit => self.hugetlb_bytes,
+        }
+    }
+}
+
+/// Anonymous mapping used as a memory test buffer
+/// Touched page by page and locked with mlock, so the patterns run on
+/// physical DRAM rather than on swap, zram or never-faulted zero pages.
+pub struct TestBuffer {
+    ptr: NonNull<u64>,
+    len: usize,
+}
+
+impl TestBuffer {
+    /// Map, pre-touch and lock `spec.bytes` (rounded up to the page size in use)
+    /// Failing to lock is not an error; `residency` shows what the kernel granted.
+    pub fn new(spec: BufferSpec) -> io::Result<Self> {
+        let explicit = (spec.hugepages == HugePages::Explicit)
+            .then(huge_page_size)
+            .flatten()
+            .and_then(|huge| map(round_up(spec.bytes, huge), libc::MAP_HUGETLB).ok());
+        let (ptr, len) = match explicit {
+            Some(mapping) => mapping,
+            None => {
+                let len = round_up(spec.bytes, page_size());
+                let ptr = map(len, 0)?.0;
+                if spec.hugepages != HugePages::Off {
+                    // Advisory: the kernel may ignore it if THP is disabled
+                    unsafe {
+                        libc::madvise(ptr.as_ptr() as *mut libc::c_void, len, libc::MADV_HUGEPAGE)
+                    };
+                }
+                (ptr, len)
+            }
+        };
+        let buffer = Self { ptr, len };
+
+        // Fault in every page before locking so huge pages are allocated on first touch
+        let step = page_size() / 8;
+        let words = unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), len / 8) };
+        for word in words.iter_mut().step_by(step) {
+            unsafe { std::ptr::write_volatile(word, 0) };
+        }
+
+        raise_memlock_limit();
+        unsafe { libc::mlock(ptr.as_ptr() as *const libc::c_void, len) };
+        Ok(buffer)
+    }
+
+    pub fn words(&mut self) -> &mut [u64] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len / 8) }
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Locked and huge-page-backed bytes, as reported in /proc/self/smaps
+    pub fn residency(&self) -> Residency {
+        std::fs::read_to_string("/proc/self/smaps")
+            .ok()
+            .and_then(|smaps| parse_smaps(&smaps, self.ptr.as_ptr() as usize))
+            .map(|r| Residency {
+                locked_bytes: r.locked_bytes.min(self.len as u64),
+                thp_bytes: r.thp_bytes.min(self.len as u64),
+                hugetlb_bytes: r.hugetlb_bytes.min(self.len as u64),
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for TestBuffer {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len) };
+    }
+}
+
+/// Map `len` bytes of private anonymous memory with extra `flags`
+fn map(len: usize, flags: libc::c_int) -> io::Result<(NonNull<u64>, usize)> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    NonNull::new(ptr as *mut u64)
+        .map(|ptr| (ptr, len))
+        .ok_or_else(|| io::Error::other("mmap returned null"))
+}
+
+/// Lift RLIMIT_MEMLOCK so mlock can cover the whole buffer (only works as root)
+fn raise_memlock_limit() {
+    let unlimited = libc::rlimit {
+        rlim_cur: libc::RLIM_INFINITY,
+        rlim_max: libc::RLIM_INFINITY,
+    };
+    unsafe { libc::setrlimit(libc::RLIMIT_MEMLOCK, &unlimited) };
+}
+
+fn round_up(bytes: usize, unit: usize) -> usize {
+    bytes.div_ceil(unit) * unit
+}
+
+/// Default hugetlbfs page size from /proc/meminfo
+fn huge_page_size() -> Option<usize> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("Hugepagesize:"))
+        .and_then(parse_kb)
+        .map(|bytes| bytes as usize)
+}
+
+/// Parse a "  1234 kB" smaps/meminfo value into bytes
+fn parse_kb(value: &str) -> Option<u64> {
+    let kb: u64 = value.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Address range of an smaps entry header ("start-end perms offset ...")
+fn parse_range(line: &str) -> Option<(usize, usize)> {
+    let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+    Some((
+        usize::from_str_radix(start, 16).ok()?,
+        usize::from_str_radix(end, 16).ok()?,
+    ))
+}
+
+/// Residency of the smaps entry whose address range contains `address`
+fn parse_smaps(smaps: &str, address: usize) -> Option<Residency> {
+    let mut residency: Option<Residency> = None;
+    for line in smaps.lines() {
+        if let Some((start, end)) = parse_range(line) {
+            if residency.is_some() {
+                break;
+            }
+            if (start..end).contains(&address) {
+                residency = Some(Residency::default());
+            }
+            continue;
+        }
+
+        let (Some(r), Some((name, value))) = (residency.as_mut(), line.split_once(':')) else {
+            continue;
+        };
+        match name {
+            "Locked" => r.locked_bytes = parse_kb(value).unwrap_or(0),
+            "AnonHugePages" => r.thp_bytes = parse_kb(value).unwrap_or(0),
+            "Private_Hugetlb" | "Shared_Hugetlb" => r.hugetlb_bytes += parse_kb(value).unwrap_or(0),
+            _ => {}
+        }
+    }
+    residency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMAPS: &str = "\
+7f0000000000-7f0000200000 rw-p 00000000 00:00 0
+Size:               2048 kB
+Locked:                0 kB
+AnonHugePages:         0 kB
+7f1000000000-7f1004000000 rw-p 00000000 00:00 0
+Size:              65536 kB
+AnonHugePages:     61440 kB
+Shared_Hugetlb:        0 kB
+Private_Hugetlb:       0 kB
+Locked:            65536 kB
+VmFlags: rd wr mr mw me lo ac hg
+7f2000000000-7f2000001000 r--p 00000000 08:01 1234 /usr/lib/libc.so.6
+Locked:                4 kB
+";
+
+    #[test]
+    fn test_parse_smaps_finds_containing_entry() {
+        let residency = parse_smaps(SMAPS, 0x7f10_0000_1000).unwrap();
+        assert_eq!(residency.locked_bytes, 64 * 1024 * 1024);
+        assert_eq!(residency.thp_bytes, 60 * 1024 * 1024);
+        assert_eq!(residency.huge_bytes(HugePages::Explicit), 0);
+        assert_eq!(parse_smaps(SMAPS, 0x7f30_0000_0000), None);
+    }
+
+    #[test]
+    fn test_buffer_is_usable_and_reported() {
+        let mut buffer = TestBuffer::new(BufferSpec {
+            bytes: 1024 * 1024 + 1,
+            hugepages: HugePages::Off,
+        })
+        .unwrap();
+        assert!(buffer.size() > 1024 * 1024);
+        assert_eq!(buffer.size() % page_size(), 0);
+
+        let words = buffer.words();
+        words.fill(0xA5A5_A5A5_A5A5_A5A5);
+        assert!(words.iter().all(|&w| w == 0xA5A5_A5A5_A5A5_A5A5));
+
+        // Locking needs privileges or a generous RLIMIT_MEMLOCK; either way it never overstates
+        assert!(buffer.residency().locked_bytes <= buffer.size() as u64);
+    }
+
+    #[test]
+    fn test_explicit_hugepages_fall_back() {
+        // Works whether or not the machine has hugetlbfs pages reserved
+        let mut buffer = TestBuffer::new(BufferSpec {
+            bytes: 4 * 1024 * 1024,
+            hugepages: HugePages::Explicit,
+        })
+        .unwrap();
+        assert!(buffer.words().len() * 8 >= 4 * 1024 * 1024);
+    }
+}
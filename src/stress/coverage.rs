@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::system::memlimit::MemoryLimits;
+
+const MB: u64 = 1024 * 1024;
+
+/// Memory always left for the OS, page cache and this tool
+const MIN_RESERVE: u64 = 256 * MB;
+
+/// Share of usable memory kept back, when that is more than `MIN_RESERVE`
+const RESERVE_FRACTION: f64 = 0.1;
+
+/// Smallest buffer a memory worker is given; fewer workers are used
+/// rather than smaller buffers
+pub const MIN_WORKER_BYTES: u64 = 64 * MB;
+
+/// How much memory the memory workers should test in total
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemorySize {
+    Bytes(u64),
+    /// Percentage of MemAvailable
+    PercentOfAvailable(f64),
+    /// Percentage of MemTotal
+    PercentOfTotal(f64),
+}
+
+impl Default for MemorySize {
+    fn default() -> Self {
+        MemorySize::PercentOfAvailable(50.0)
+    }
+}
+
+impl MemorySize {
+    pub fn bytes(&self, limits: &MemoryLimits) -> u64 {
+        match *self {
+            MemorySize::Bytes(bytes) => bytes,
+            MemorySize::PercentOfAvailable(pct) => {
+                (limits.available_bytes as f64 * pct / 100.0) as u64
+            }
+            MemorySize::PercentOfTotal(pct) => (limits.total_bytes as f64 * pct / 100.0) as u64,
+        }
+    }
+}
+
+impl FromStr for MemorySize {
+    type Err = String;
+
+    /// Accepts "2G", "512M", "50%" (of available), "50%available" or "80%total"
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid memory size '{}' (e.g. 2G, 512M, 50%, 80%total)",
+                spec
+            )
+        };
+        let spec = spec.trim().to_ascii_lowercase();
+
+        if let Some((pct, of)) = spec.split_once('%') {
+            let pct: f64 = pct
+                .trim()
+                .parse()
+                .ok()
+                .filter(|p| *p > 0.0 && *p <= 100.0)
+                .ok_or_else(invalid)?;
+            return match of.trim() {
+                "" | "avail" | "available" => Ok(MemorySize::PercentOfAvailable(pct)),
+                "total" => Ok(MemorySize::PercentOfTotal(pct)),
+                _ => Err(invalid()),
+            };
+        }
+
+        parse_bytes(&spec)
+            .map(MemorySize::Bytes)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Parse a non-zero size with a binary unit, e.g. "2G", "512MiB", "1.5g"
+pub fn parse_bytes(spec: &str) -> Option<u64> {
+    let spec = spec.trim().to_ascii_lowercase();
+    let number_end = spec.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let value: f64 = spec[..number_end].parse().ok()?;
+    let unit = match spec[number_end..]
+        .trim()
+        .trim_end_matches("ib")
+        .trim_end_matches('b')
+    {
+        "k" => 1024,
+        "m" => MB,
+        "g" => 1024 * MB,
+        "t" => 1024 * 1024 * MB,
+        _ => return None,
+    };
+    Some((value * unit as f64) as u64).filter(|&bytes| bytes > 0)
+}
+
+impl fmt::Display for MemorySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemorySize::Bytes(bytes) => write!(f, "{} MB", bytes / MB),
+            MemorySize::PercentOfAvailable(pct) => write!(f, "{}% of available", pct),
+            MemorySize::PercentOfTotal(pct) => write!(f, "{}% of total", pct),
+        }
+    }
+}
+
+/// How much RAM the memory test was asked to cover and actually covered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryCoverage {
+    /// The size as given on the command line
+    pub requested: String,
+    pub requested_bytes: u64,
+    /// Total after the safety limit was applied; each of the `threads`
+    /// workers gets an equal share
+    pub planned_bytes: u64,
+    /// Actually allocated by the memory workers
+    pub tested_bytes: u64,
+    pub threads: usize,
+    pub total_bytes: u64,
+    /// `tested_bytes` as a share of MemTotal
+    pub percent_of_total: f64,
+    /// Why the request was reduced, if it was
+    pub limited_by: Option<String>,
+}
+
+impl MemoryCoverage {
+    /// Fill in what the workers actually allocated
+    pub fn with_tested(self, tested_bytes: u64) -> Self {
+        let percent_of_total = if self.total_bytes > 0 {
+            tested_bytes as f64 / self.total_bytes as f64 * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            tested_bytes,
+            percent_of_total,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for MemoryCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} MB of {} MB ({:.1}%) in {} thread{}",
+            self.tested_bytes / MB,
+            self.total_bytes / MB,
+            self.percent_of_total,
+            self.threads,
+            if self.threads == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Size the memory test so it fits in RAM and under any cgroup limit
+/// Swap is never counted as room: buffers pushed out to swap (or zram) would
+/// test the swap device instead of DRAM, and an over-committed cgroup gets
+/// the run OOM-killed.
+pub fn plan(
+    size: MemorySize,
+    threads: usize,
+    limits: &MemoryLimits,
+) -> Result<MemoryCoverage, String> {
+    let requested_bytes = size.bytes(limits);
+
+    let (mut usable, mut reason) = (limits.available_bytes, "available RAM");
+    if let Some(headroom) = limits.cgroup_headroom.filter(|&h| h < usable) {
+        usable = headroom;
+        reason = "cgroup memory limit";
+    }
+    let reserve = MIN_RESERVE.max((usable as f64 * RESERVE_FRACTION) as u64);
+    let safe = usable.saturating_sub(reserve);
+
+    let limited_by = (requested_bytes > safe).then(|| {
+        let swap = if limits.swap_bytes > 0 {
+            ", swap not counted"
+        } else {
+            ""
+        };
+        format!("{} (safe limit {} MB{})", reason, safe / MB, swap)
+    });
+
+    let planned_bytes = requested_bytes.min(safe);
+    if planned_bytes < MIN_WORKER_BYTES {
+        return Err(match limited_by {
+            Some(limit) => format!(
+                "Not enough memory to test: limited by {}, need at least {} MB",
+                limit,
+                MIN_WORKER_BYTES / MB
+            ),
+            None => format!(
+                "Memory size {} is below the {} MB minimum",
+                size,
+                MIN_WORKER_BYTES / MB
+            ),
+        });
+    }
+
+    Ok(MemoryCoverage {
+        requested: size.to_string(),
+        requested_bytes,
+        planned_bytes,
+        tested_bytes: 0,
+        threads: threads.min((planned_bytes / MIN_WORKER_BYTES) as usize),
+        total_bytes: limits.total_bytes,
+        percent_of_total: 0.0,
+        limited_by,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(cgroup_headroom: Option<u64>) -> MemoryLimits {
+        MemoryLimits {
+            total_bytes: 8192 * MB,
+            available_bytes: 6000 * MB,
+            swap_bytes: 512 * MB,
+            cgroup_headroom,
+        }
+    }
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!("2G".parse(), Ok(MemorySize::Bytes(2048 * MB)));
+        assert_eq!("512MiB".parse(), Ok(MemorySize::Bytes(512 * MB)));
+        assert_eq!("1.5g".parse(), Ok(MemorySize::Bytes(1536 * MB)));
+        assert_eq!("60%".parse(), Ok(MemorySize::PercentOfAvailable(60.0)));
+        assert_eq!("80%total".parse(), Ok(MemorySize::PercentOfTotal(80.0)));
+        assert_eq!(
+            "25% available".parse(),
+            Ok(MemorySize::PercentOfAvailable(25.0))
+        );
+
+        for bad in ["", "2", "0M", "150%", "0%", "10%free", "2X"] {
+            assert!(bad.parse::<MemorySize>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_plan_within_limits() {
+        let coverage = plan(MemorySize::Bytes(2048 * MB), 2, &limits(None)).unwrap();
+        assert_eq!(coverage.planned_bytes, 2048 * MB);
+        assert_eq!(coverage.limited_by, None);
+
+        let coverage = coverage.with_tested(2048 * MB);
+        assert_eq!(coverage.percent_of_total, 25.0);
+        assert_eq!(
+            coverage.to_string(),
+            "2048 MB of 8192 MB (25.0%) in 2 threads"
+        );
+    }
+
+    #[test]
+    fn test_plan_clamps_to_available_ram() {
+        let coverage = plan(MemorySize::PercentOfTotal(95.0), 2, &limits(None)).unwrap();
+        assert_eq!(coverage.planned_bytes, 5400 * MB);
+        assert_eq!(
+            coverage.limited_by.as_deref(),
+            Some("available RAM (safe limit 5400 MB, swap not counted)")
+        );
+    }
+
+    #[test]
+    fn test_plan_clamps_to_cgroup() {
+        let coverage = plan(
+            MemorySize::PercentOfAvailable(50.0),
+            1,
+            &limits(Some(1024 * MB)),
+        )
+        .unwrap();
+        assert_eq!(coverage.planned_bytes, 768 * MB);
+        assert!(coverage
+            .limited_by
+            .unwrap()
+            .starts_with("cgroup memory limit"));
+    }
+
+    #[test]
+    fn test_plan_keeps_worker_minimum() {
+        // 100 MB cannot feed 4 workers 64 MB each, so only one runs
+        let coverage = plan(MemorySize::Bytes(100 * MB), 4, &limits(None)).unwrap();
+        assert_eq!(coverage.threads, 1);
+        assert_eq!(coverage.planned_bytes, 100 * MB);
+
+        let err = plan(MemorySize::Bytes(32 * MB), 2, &limits(None)).unwrap_err();
+        assert_eq!(err, "Memory size 32 MB is below the 64 MB minimum");
+
+        let err = plan(MemorySize::Bytes(2048 * MB), 2, &limits(Some(300 * MB))).unwrap_err();
+        assert!(err.starts_with("Not enough memory to test: limited by cgroup"));
+    }
+}
@@ -1,144 +1,984 @@
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
 use aes::Aes256;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::shared::{ProcessShared, RecordLog, Shared, SharedSlice};
+use super::simd;
+use super::supervisor::Heartbeat;
+use super::throughput::WorkerThroughput;
+use super::workload::{Workload, WorkloadMix, WorkloadWeight};
+use crate::system::affinity;
+
+/// DFT transform length
+const DFT_SIZE: usize = 4096;
+
+/// Allowed deviation of the inverse DFT from the original signal
+const DFT_TOLERANCE: f64 = 1e-6;
+
+/// Matrix dimension for the multiplication workload
+const MATRIX_SIZE: usize = 128;
+
+/// Upper bound for the prime sieve
+const PRIME_LIMIT: usize = 100_000;
+
+/// Known value: there are 9592 primes below 100000
+const PRIME_COUNT: usize = 9592;
+
+/// AES-256 key from NIST SP 800-38A, F.1.5
+const AES_KEY: [u8; 32] = [
+    0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d, 0x77, 0x81,
+    0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3, 0x09, 0x14, 0xdf, 0xf4,
+];
+
+/// Known-answer vectors (plaintext, ciphertext) for ECB-AES256 from NIST SP 800-38A, F.1.5
+const AES_KAT: [([u8; 16], [u8; 16]); 4] = [
+    (
+        [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93,
+            0x17, 0x2a,
+        ],
+        [
+            0xf3, 0xee, 0xd1, 0xbd, 0xb5, 0xd2, 0xa0, 0x3c, 0x06, 0x4b, 0x5a, 0x7e, 0x3d, 0xb1,
+            0x81, 0xf8,
+        ],
+    ),
+    (
+        [
+            0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf,
+            0x8e, 0x51,
+        ],
+        [
+            0x59, 0x1c, 0xcb, 0x10, 0xd4, 0x10, 0xed, 0x26, 0xdc, 0x5b, 0xa7, 0x4a, 0x31, 0x36,
+            0x28, 0x70,
+        ],
+    ),
+    (
+        [
+            0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a,
+            0x52, 0xef,
+        ],
+        [
+            0xb6, 0xed, 0x21, 0xb9, 0x9c, 0xa6, 0xf4, 0xf9, 0xf1, 0x53, 0xe7, 0xb1, 0xbe, 0xaf,
+            0xed, 0x1d,
+        ],
+    ),
+    (
+        [
+            0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c,
+            0x37, 0x10,
+        ],
+        [
+            0x23, 0x30, 0x4b, 0x7a, 0x39, 0xf9, 0xf3, 0xff, 0x06, 0x7d, 0x8d, 0x8f, 0x9e, 0x24,
+            0xec, 0xc7,
+        ],
+    ),
+];
+
+/// Number of chained encryptions in the AES round-trip
+const AES_ROUNDS: usize = 10_000;
+
+/// Matrix dimension for the LINPACK-style LU solve
+const LINPACK_SIZE: usize = 512;
+
+/// Largest accepted HPL normalized residual
+const LINPACK_RESIDUAL_LIMIT: f64 = 16.0;
+
+/// Points evaluated by the NEON polynomial kernels
+const SIMD_POINTS: usize = 4096;
+
+/// Polynomial degree (FMAs per point) for the NEON kernels
+const SIMD_DEGREE: usize = 256;
+
+/// CPU workloads rotated by each stress thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuWorkload {
+    Dft,
+    Matrix,
+    Prime,
+    Aes,
+    Neon,
+    Linpack,
+}
+
+impl CpuWorkload {
+    /// All workloads, in rotation order (must match declaration order)
+    pub const ALL: [CpuWorkload; 6] = [
+        CpuWorkload::Dft,
+        CpuWorkload::Matrix,
+        CpuWorkload::Prime,
+        CpuWorkload::Aes,
+        CpuWorkload::Neon,
+        CpuWorkload::Linpack,
+    ];
+
+    pub const COUNT: usize = Self::ALL.len();
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CpuWorkload::Dft => "dft",
+            CpuWorkload::Matrix => "matrix",
+            CpuWorkload::Prime => "prime",
+            CpuWorkload::Aes => "aes",
+            CpuWorkload::Neon => "neon",
+            CpuWorkload::Linpack => "linpack",
+        }
+    }
+}
+
+impl Workload for CpuWorkload {
+    const VARIANTS: &'static [Self] = &Self::ALL;
+
+    fn name(&self) -> &'static str {
+        CpuWorkload::name(self)
+    }
+}
+
+/// How CPU workers verify their results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CpuMode {
+    /// Each worker rotates through the workloads and checks golden results
+    #[default]
+    Rotate,
+    /// All workers run the identical job each round and vote on the result
+    Vote,
+    /// All workers switch between busy and idle together (VRM load transients)
+    LoadStep,
+}
+
+impl CpuMode {
+    /// Workloads this mode actually runs when `selected` were asked for;
+    /// load-step only ever runs matrix rows
+    pub fn workloads(self, selected: &WorkloadMix<CpuWorkload>) -> WorkloadMix<CpuWorkload> {
+        match self {
+            CpuMode::Rotate | CpuMode::Vote => selected.clone(),
+            CpuMode::LoadStep => WorkloadMix(vec![WorkloadWeight {
+                workload: CpuWorkload::Matrix,
+                weight: 1,
+            }]),
+        }
+    }
+}
+
+/// First element of a workload result that disagreed with its reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuMismatch {
+    pub workload: CpuWorkload,
+    /// Core the failing worker was running on
+    pub core: Option<usize>,
+    /// Element index (or voting round in vote mode)
+    pub index: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for CpuMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(core) = self.core {
+            write!(f, "core {} ", core)?;
+        }
+        write!(
+            f,
+            "{}[{}]: expected {}, got {}",
+            self.workload.name(),
+            self.index,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+/// Results shared by all CPU workers and read by the monitoring loop
+/// Backed by shared memory so forked worker processes report into it.
+pub struct CpuShared {
+    pub errors: Shared<AtomicU64>,
+    pub first_error: RecordLog<CpuMismatch>,
+    pub linpack: Shared<LinpackStats>,
+}
+
+impl Default for CpuShared {
+    fn default() -> Self {
+        Self {
+            errors: Shared::default(),
+            first_error: RecordLog::new(1),
+            linpack: Shared::default(),
+        }
+    }
+}
+
+/// LINPACK throughput of the most recent and the extreme iterations
+pub struct LinpackStats {
+    iterations: AtomicU64,
+    last_gflops: AtomicU64,
+    min_gflops: AtomicU64,
+    max_gflops: AtomicU64,
+}
+
+impl Default for LinpackStats {
+    fn default() -> Self {
+        Self {
+            iterations: AtomicU64::new(0),
+            last_gflops: AtomicU64::new(0),
+            min_gflops: AtomicU64::new(f64::INFINITY.to_bits()),
+            max_gflops: AtomicU64::new(0),
+        }
+    }
+}
+
+unsafe impl ProcessShared for LinpackStats {}
+
+impl LinpackStats {
+    fn record(&self, gflops: f64) {
+        // f64 values stored as bits; non-negative floats order like their bits
+        let bits = gflops.to_bits();
+        self.last_gflops.store(bits, Ordering::Relaxed);
+        self.min_gflops.fetch_min(bits, Ordering::Relaxed);
+        self.max_gflops.fetch_max(bits, Ordering::Relaxed);
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take a serializable snapshot (None until the first iteration completes)
+    pub fn snapshot(&self) -> Option<LinpackSummary> {
+        let iterations = self.iterations.load(Ordering::Relaxed);
+        if iterations == 0 {
+            return None;
+        }
+
+        Some(LinpackSummary {
+            iterations,
+            last_gflops: f64::from_bits(self.last_gflops.load(Ordering::Relaxed)),
+            min_gflops: f64::from_bits(self.min_gflops.load(Ordering::Relaxed)),
+            max_gflops: f64::from_bits(self.max_gflops.load(Ordering::Relaxed)),
+        })
+    }
+}
+
+/// LINPACK throughput summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinpackSummary {
+    pub iterations: u64,
+    pub last_gflops: f64,
+    pub min_gflops: f64,
+    pub max_gflops: f64,
+}
+
+/// Live error counters for one CPU core, one slot per workload
+pub struct CoreErrorCounters {
+    core: usize,
+    counts: Shared<CoreCounts>,
+}
+
+#[derive(Default)]
+struct CoreCounts {
+    pinned: AtomicBool,
+    errors: [AtomicU64; CpuWorkload::COUNT],
+}
+
+unsafe impl ProcessShared for CoreCounts {}
+
+impl CoreErrorCounters {
+    pub fn new(core: usize) -> Self {
+        Self {
+            core,
+            counts: Shared::default(),
+        }
+    }
+
+    /// Core the counters belong to
+    pub fn core(&self) -> usize {
+        self.core
+    }
+
+    /// Pin the calling thread to this core and remember whether it worked
+    fn pin(&self) {
+        let pinned = affinity::pin_current_thread(self.core);
+        self.counts.pinned.store(pinned, Ordering::Relaxed);
+    }
+
+    fn record(&self, workload: CpuWorkload) {
+        self.counts.errors[workload as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take a serializable snapshot of the counters
+    pub fn snapshot(&self) -> CoreErrors {
+        let workload_errors: Vec<WorkloadErrors> = CpuWorkload::ALL
+            .iter()
+            .map(|&workload| WorkloadErrors {
+                workload,
+                errors: self.counts.errors[workload as usize].load(Ordering::Relaxed),
+            })
+            .collect();
+
+        CoreErrors {
+            core: self.core,
+            pinned: self.counts.pinned.load(Ordering::Relaxed),
+            errors: workload_errors.iter().map(|w| w.errors).sum(),
+            workload_errors,
+        }
+    }
+}
+
+/// Error count for one workload on one core
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadErrors {
+    pub workload: CpuWorkload,
+    pub errors: u64,
+}
+
+/// Errors attributed to one CPU core
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreErrors {
+    pub core: usize,
+    /// Whether the worker could be pinned to this core
+    pub pinned: bool,
+    pub errors: u64,
+    pub workload_errors: Vec<WorkloadErrors>,
+}
+
+impl CoreErrors {
+    /// Summarize the errors, e.g. "3 matrix, 1 aes errors"
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = self
+            .workload_errors
+            .iter()
+            .filter(|w| w.errors > 0)
+            .map(|w| format!("{} {}", w.errors, w.workload.name()))
+            .collect();
+
+        if parts.is_empty() {
+            "no errors".to_string()
+        } else {
+            format!("{} errors", parts.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for CoreErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "core {}: {}", self.core, self.summary())
+    }
+}
+
+/// Golden results, computed once before the stress threads start
+struct Reference {
+    dft_real: Vec<f64>,
+    dft_imag: Vec<f64>,
+    matrix: Vec<f64>,
+    primes: Vec<bool>,
+}
+
+static REFERENCE: OnceLock<Reference> = OnceLock::new();
+
+/// Compute the golden results (call once at startup, before spawning workers)
+pub fn init_reference() {
+    reference();
+}
+
+fn reference() -> &'static Reference {
+    REFERENCE.get_or_init(|| {
+        let (dft_real, dft_imag) = dft(&dft_input(0), &vec![0.0; DFT_SIZE], false);
+        Reference {
+            dft_real,
+            dft_imag,
+            matrix: matrix_multiply(0),
+            primes: prime_sieve(PRIME_LIMIT),
+        }
+    })
+}
 
 /// Run CPU stress test with multiple workloads
-pub fn run_cpu_stress(running: Arc<AtomicBool>, errors: Arc<AtomicU64>) {
+/// The calling thread is pinned to the core owning `core_errors`
+/// `rotation` lists the workloads to cycle through, repeated by weight
+pub fn run_cpu_stress(
+    rotation: &[CpuWorkload],
+    running: &AtomicBool,
+    shared: &CpuShared,
+    core_errors: &CoreErrorCounters,
+    throughput: &WorkerThroughput,
+    heartbeat: &Heartbeat,
+) {
+    core_errors.pin();
+
     let mut iteration: u64 = 0;
 
     while running.load(Ordering::Relaxed) {
-        // Rotate between different stress methods
-        match iteration % 4 {
-            0 => {
-                if !run_dft_stress() {
-                    errors.fetch_add(1, Ordering::Relaxed);
+        heartbeat.beat();
+
+        // Rotate between the selected stress methods
+        let workload = rotation[(iteration % rotation.len() as u64) as usize];
+
+        let started = Instant::now();
+        match run_workload(workload, &shared.linpack) {
+            Ok(()) => throughput.record(workload, started.elapsed()),
+            Err(mismatch) => record_mismatch(shared, core_errors, mismatch),
+        }
+
+        iteration = iteration.wrapping_add(1);
+    }
+}
+
+/// Count a failed check against the total, the core and the first-error slot
+fn record_mismatch(shared: &CpuShared, core_errors: &CoreErrorCounters, mut mismatch: CpuMismatch) {
+    shared.errors.fetch_add(1, Ordering::Relaxed);
+    core_errors.record(mismatch.workload);
+    mismatch.core = Some(core_errors.core);
+    shared.first_error.push(&mismatch);
+}
+
+/// Number of discrete periods in a load-step sweep
+const LOAD_STEP_STEPS: u32 = 16;
+
+/// Longest single sleep while idle, so a stop request is noticed quickly
+const LOAD_STEP_MAX_SLEEP: Duration = Duration::from_millis(50);
+
+/// Square-wave schedule shared by all load-step workers
+/// The period sweeps logarithmically from `min_period` to `max_period` over
+/// the run in discrete steps. Every worker derives its busy/idle phase from
+/// the same start instant, so all cores change load at the same moment.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadStepSchedule {
+    start: Instant,
+    duration: Duration,
+    min_period: Duration,
+    max_period: Duration,
+}
+
+impl LoadStepSchedule {
+    pub fn new(
+        start: Instant,
+        duration: Duration,
+        min_period: Duration,
+        max_period: Duration,
+    ) -> Self {
+        Self {
+            start,
+            duration,
+            min_period,
+            max_period,
+        }
+    }
+
+    /// Start offset and period of the sweep step active at `elapsed`
+    fn step_at(&self, elapsed: Duration) -> (Duration, Duration) {
+        let step_len = self.duration / LOAD_STEP_STEPS;
+        let step = if step_len.is_zero() {
+            0
+        } else {
+            ((elapsed.as_nanos() / step_len.as_nanos()) as u32).min(LOAD_STEP_STEPS - 1)
+        };
+
+        let min = self.min_period.as_secs_f64();
+        let max = self.max_period.as_secs_f64();
+        let fraction = step as f64 / (LOAD_STEP_STEPS - 1) as f64;
+        let period = Duration::from_secs_f64(min * (max / min).powf(fraction));
+
+        (step_len * step, period)
+    }
+
+    /// Square-wave frequency active at `elapsed`
+    pub fn frequency_hz(&self, elapsed: Duration) -> f32 {
+        let (_, period) = self.step_at(elapsed);
+        (1.0 / period.as_secs_f64()) as f32
+    }
+
+    /// Whether `now` falls in a busy half-period, and when that half ends
+    fn phase(&self, now: Instant) -> (bool, Instant) {
+        let elapsed = now.saturating_duration_since(self.start);
+        let (step_start, period) = self.step_at(elapsed);
+
+        let period_ns = period.as_nanos().max(2);
+        let since_step = elapsed.saturating_sub(step_start).as_nanos();
+        let cycle_start = since_step - since_step % period_ns;
+        let half = period_ns / 2;
+        let busy = since_step - cycle_start < half;
+        let edge = if busy {
+            cycle_start + half
+        } else {
+            cycle_start + period_ns
+        };
+
+        (
+            busy,
+            self.start + step_start + Duration::from_nanos(edge as u64),
+        )
+    }
+}
+
+/// Run CPU stress in load-step mode
+/// During busy half-periods the worker verifies rows of the golden matrix
+/// product, so results stay checked right through each load transient.
+pub fn run_load_step_stress(
+    schedule: LoadStepSchedule,
+    running: &AtomicBool,
+    shared: &CpuShared,
+    core_errors: &CoreErrorCounters,
+    heartbeat: &Heartbeat,
+) {
+    core_errors.pin();
+
+    let (a, b) = matrix_inputs(0);
+    let golden = &reference().matrix;
+    let mut row = vec![0.0f64; MATRIX_SIZE];
+    let mut i = 0;
+
+    while running.load(Ordering::Relaxed) {
+        heartbeat.beat();
+        let (busy, edge) = schedule.phase(Instant::now());
+
+        if busy {
+            while Instant::now() < edge {
+                heartbeat.beat();
+                matrix_row(&a, &b, i, &mut row);
+                let expected = &golden[i * MATRIX_SIZE..(i + 1) * MATRIX_SIZE];
+                if let Err(mismatch) =
+                    compare_bits(CpuWorkload::Matrix, i * MATRIX_SIZE, &row, expected)
+                {
+                    record_mismatch(shared, core_errors, mismatch);
                 }
+                i = (i + 1) % MATRIX_SIZE;
             }
-            1 => {
-                if !run_matrix_stress() {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
+        } else {
+            let idle = edge.saturating_duration_since(Instant::now());
+            std::thread::sleep(idle.min(LOAD_STEP_MAX_SLEEP));
+        }
+    }
+}
+
+/// Barrier that spins (keeping the core loaded) and gives up when the test stops
+pub struct SpinBarrier {
+    parties: usize,
+    arrived: AtomicUsize,
+    generation: AtomicUsize,
+}
+
+impl SpinBarrier {
+    pub fn new(parties: usize) -> Self {
+        Self {
+            parties,
+            arrived: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait for all parties; returns false if `running` was cleared meanwhile
+    /// The waiter keeps beating `heartbeat`, so only a stalled party looks hung.
+    pub fn wait(&self, running: &AtomicBool, heartbeat: &Heartbeat) -> bool {
+        let generation = self.generation.load(Ordering::Acquire);
+
+        if self.arrived.fetch_add(1, Ordering::AcqRel) + 1 == self.parties {
+            self.arrived.store(0, Ordering::Relaxed);
+            self.generation.fetch_add(1, Ordering::Release);
+            return true;
+        }
+
+        while self.generation.load(Ordering::Acquire) == generation {
+            if !running.load(Ordering::Relaxed) {
+                return false;
             }
-            2 => {
-                if !run_prime_stress() {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
+            heartbeat.beat();
+            std::thread::yield_now();
+        }
+
+        true
+    }
+}
+
+unsafe impl ProcessShared for SpinBarrier {}
+
+/// Shared state for cross-core result voting
+pub struct VoteBoard {
+    barrier: Shared<SpinBarrier>,
+    /// Latest result digest of each worker
+    digests: SharedSlice<AtomicU64>,
+    /// Fingerprint of the latest tally by each of the first `TALLIERS` workers
+    tallies: SharedSlice<AtomicU64>,
+    /// Error counters of each worker's core (workers may share a core)
+    cores: Vec<Arc<CoreErrorCounters>>,
+}
+
+impl VoteBoard {
+    /// Create a board for one worker per entry in `cores`
+    pub fn new(cores: Vec<Arc<CoreErrorCounters>>) -> Self {
+        Self {
+            barrier: Shared::new(SpinBarrier::new(cores.len())),
+            digests: SharedSlice::from_fn(cores.len(), |_| AtomicU64::new(0)),
+            tallies: SharedSlice::from_fn(TALLIERS, |_| AtomicU64::new(0)),
+            cores,
+        }
+    }
+
+    /// Clear a half-finished round; only valid while no worker is running
+    pub fn reset(&self) {
+        self.barrier.arrived.store(0, Ordering::Relaxed);
+        for digest in self.digests.iter().chain(self.tallies.iter()) {
+            digest.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Fewest workers that can outvote a single faulty core
+pub const MIN_VOTERS: usize = 3;
+
+/// Workers that tally every round, each on its own core
+const TALLIERS: usize = 2;
+
+/// Run CPU stress in voting mode
+/// Every worker computes the same seeded job each round; workers 0 and 1
+/// both compare the digests, and worker 0 charges an error to every core
+/// that disagrees with the majority once the two tallies match. Unlike
+/// self-checking workloads, a faulty core cannot hide its own miscomputation,
+/// nor decide the tally alone.
+pub fn run_vote_stress(
+    worker: usize,
+    rotation: &[CpuWorkload],
+    board: &VoteBoard,
+    running: &AtomicBool,
+    shared: &CpuShared,
+    throughput: &WorkerThroughput,
+    heartbeat: &Heartbeat,
+) {
+    board.cores[worker].pin();
+
+    let mut round: u64 = 0;
+
+    while running.load(Ordering::Relaxed) {
+        // Start the round together so every core runs the job under the same load
+        if !board.barrier.wait(running, heartbeat) {
+            break;
+        }
+
+        let workload = rotation[(round % rotation.len() as u64) as usize];
+        heartbeat.beat();
+        let started = Instant::now();
+        let digest = vote_digest(workload, round);
+        throughput.record(workload, started.elapsed());
+        board.digests[worker].store(digest, Ordering::Release);
+
+        if !board.barrier.wait(running, heartbeat) {
+            break;
+        }
+
+        // The talliers count; the others are held at the next barrier
+        let tally = (worker < TALLIERS).then(|| {
+            let digests: Vec<u64> = board
+                .digests
+                .iter()
+                .map(|d| d.load(Ordering::Acquire))
+                .collect();
+            let (majority, dissenters) = find_dissenters(&digests);
+            board.tallies[worker]
+                .store(tally_fingerprint(majority, &dissenters), Ordering::Release);
+            (digests, majority, dissenters)
+        });
+
+        if !board.barrier.wait(running, heartbeat) {
+            break;
+        }
+
+        if let Some((digests, majority, dissenters)) = tally.filter(|_| worker == 0) {
+            let tallies: Vec<u64> = board
+                .tallies
+                .iter()
+                .map(|t| t.load(Ordering::Acquire))
+                .collect();
+            let mismatches: Vec<(usize, String, String)> =
+                if tallies.iter().all(|&t| t == tallies[0]) {
+                    let expected = majority.map_or_else(
+                        || "majority (none)".to_string(),
+                        |m| format!("digest {:#018x}", m),
+                    );
+                    dissenters
+                        .into_iter()
+                        .map(|d| (d, expected.clone(), format!("digest {:#018x}", digests[d])))
+                        .collect()
+                } else {
+                    // The tallying cores disagree, and either may be the faulty one
+                    (0..TALLIERS)
+                        .map(|t| {
+                            let other = (t + 1) % TALLIERS;
+                            (
+                                t,
+                                format!("tally {:#018x} of worker {}", tallies[other], other),
+                                format!("tally {:#018x}", tallies[t]),
+                            )
+                        })
+                        .collect()
+                };
+
+            for (suspect, expected, actual) in mismatches {
+                let mismatch = CpuMismatch {
+                    workload,
+                    core: None,
+                    index: round as usize,
+                    expected,
+                    actual,
+                };
+                record_mismatch(shared, &board.cores[suspect], mismatch);
             }
-            3 => {
-                if !run_aes_stress() {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
+        }
+
+        round = round.wrapping_add(1);
+    }
+}
+
+/// Find the majority digest and the workers that disagree with it
+/// Without a strict majority the faulty core cannot be identified, so
+/// every worker is reported.
+fn find_dissenters(digests: &[u64]) -> (Option<u64>, Vec<usize>) {
+    let majority = digests
+        .iter()
+        .find(|&&candidate| digests.iter().filter(|&&d| d == candidate).count() * 2 > digests.len())
+        .copied();
+
+    let dissenters = match majority {
+        Some(m) => (0..digests.len()).filter(|&i| digests[i] != m).collect(),
+        None => (0..digests.len()).collect(),
+    };
+
+    (majority, dissenters)
+}
+
+/// Digest of a tally, for comparing the talliers' results
+fn tally_fingerprint(majority: Option<u64>, dissenters: &[usize]) -> u64 {
+    let majority = majority.map_or([0, 0], |m| [1, m]);
+    fnv1a(
+        majority
+            .into_iter()
+            .chain(dissenters.iter().map(|&d| d as u64)),
+    )
+}
+
+/// FNV-1a over 64-bit words
+fn fnv1a(words: impl IntoIterator<Item = u64>) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for word in words {
+        for byte in word.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Run a deterministic, seeded job and digest its entire output
+pub fn vote_digest(workload: CpuWorkload, seed: u64) -> u64 {
+    match workload {
+        CpuWorkload::Dft => {
+            let (real, imag) = dft(&dft_input(seed), &vec![0.0; DFT_SIZE], false);
+            fnv1a(real.iter().chain(&imag).map(|v| v.to_bits()))
+        }
+        CpuWorkload::Matrix => fnv1a(matrix_multiply(seed).iter().map(|v| v.to_bits())),
+        CpuWorkload::Prime => {
+            let limit = PRIME_LIMIT - (seed % 1000) as usize;
+            fnv1a(prime_sieve(limit).iter().map(|&p| p as u64))
+        }
+        CpuWorkload::Aes => {
+            let cipher = Aes256::new_from_slice(&AES_KEY).unwrap();
+            let mut block = [0u8; 16];
+            block[..8].copy_from_slice(&seed.to_le_bytes());
+            block[8..].copy_from_slice(&(!seed).to_le_bytes());
+
+            let mut chain = Vec::with_capacity(AES_ROUNDS * 2);
+            for _ in 0..AES_ROUNDS {
+                cipher.encrypt_block((&mut block).into());
+                chain.push(u64::from_le_bytes(block[..8].try_into().unwrap()));
+                chain.push(u64::from_le_bytes(block[8..].try_into().unwrap()));
             }
-            _ => unreachable!(),
+            fnv1a(chain)
+        }
+        CpuWorkload::Neon => {
+            let (x32, x64) = simd_inputs(seed);
+            let (c32, c64) = simd_coefficients();
+            let mut out32 = vec![0.0f32; SIMD_POINTS];
+            let mut out64 = vec![0.0f64; SIMD_POINTS];
+            simd::horner_f32_vector(&x32, &c32, &mut out32);
+            simd::horner_f64_vector(&x64, &c64, &mut out64);
+            fnv1a(
+                out32
+                    .iter()
+                    .map(|v| v.to_bits() as u64)
+                    .chain(out64.iter().map(|v| v.to_bits())),
+            )
+        }
+        CpuWorkload::Linpack => {
+            let (a, b) = linpack_system(LINPACK_SIZE, seed);
+            let x = lu_solve(a, b);
+            fnv1a(x.iter().map(|v| v.to_bits()))
         }
+    }
+}
 
-        iteration = iteration.wrapping_add(1);
+/// Run a single iteration of the given workload and verify its full output
+pub fn run_workload(workload: CpuWorkload, linpack: &LinpackStats) -> Result<(), CpuMismatch> {
+    match workload {
+        CpuWorkload::Dft => run_dft_stress(),
+        CpuWorkload::Matrix => run_matrix_stress(),
+        CpuWorkload::Prime => run_prime_stress(),
+        CpuWorkload::Aes => run_aes_stress(),
+        CpuWorkload::Neon => run_neon_stress(),
+        CpuWorkload::Linpack => run_linpack_stress().map(|gflops| linpack.record(gflops)),
     }
 }
 
-/// DFT stress test - floating-point intensive
-fn run_dft_stress() -> bool {
-    const SIZE: usize = 4096;
+/// Compare two f64 slices bit-for-bit, reporting the first differing element
+fn compare_bits(
+    workload: CpuWorkload,
+    offset: usize,
+    actual: &[f64],
+    expected: &[f64],
+) -> Result<(), CpuMismatch> {
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        if a.to_bits() != e.to_bits() {
+            return Err(CpuMismatch {
+                workload,
+                core: None,
+                index: offset + i,
+                expected: format_f64(*e),
+                actual: format_f64(*a),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn format_f64(value: f64) -> String {
+    format!("{:e} ({:#018x})", value, value.to_bits())
+}
+
+fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Input signal for the DFT workload (seed 0 is the golden input)
+fn dft_input(seed: u64) -> Vec<f64> {
+    (0..DFT_SIZE)
+        .map(|i| (i as u64).wrapping_add(seed) as f64)
+        .map(f64::sin)
+        .collect()
+}
 
-    // Create test data
-    let mut real: Vec<f64> = (0..SIZE).map(|i| (i as f64).sin()).collect();
-    let mut imag: Vec<f64> = vec![0.0; SIZE];
+/// Naive DFT (not optimized, but CPU-intensive)
+fn dft(real: &[f64], imag: &[f64], inverse: bool) -> (Vec<f64>, Vec<f64>) {
+    let size = real.len();
+    let sign = if inverse { 2.0 } else { -2.0 };
+    let scale = if inverse { size as f64 } else { 1.0 };
 
-    // Simple DFT (not optimized, but CPU-intensive)
-    let mut result_real = vec![0.0; SIZE];
-    let mut result_imag = vec![0.0; SIZE];
+    let mut result_real = vec![0.0; size];
+    let mut result_imag = vec![0.0; size];
 
-    for k in 0..SIZE {
+    for k in 0..size {
         let mut sum_real = 0.0;
         let mut sum_imag = 0.0;
 
-        for n in 0..SIZE {
-            let angle = -2.0 * std::f64::consts::PI * (k as f64) * (n as f64) / (SIZE as f64);
+        for n in 0..size {
+            let angle = sign * std::f64::consts::PI * (k as f64) * (n as f64) / (size as f64);
             sum_real += real[n] * angle.cos() - imag[n] * angle.sin();
             sum_imag += real[n] * angle.sin() + imag[n] * angle.cos();
         }
 
-        result_real[k] = sum_real;
-        result_imag[k] = sum_imag;
+        result_real[k] = sum_real / scale;
+        result_imag[k] = sum_imag / scale;
     }
 
-    // Inverse DFT to verify
-    for k in 0..SIZE {
-        let mut sum_real = 0.0;
-        let mut sum_imag = 0.0;
+    (result_real, result_imag)
+}
 
-        for n in 0..SIZE {
-            let angle = 2.0 * std::f64::consts::PI * (k as f64) * (n as f64) / (SIZE as f64);
-            sum_real += result_real[n] * angle.cos() - result_imag[n] * angle.sin();
-            sum_imag += result_real[n] * angle.sin() + result_imag[n] * angle.cos();
-        }
+/// DFT stress test - floating-point intensive
+/// The spectrum must match the golden spectrum bit-for-bit and the inverse
+/// transform must recover every sample of the original signal.
+fn run_dft_stress() -> Result<(), CpuMismatch> {
+    let signal = dft_input(0);
+    let golden = reference();
 
-        real[k] = sum_real / SIZE as f64;
-        imag[k] = sum_imag / SIZE as f64;
-    }
+    let (spectrum_real, spectrum_imag) = dft(&signal, &vec![0.0; DFT_SIZE], false);
+    compare_bits(CpuWorkload::Dft, 0, &spectrum_real, &golden.dft_real)?;
+    compare_bits(CpuWorkload::Dft, DFT_SIZE, &spectrum_imag, &golden.dft_imag)?;
 
-    // Verify result (original signal should be approximately recovered)
-    let expected_first = 0.0_f64.sin();
-    let tolerance = 0.001;
+    // Inverse DFT to verify
+    let (real, imag) = dft(&spectrum_real, &spectrum_imag, true);
+    for (i, (&value, &expected)) in real.iter().zip(&signal).enumerate() {
+        if (value - expected).abs() > DFT_TOLERANCE || imag[i].abs() > DFT_TOLERANCE {
+            return Err(CpuMismatch {
+                workload: CpuWorkload::Dft,
+                core: None,
+                index: 2 * DFT_SIZE + i,
+                expected: format_f64(expected),
+                actual: format_f64(value),
+            });
+        }
+    }
 
-    (real[0] - expected_first).abs() < tolerance
+    Ok(())
 }
 
-/// Matrix multiplication stress - SIMD friendly
-fn run_matrix_stress() -> bool {
-    const SIZE: usize = 128;
+/// Deterministic input matrices for the multiplication workload
+/// (seed 0 gives the golden matrices)
+fn matrix_inputs(seed: u64) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    const SIZE: usize = MATRIX_SIZE;
+    let seed = (seed % 100) as usize;
 
-    // Create matrices
     let mut a = vec![vec![0.0f64; SIZE]; SIZE];
     let mut b = vec![vec![0.0f64; SIZE]; SIZE];
-    let mut c = vec![vec![0.0f64; SIZE]; SIZE];
 
-    // Initialize with deterministic values
     for i in 0..SIZE {
         for j in 0..SIZE {
-            a[i][j] = ((i * SIZE + j) % 100) as f64 / 100.0;
-            b[i][j] = ((j * SIZE + i) % 100) as f64 / 100.0;
+            a[i][j] = ((i * SIZE + j + seed) % 100) as f64 / 100.0;
+            b[i][j] = ((j * SIZE + i + seed) % 100) as f64 / 100.0;
         }
     }
 
-    // Matrix multiplication
-    for i in 0..SIZE {
-        for j in 0..SIZE {
-            let mut sum = 0.0;
-            for k in 0..SIZE {
-                sum += a[i][k] * b[k][j];
-            }
-            c[i][j] = sum;
+    (a, b)
+}
+
+/// Compute one row of the product `a * b`
+fn matrix_row(a: &[Vec<f64>], b: &[Vec<f64>], i: usize, row: &mut [f64]) {
+    for (j, out) in row.iter_mut().enumerate() {
+        let mut sum = 0.0;
+        for k in 0..a.len() {
+            sum += a[i][k] * b[k][j];
         }
+        *out = sum;
     }
+}
+
+/// Multiply two deterministic matrices, returning the product in row-major order
+fn matrix_multiply(seed: u64) -> Vec<f64> {
+    let (a, b) = matrix_inputs(seed);
+    let mut c = vec![0.0f64; MATRIX_SIZE * MATRIX_SIZE];
 
-    // Verify a known element
-    // c[0][0] = sum of a[0][k] * b[k][0] for k=0..SIZE
-    let mut expected = 0.0;
-    for k in 0..SIZE {
-        expected += a[0][k] * b[k][0];
+    for (i, row) in c.chunks_exact_mut(MATRIX_SIZE).enumerate() {
+        matrix_row(&a, &b, i, row);
     }
 
-    (c[0][0] - expected).abs() < 0.0001
+    c
 }
 
-/// Prime sieve stress - integer operations
-fn run_prime_stress() -> bool {
-    const LIMIT: usize = 100_000;
+/// Matrix multiplication stress - SIMD friendly
+/// Every element of the product is compared against the golden product.
+fn run_matrix_stress() -> Result<(), CpuMismatch> {
+    let product = matrix_multiply(0);
+    compare_bits(CpuWorkload::Matrix, 0, &product, &reference().matrix)
+}
 
-    // Sieve of Eratosthenes
-    let mut is_prime = vec![true; LIMIT + 1];
+/// Sieve of Eratosthenes up to `limit`
+fn prime_sieve(limit: usize) -> Vec<bool> {
+    let mut is_prime = vec![true; limit + 1];
     is_prime[0] = false;
     is_prime[1] = false;
 
     let mut i = 2;
-    while i * i <= LIMIT {
+    while i * i <= limit {
         if is_prime[i] {
             let mut j = i * i;
-            while j <= LIMIT {
+            while j <= limit {
                 is_prime[j] = false;
                 j += i;
             }
@@ -146,38 +986,242 @@ fn run_prime_stress() -> bool {
         i += 1;
     }
 
-    // Count primes
+    is_prime
+}
+
+/// Prime sieve stress - integer operations
+/// The whole sieve is compared against the golden sieve, then the prime
+/// count is checked against the known value.
+fn run_prime_stress() -> Result<(), CpuMismatch> {
+    let is_prime = prime_sieve(PRIME_LIMIT);
+
+    for (i, (&actual, &expected)) in is_prime.iter().zip(&reference().primes).enumerate() {
+        if actual != expected {
+            return Err(CpuMismatch {
+                workload: CpuWorkload::Prime,
+                core: None,
+                index: i,
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+
     let prime_count = is_prime.iter().filter(|&&p| p).count();
+    if prime_count != PRIME_COUNT {
+        return Err(CpuMismatch {
+            workload: CpuWorkload::Prime,
+            core: None,
+            index: PRIME_LIMIT + 1,
+            expected: format!("{} primes", PRIME_COUNT),
+            actual: format!("{} primes", prime_count),
+        });
+    }
 
-    // Known value: there are 9592 primes below 100000
-    prime_count == 9592
+    Ok(())
 }
 
 /// AES stress test - uses hardware crypto if available
-fn run_aes_stress() -> bool {
-    // 256-bit key
-    let key: [u8; 32] = [
-        0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d, 0x77,
-        0x81, 0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3, 0x09, 0x14,
-        0xdf, 0xf4,
-    ];
+/// Checks the NIST known-answer vectors, then runs a chained
+/// encrypt/decrypt round-trip on a random block.
+fn run_aes_stress() -> Result<(), CpuMismatch> {
+    let cipher = Aes256::new_from_slice(&AES_KEY).unwrap();
+
+    for (i, (plaintext, ciphertext)) in AES_KAT.iter().enumerate() {
+        let mut block = *plaintext;
+        cipher.encrypt_block((&mut block).into());
+        if block != *ciphertext {
+            return Err(CpuMismatch {
+                workload: CpuWorkload::Aes,
+                core: None,
+                index: i,
+                expected: format_hex(ciphertext),
+                actual: format_hex(&block),
+            });
+        }
 
-    let cipher = Aes256::new_from_slice(&key).unwrap();
+        cipher.decrypt_block((&mut block).into());
+        if block != *plaintext {
+            return Err(CpuMismatch {
+                workload: CpuWorkload::Aes,
+                core: None,
+                index: i,
+                expected: format_hex(plaintext),
+                actual: format_hex(&block),
+            });
+        }
+    }
 
-    // Encrypt many blocks
+    // Encrypt many times, then decrypt back to the original block
     let mut rng = rand::thread_rng();
     let mut block = [0u8; 16];
     rng.fill(&mut block);
 
     let original_block = block;
 
-    // Encrypt many times
-    for _ in 0..10000 {
+    for _ in 0..AES_ROUNDS {
         cipher.encrypt_block((&mut block).into());
     }
+    for _ in 0..AES_ROUNDS {
+        cipher.decrypt_block((&mut block).into());
+    }
+
+    if block != original_block {
+        return Err(CpuMismatch {
+            workload: CpuWorkload::Aes,
+            core: None,
+            index: AES_KAT.len(),
+            expected: format_hex(&original_block),
+            actual: format_hex(&block),
+        });
+    }
 
-    // The block should have changed
-    block != original_block
+    Ok(())
+}
+
+/// Evaluation points in [-0.9, 0.9] for the NEON kernels (seed shifts them)
+fn simd_inputs(seed: u64) -> (Vec<f32>, Vec<f64>) {
+    let x64: Vec<f64> = (0..SIMD_POINTS)
+        .map(|i| ((i as u64).wrapping_add(seed) % SIMD_POINTS as u64) as f64)
+        .map(|i| -0.9 + 1.8 * i / SIMD_POINTS as f64)
+        .collect();
+    let x32 = x64.iter().map(|&x| x as f32).collect();
+    (x32, x64)
+}
+
+/// Polynomial coefficients in [-0.375, 0.375], keeping every result bounded
+fn simd_coefficients() -> (Vec<f32>, Vec<f64>) {
+    let c64: Vec<f64> = (0..SIMD_DEGREE)
+        .map(|k| ((k % 7) as f64 - 3.0) / 8.0)
+        .collect();
+    let c32 = c64.iter().map(|&c| c as f32).collect();
+    (c32, c64)
+}
+
+/// NEON/ASIMD stress - FMA-heavy f32 and f64 vector kernels
+/// The vector results must match a scalar FMA implementation bit-for-bit,
+/// so a fault in the SIMD datapath shows up as a disagreement.
+fn run_neon_stress() -> Result<(), CpuMismatch> {
+    let (x32, x64) = simd_inputs(0);
+    let (c32, c64) = simd_coefficients();
+
+    let mut vector32 = vec![0.0f32; SIMD_POINTS];
+    let mut scalar32 = vec![0.0f32; SIMD_POINTS];
+    simd::horner_f32_vector(&x32, &c32, &mut vector32);
+    simd::horner_f32_scalar(&x32, &c32, &mut scalar32);
+
+    for (i, (v, s)) in vector32.iter().zip(&scalar32).enumerate() {
+        if v.to_bits() != s.to_bits() {
+            return Err(CpuMismatch {
+                workload: CpuWorkload::Neon,
+                core: None,
+                index: i,
+                expected: format!("{:e} ({:#010x})", s, s.to_bits()),
+                actual: format!("{:e} ({:#010x})", v, v.to_bits()),
+            });
+        }
+    }
+
+    let mut vector64 = vec![0.0f64; SIMD_POINTS];
+    let mut scalar64 = vec![0.0f64; SIMD_POINTS];
+    simd::horner_f64_vector(&x64, &c64, &mut vector64);
+    simd::horner_f64_scalar(&x64, &c64, &mut scalar64);
+
+    compare_bits(CpuWorkload::Neon, SIMD_POINTS, &vector64, &scalar64)
+}
+
+/// Random system `A x = b` with entries in [-0.5, 0.5), A row-major
+fn linpack_system(n: usize, seed: u64) -> (Vec<f64>, Vec<f64>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let a = (0..n * n).map(|_| rng.gen::<f64>() - 0.5).collect();
+    let b = (0..n).map(|_| rng.gen::<f64>() - 0.5).collect();
+    (a, b)
+}
+
+/// Solve `A x = b` by LU factorization with partial pivoting (consumes A and b)
+fn lu_solve(mut a: Vec<f64>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+
+    for k in 0..n {
+        // Partial pivoting: bring the largest remaining entry of column k up
+        let pivot = (k..n)
+            .max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))
+            .unwrap_or(k);
+        if pivot != k {
+            for j in 0..n {
+                a.swap(k * n + j, pivot * n + j);
+            }
+            b.swap(k, pivot);
+        }
+
+        let diag = a[k * n + k];
+        for i in k + 1..n {
+            let factor = a[i * n + k] / diag;
+            a[i * n + k] = factor;
+            for j in k + 1..n {
+                a[i * n + j] -= factor * a[k * n + j];
+            }
+            b[i] -= factor * b[k];
+        }
+    }
+
+    // Back substitution on the upper triangle
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let mut sum = b[i];
+        for j in i + 1..n {
+            sum -= a[i * n + j] * x[j];
+        }
+        x[i] = sum / a[i * n + i];
+    }
+
+    x
+}
+
+/// HPL normalized residual ||Ax - b|| / (eps * (||A|| * ||x|| + ||b||) * n), infinity norms
+fn linpack_residual(a: &[f64], b: &[f64], x: &[f64]) -> f64 {
+    let n = b.len();
+    let mut residual: f64 = 0.0;
+    let mut norm_a: f64 = 0.0;
+
+    for i in 0..n {
+        let row = &a[i * n..(i + 1) * n];
+        let ax: f64 = row.iter().zip(x).map(|(a, x)| a * x).sum();
+        residual = residual.max((ax - b[i]).abs());
+        norm_a = norm_a.max(row.iter().map(|v| v.abs()).sum());
+    }
+
+    let norm_x = x.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    let norm_b = b.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+
+    residual / (f64::EPSILON * (norm_a * norm_x + norm_b) * n as f64)
+}
+
+/// LINPACK-style stress - dense LU factorization and solve
+/// A residual outside the HPL tolerance counts as an error; on success the
+/// achieved GFLOPS of the factorization and solve is returned.
+fn run_linpack_stress() -> Result<f64, CpuMismatch> {
+    let n = LINPACK_SIZE;
+    let (a, b) = linpack_system(n, rand::thread_rng().gen());
+
+    let start = Instant::now();
+    let x = lu_solve(a.clone(), b.clone());
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let residual = linpack_residual(&a, &b, &x);
+    if residual.is_nan() || residual >= LINPACK_RESIDUAL_LIMIT {
+        return Err(CpuMismatch {
+            workload: CpuWorkload::Linpack,
+            core: None,
+            index: 0,
+            expected: format!("residual < {}", LINPACK_RESIDUAL_LIMIT),
+            actual: format!("residual {:.3e}", residual),
+        });
+    }
+
+    let n = n as f64;
+    let flops = 2.0 / 3.0 * n * n * n + 2.0 * n * n;
+    Ok(flops / elapsed.max(f64::MIN_POSITIVE) / 1e9)
 }
 
 #[cfg(test)]
@@ -186,21 +1230,154 @@ mod tests {
 
     #[test]
     fn test_dft_stress() {
-        assert!(run_dft_stress());
+        assert!(run_dft_stress().is_ok());
     }
 
     #[test]
     fn test_matrix_stress() {
-        assert!(run_matrix_stress());
+        assert!(run_matrix_stress().is_ok());
     }
 
     #[test]
     fn test_prime_stress() {
-        assert!(run_prime_stress());
+        assert!(run_prime_stress().is_ok());
+        assert_eq!(
+            reference().primes.iter().filter(|&&p| p).count(),
+            PRIME_COUNT
+        );
     }
 
     #[test]
     fn test_aes_stress() {
-        assert!(run_aes_stress());
+        assert!(run_aes_stress().is_ok());
+    }
+
+    #[test]
+    fn test_neon_stress() {
+        assert!(run_neon_stress().is_ok());
+    }
+
+    #[test]
+    fn test_linpack_stress() {
+        let gflops = run_linpack_stress().unwrap();
+        assert!(gflops > 0.0);
+    }
+
+    #[test]
+    fn test_linpack_residual_detects_bad_solution() {
+        let (a, b) = linpack_system(32, 1);
+        let mut x = lu_solve(a.clone(), b.clone());
+        assert!(linpack_residual(&a, &b, &x) < LINPACK_RESIDUAL_LIMIT);
+
+        x[7] += 1e-6;
+        assert!(linpack_residual(&a, &b, &x) > LINPACK_RESIDUAL_LIMIT);
+    }
+
+    #[test]
+    fn test_linpack_stats() {
+        let stats = LinpackStats::default();
+        assert!(stats.snapshot().is_none());
+
+        stats.record(4.0);
+        stats.record(2.5);
+        let summary = stats.snapshot().unwrap();
+        assert_eq!(summary.iterations, 2);
+        assert_eq!(summary.last_gflops, 2.5);
+        assert_eq!(summary.min_gflops, 2.5);
+        assert_eq!(summary.max_gflops, 4.0);
+    }
+
+    #[test]
+    fn test_core_error_counters() {
+        let counters = CoreErrorCounters::new(2);
+        counters.record(CpuWorkload::Matrix);
+        counters.record(CpuWorkload::Matrix);
+        counters.record(CpuWorkload::Matrix);
+
+        let snapshot = counters.snapshot();
+        assert_eq!(snapshot.core, 2);
+        assert_eq!(snapshot.errors, 3);
+        assert_eq!(snapshot.to_string(), "core 2: 3 matrix errors");
+    }
+
+    #[test]
+    fn test_find_dissenters() {
+        assert_eq!(find_dissenters(&[7, 7, 7, 7]), (Some(7), vec![]));
+        assert_eq!(find_dissenters(&[7, 7, 9, 7]), (Some(7), vec![2]));
+        // No strict majority: every worker is suspect
+        assert_eq!(find_dissenters(&[7, 7, 9, 9]), (None, vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_tally_fingerprints_differ() {
+        let tallies = [
+            tally_fingerprint(Some(7), &[]),
+            tally_fingerprint(Some(7), &[2]),
+            tally_fingerprint(Some(9), &[2]),
+            tally_fingerprint(None, &[0, 1, 2]),
+        ];
+        for (i, a) in tallies.iter().enumerate() {
+            assert!(tallies[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(tallies[1], tally_fingerprint(Some(7), &[2]));
+    }
+
+    #[test]
+    fn test_vote_digest_is_deterministic() {
+        for workload in [
+            CpuWorkload::Matrix,
+            CpuWorkload::Prime,
+            CpuWorkload::Aes,
+            CpuWorkload::Neon,
+        ] {
+            assert_eq!(vote_digest(workload, 3), vote_digest(workload, 3));
+            assert_ne!(vote_digest(workload, 3), vote_digest(workload, 4));
+        }
+    }
+
+    #[test]
+    fn test_spin_barrier_releases_on_stop() {
+        let barrier = SpinBarrier::new(2);
+        let running = AtomicBool::new(false);
+        assert!(!barrier.wait(&running, &Heartbeat::new()));
+    }
+
+    #[test]
+    fn test_load_step_schedule_sweeps() {
+        let start = Instant::now();
+        let schedule = LoadStepSchedule::new(
+            start,
+            Duration::from_secs(160),
+            Duration::from_millis(1),
+            Duration::from_secs(5),
+        );
+
+        assert!((schedule.frequency_hz(Duration::ZERO) - 1000.0).abs() < 0.01);
+        assert!((schedule.frequency_hz(Duration::from_secs(159)) - 0.2).abs() < 0.001);
+
+        // First half of the first 1 ms cycle is busy, second half idle
+        let (busy, edge) = schedule.phase(start + Duration::from_micros(100));
+        assert!(busy);
+        assert_eq!(edge, start + Duration::from_micros(500));
+        let (busy, edge) = schedule.phase(start + Duration::from_micros(600));
+        assert!(!busy);
+        assert_eq!(edge, start + Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_load_step_reports_matrix_only() {
+        let selected: WorkloadMix<CpuWorkload> = "dft:2,aes".parse().unwrap();
+        assert_eq!(CpuMode::Rotate.workloads(&selected), selected);
+        assert_eq!(CpuMode::LoadStep.workloads(&selected).to_string(), "matrix");
+    }
+
+    #[test]
+    fn test_compare_bits_reports_first_mismatch() {
+        let expected = [1.0, 2.0, 3.0, 4.0];
+        let actual = [1.0, 2.0, 3.5, 4.5];
+        let mismatch = compare_bits(CpuWorkload::Matrix, 10, &actual, &expected).unwrap_err();
+        assert_eq!(mismatch.workload, CpuWorkload::Matrix);
+        assert_eq!(mismatch.index, 12);
+        assert!(compare_bits(CpuWorkload::Matrix, 0, &expected, &expected).is_ok());
     }
 }
@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::FileExt;
+use std::ptr::NonNull;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::uring::Ring;
+
+/// Buffer alignment for O_DIRECT; covers 512-byte and 4K logical blocks
+pub const DIRECT_IO_ALIGN: usize = 4096;
+
+/// How I/O requests are kept in flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoEngine {
+    /// One io_uring per worker with the whole queue depth in flight
+    #[default]
+    IoUring,
+    /// A pool of threads per worker, each with one blocking request in flight
+    Threads,
+}
+
+impl IoEngine {
+    /// The engine that will actually run: io_uring falls back to threads
+    /// where the kernel does not offer it
+    pub fn available(self) -> IoEngine {
+        match self {
+            IoEngine::IoUring if Ring::new(1).is_ok() => IoEngine::IoUring,
+            _ => IoEngine::Threads,
+        }
+    }
+}
+
+impl fmt::Display for IoEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoEngine::IoUring => write!(f, "io_uring"),
+            IoEngine::Threads => write!(f, "threads"),
+        }
+    }
+}
+
+/// Zeroed heap buffer aligned for O_DIRECT
+pub struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// Owns its allocation exclusively, like a Vec<u8>
+unsafe impl Send for AlignedBuffer {}
+
+impl AlignedBuffer {
+    pub fn new(len: usize) -> Self {
+        let layout = Layout::from_size_align(len, DIRECT_IO_ALIGN).expect("invalid buffer size");
+        let ptr = NonNull::new(unsafe { alloc::alloc_zeroed(layout) })
+            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Self { ptr, layout }
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoKind {
+    Read,
+    Write,
+}
+
+/// A read into or a write of the whole buffer at `offset`
+pub struct IoRequest {
+    pub kind: IoKind,
+    pub offset: u64,
+    pub buffer: AlignedBuffer,
+}
+
+/// A finished request, with its buffer handed back
+pub struct IoCompletion {
+    pub request: IoRequest,
+    pub result: io::Result<()>,
+    /// From submission to completion, including any wait in the queue
+    pub latency: Duration,
+}
+
+/// Keeps up to `depth` requests on one file in flight
+pub struct IoQueue {
+    depth: usize,
+    in_flight: usize,
+    backend: Backend,
+}
+
+enum Backend {
+    Uring {
+        ring: Ring,
+        file: Arc<File>,
+        /// Requests owned by the kernel and when they were submitted,
+        /// indexed by their user_data
+        slots: Vec<Option<(IoRequest, Instant)>>,
+    },
+    Threads {
+        jobs: Option<Sender<(IoRequest, Instant)>>,
+        done: Receiver<IoCompletion>,
+        threads: Vec<JoinHandle<()>>,
+    },
+}
+
+impl IoQueue {
+    pub fn new(engine: IoEngine, file: Arc<File>, depth: usize) -> io::Result<Self> {
+        let backend = match engine {
+            IoEngine::IoUring => Backend::Uring {
+                ring: Ring::new(depth as u32)?,
+                file,
+                slots: (0..depth).map(|_| None).collect(),
+            },
+            IoEngine::Threads => {
+                let (jobs, queued) = mpsc::channel();
+                let (finished, done) = mpsc::channel();
+                let queued = Arc::new(Mutex::new(queued));
+                let threads = (0..depth)
+                    .map(|_| {
+                        let (file, queued, finished) =
+                            (file.clone(), queued.clone(), finished.clone());
+                        std::thread::spawn(move || serve(&file, &queued, &finished))
+                    })
+                    .collect();
+                Backend::Threads {
+                    jobs: Some(jobs),
+                    done,
+                    threads,
+                }
+            }
+        };
+        Ok(Self {
+            depth,
+            in_flight: 0,
+            backend,
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Start `request`; at most `depth` may be in flight
+    pub fn submit(&mut self, request: IoRequest) -> io::Result<()> {
+        assert!(self.in_flight < self.depth, "I/O queue is full");
+        match &mut self.backend {
+            Backend::Uring { ring, file, slots } => {
+                let slot = slots.iter().position(Option::is_none).unwrap();
+                let (request, _) = slots[slot].insert((request, Instant::now()));
+                let fd = file.as_raw_fd();
+                let queued = match request.kind {
+                    IoKind::Read => {
+                        ring.push_read(fd, &mut request.buffer, request.offset, slot as u64)
+                    }
+                    IoKind::Write => {
+                        ring.push_write(fd, &request.buffer, request.offset, slot as u64)
+                    }
+                };
+                if !queued {
+                    slots[slot] = None;
+                    return Err(io::Error::other("io_uring submission queue full"));
+                }
+            }
+            Backend::Threads { jobs, .. } => {
+                let sent = jobs
+                    .as_ref()
+                    .map(|jobs| jobs.send((request, Instant::now())));
+                if !matches!(sent, Some(Ok(()))) {
+                    return Err(io::Error::other("I/O threads have stopped"));
+                }
+            }
+        }
+        self.in_flight += 1;
+        Ok(())
+    }
+
+    /// Block until at least one request completes and return all that have
+    pub fn wait(&mut self) -> io::Result<Vec<IoCompletion>> {
+        let mut completions = Vec::new();
+        if self.in_flight == 0 {
+            return Ok(completions);
+        }
+        match &mut self.backend {
+            Backend::Uring { ring, slots, .. } => {
+                ring.submit_and_wait(1)?;
+                ring.complete(|user_data, res| {
+                    let Some((request, submitted)) =
+                        slots.get_mut(user_data as usize).and_then(Option::take)
+                    else {
+                        return;
+                    };
+                    let latency = submitted.elapsed();
+                    let result = match res {
+                        res if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+                        res if res as usize != request.buffer.len() => Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!(
+                                "short {:?} at offset {}: {} of {} bytes",
+                                request.kind,
+                                request.offset,
+                                res,
+                                request.buffer.len()
+                            ),
+                        )),
+                        _ => Ok(()),
+                    };
+                    completions.push(IoCompletion {
+                        request,
+                        result,
+                        latency,
+                    });
+                });
+            }
+            Backend::Threads { done, .. } => {
+                let first = done
+                    .recv()
+                    .map_err(|_| io::Error::other("I/O threads have stopped"))?;
+                completions.push(first);
+                completions.extend(done.try_iter());
+            }
+        }
+        self.in_flight -= completions.len();
+        Ok(completions)
+    }
+
+    /// Wait for every request in flight
+    pub fn drain(&mut self) -> io::Result<Vec<IoCompletion>> {
+        let mut completions = Vec::new();
+        while self.in_flight > 0 {
+            completions.extend(self.wait()?);
+        }
+        Ok(completions)
+    }
+}
+
+impl Drop for IoQueue {
+    fn drop(&mut self) {
+        let drained = self.drain().is_ok();
+        match &mut self.backend {
+            Backend::Uring { slots, .. } => {
+                // The kernel may still write into buffers it was never seen to
+                // finish with, so leak them rather than free them under it
+                if !drained {
+                    slots.drain(..).for_each(std::mem::forget);
+                }
+            }
+            Backend::Threads { jobs, threads, .. } => {
+                jobs.take();
+                for thread in threads.drain(..) {
+                    let _ = thread.join();
+                }
+            }
+        }
+    }
+}
+
+/// Body of a thread-pool engine thread: blocking I/O until the queue closes
+fn serve(
+    file: &File,
+    queued: &Mutex<Receiver<(IoRequest, Instant)>>,
+    finished: &Sender<IoCompletion>,
+) {
+    loop {
+        let Ok((mut request, submitted)) = queued.lock().unwrap().recv() else {
+            return;
+        };
+        let result = match request.kind {
+            IoKind::Read => file.read_exact_at(&mut request.buffer, request.offset),
+            IoKind::Write => file.write_all_at(&request.buffer, request.offset),
+        };
+        let completion = IoCompletion {
+            request,
+            result,
+            latency: submitted.elapsed(),
+        };
+        if finished.send(completion).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: usize = 4096;
+
+    #[test]
+    fn test_aligned_buffer() {
+        let mut buffer = AlignedBuffer::new(32 * BLOCK);
+        assert_eq!(buffer.as_ptr() as usize % DIRECT_IO_ALIGN, 0);
+        assert_eq!(buffer.len(), 32 * BLOCK);
+        assert!(buffer.iter().all(|&b| b == 0));
+        buffer[32 * BLOCK - 1] = 0xff;
+        assert_eq!(buffer[32 * BLOCK - 1], 0xff);
+    }
+
+    /// Write 16 distinct blocks with 4 in flight, then read them back
+    fn round_trip(engine: IoEngine) {
+        let path =
+            std::env::temp_dir().join(format!("piup-io-engine-{}-{}", engine, std::process::id()));
+        let file = Arc::new(
+            File::options()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .unwrap(),
+        );
+        let mut queue = IoQueue::new(engine, file, 4).unwrap();
+
+        let mut run = |kind: IoKind| {
+            let mut completions = Vec::new();
+            for block in 0..16u8 {
+                if queue.in_flight() == queue.depth() {
+                    completions.extend(queue.wait().unwrap());
+                }
+                let mut buffer = AlignedBuffer::new(BLOCK);
+                if kind == IoKind::Write {
+                    buffer.fill(block);
+                }
+                let offset = block as u64 * BLOCK as u64;
+                queue
+                    .submit(IoRequest {
+                        kind,
+                        offset,
+                        buffer,
+                    })
+                    .unwrap();
+            }
+            completions.extend(queue.drain().unwrap());
+            completions
+        };
+        run(IoKind::Write);
+        let reads = run(IoKind::Read);
+        drop(queue);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(reads.len(), 16);
+        for IoCompletion {
+            request, result, ..
+        } in reads
+        {
+            result.unwrap();
+            let block = (request.offset / BLOCK as u64) as u8;
+            assert!(request.buffer.iter().all(|&b| b == block));
+        }
+    }
+
+    #[test]
+    fn test_thread_engine_round_trip() {
+        round_trip(IoEngine::Threads);
+    }
+
+    #[test]
+    fn test_io_uring_round_trip() {
+        // Kernels and sandboxes without io_uring fall back, which is tested above
+        if IoEngine::IoUring.available() == IoEngine::IoUring {
+            round_trip(IoEngine::IoUring);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Linear sub-buckets per power of two, bounding the error to 1/16
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// Latencies are tracked up to 2^40 µs (about 12 days); longer ones are clamped
+const MAX_EXPONENT: u32 = 40;
+
+const BUCKETS: usize = (MAX_EXPONENT - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// HDR-style latency histogram with log-linear buckets in microseconds
+/// Recording is lock-free, so any number of threads can share one.
+pub struct LatencyHistogram {
+    buckets: Box<[AtomicU64]>,
+    max_us: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            max_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, latency: Duration) {
+        let us = (latency.as_micros() as u64).min((1 << MAX_EXPONENT) - 1);
+        self.buckets[bucket(us)].fetch_add(1, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts().iter().sum()
+    }
+
+    fn counts(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Latency at `quantile` (0..=1) in µs, rounded up to its bucket's top;
+    /// None before anything was recorded
+    pub fn percentile(&self, quantile: f64) -> Option<u64> {
+        percentile(
+            &self.counts(),
+            quantile,
+            self.max_us.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn summary(&self) -> Option<LatencySummary> {
+        let counts = self.counts();
+        let max_us = self.max_us.load(Ordering::Relaxed);
+        Some(LatencySummary {
+            count: counts.iter().sum(),
+            p50_us: percentile(&counts, 0.5, max_us)?,
+            p99_us: percentile(&counts, 0.99, max_us)?,
+            p999_us: percentile(&counts, 0.999, max_us)?,
+            max_us,
+        })
+    }
+}
+
+/// Bucket of a latency: exact below 16 µs, then 16 steps per power of two
+fn bucket(us: u64) -> usize {
+    if us < SUB_BUCKETS as u64 {
+        return us as usize;
+    }
+    let exponent = 63 - us.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub = (us >> shift) as usize - SUB_BUCKETS;
+    (shift as usize + 1) * SUB_BUCKETS + sub
+}
+
+/// Largest latency that falls into `index`
+fn bucket_top(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let sub = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    ((sub + 1) << shift) - 1
+}
+
+fn percentile(counts: &[u64], quantile: f64, max_us: u64) -> Option<u64> {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let rank = ((quantile * total as f64).ceil() as u64).clamp(1, total);
+    let mut seen = 0;
+    let index = counts.iter().position(|&count| {
+        seen += count;
+        seen >= rank
+    })?;
+    Some(bucket_top(index).min(max_us))
+}
+
+/// Latency percentiles of one kind of request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_us: u64,
+    pub p99_us: u64,
+    pub p999_us: u64,
+    pub max_us: u64,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50 {}, p99 {}, p99.9 {}, max {} ({} ops)",
+            format_us(self.p50_us),
+            format_us(self.p99_us),
+            format_us(self.p999_us),
+            format_us(self.max_us),
+            self.count
+        )
+    }
+}
+
+/// "850 µs", "12.5 ms" or "1.20 s"
+pub fn format_us(us: u64) -> String {
+    match us {
+        0..=999 => format!("{} µs", us),
+        1_000..=999_999 => format!("{:.1} ms", us as f64 / 1e3),
+        _ => format!("{:.2} s", us as f64 / 1e6),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_bound_the_error() {
+        for us in [0, 1, 15, 16, 17, 31, 32, 100, 999, 4096, 123_456, 9_999_999] {
+            let top = bucket_top(bucket(us));
+            assert!(top >= us, "{} -> {}", us, top);
+            assert!(top - us <= us / 16, "{} -> {}", us, top);
+        }
+        assert_eq!(bucket((1 << MAX_EXPONENT) - 1), BUCKETS - 1);
+        // Buckets are contiguous
+        for index in 1..BUCKETS {
+            assert_eq!(bucket(bucket_top(index - 1) + 1), index);
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let histogram = LatencyHistogram::default();
+        assert_eq!(histogram.summary(), None);
+
+        // 990 fast requests, 9 at 5 ms and one 1.5 s stall
+        for _ in 0..990 {
+            histogram.record(Duration::from_micros(100));
+        }
+        for _ in 0..9 {
+            histogram.record(Duration::from_millis(5));
+        }
+        histogram.record(Duration::from_millis(1500));
+
+        let summary = histogram.summary().unwrap();
+        assert_eq!(summary.count, 1000);
+        assert_eq!(summary.p50_us, 103);
+        assert_eq!(summary.p99_us, 103);
+        assert!((5_000..5_400).contains(&summary.p999_us));
+        assert_eq!(summary.max_us, 1_500_000);
+        assert_eq!(histogram.percentile(1.0), Some(1_500_000));
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "p50 103 µs, p99 103 µs, p99.9 {}, max 1.50 s (1000 ops)",
+                format_us(summary.p999_us)
+            )
+        );
+    }
+}
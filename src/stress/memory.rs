@@ -1,190 +1,1483 @@
-use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-/// Minimum memory chunk size in bytes (64 MB)
-const MIN_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+use super::buffer::{BufferSpec, HugePages, Residency, TestBuffer};
+use super::shared::{ProcessShared, RecordLog, Shared, SharedSlice};
+use super::supervisor::Heartbeat;
+use super::throughput::WorkerThroughput;
+use super::workload::Workload;
+use crate::system::{affinity, pagemap};
+
+/// Most memory worker processes; more only splits the same RAM thinner
+pub const MAX_WORKERS: usize = 64;
+
+/// Mismatches kept with full detail for the report
+const MAX_ERROR_RECORDS: usize = 256;
+
+/// Bytes written or compared between heartbeats, so one pass over a large
+/// buffer never looks like a hang
+const TICK_BYTES: usize = 64 * 1024 * 1024;
+
+/// Random writes (and read-backs) per random access iteration
+const RANDOM_ACCESSES: usize = 100_000;
+
+/// Base patterns for moving inversions; each is also run inverted
+const MOVING_INVERSION_PATTERNS: [u64; 4] = [
+    0x0000_0000_0000_0000,
+    0x5555_5555_5555_5555,
+    0x3333_3333_3333_3333,
+    0x0F0F_0F0F_0F0F_0F0F,
+];
+
+const CHECKERBOARD: u64 = 0x5555_5555_5555_5555;
+
+/// STREAM kernels, in the order they run
+const STREAM_KERNELS: usize = 4;
+
+/// Pointer-chase working sets that fit L1 (64 KiB), L2 (512 KiB) and the
+/// shared L3 (2 MiB) of the Cortex-A76, and one that only fits DRAM
+const LATENCY_LEVELS: [usize; 4] = [32 << 10, 256 << 10, 1 << 20, 64 << 20];
+
+/// Dependent loads timed per working set
+const CHASE_STEPS: usize = 1 << 20;
+
+/// Words per 64-byte cache line
+const LINE_WORDS: usize = 8;
+
+/// Blocks handed from the producer to the consumers per coherency pass
+const HANDOFFS: u64 = 4096;
+
+/// Blocks in flight at once; the producer reuses a slot once every consumer is done with it
+const HANDOFF_SLOTS: usize = 64;
+
+/// Words per handed-off block (4 KiB)
+const HANDOFF_BLOCK_WORDS: usize = 512;
+
+/// Consumer threads, each pinned to a different core than the producer if possible
+const MAX_CONSUMERS: usize = 3;
+
+/// Busy-wait iterations before a waiting thread yields its core
+const SPINS_BEFORE_YIELD: u32 = 1000;
+
+/// Aggressor pairs hammered per row-hammer pass
+const HAMMER_PAIRS: usize = 32;
+
+/// Each pair is hammered within one 2 MiB region, physically contiguous
+/// when backed by a huge page, so it has a fair chance to share a bank
+const HAMMER_REGION: usize = 2 << 20;
+
+/// How long each pair is hammered: one LPDDR4X refresh window
+const HAMMER_WINDOW: Duration = Duration::from_millis(64);
+
+/// Read-and-flush rounds between clock checks
+const HAMMER_BATCH: usize = 4096;
+
+/// Victim fills; disturbance flips depend on the stored value (true/anti cells)
+const HAMMER_PATTERNS: [u64; 4] = [0, !0, CHECKERBOARD, !CHECKERBOARD];
+
+/// Memory workloads rotated by each stress thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemoryWorkload {
+    Sequential,
+    Random,
+    Fill,
+    Stream,
+    WalkingOnes,
+    WalkingZeros,
+    Checkerboard,
+    BitSpread,
+    MovingInversions,
+    Address,
+    SeededRandom,
+    Latency,
+    Coherency,
+    RowHammer,
+}
+
+impl MemoryWorkload {
+    /// All workloads, in rotation order (row-hammer only when selected)
+    pub const ALL: [MemoryWorkload; 14] = [
+        MemoryWorkload::Sequential,
+        MemoryWorkload::Random,
+        MemoryWorkload::Fill,
+        MemoryWorkload::Stream,
+        MemoryWorkload::WalkingOnes,
+        MemoryWorkload::WalkingZeros,
+        MemoryWorkload::Checkerboard,
+        MemoryWorkload::BitSpread,
+        MemoryWorkload::MovingInversions,
+        MemoryWorkload::Address,
+        MemoryWorkload::SeededRandom,
+        MemoryWorkload::Latency,
+        MemoryWorkload::Coherency,
+        MemoryWorkload::RowHammer,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryWorkload::Sequential => "sequential",
+            MemoryWorkload::Random => "random",
+            MemoryWorkload::Fill => "fill",
+            MemoryWorkload::Stream => "stream",
+            MemoryWorkload::WalkingOnes => "walking-ones",
+            MemoryWorkload::WalkingZeros => "walking-zeros",
+            MemoryWorkload::Checkerboard => "checkerboard",
+            MemoryWorkload::BitSpread => "bit-spread",
+            MemoryWorkload::MovingInversions => "moving-inversions",
+            MemoryWorkload::Address => "address",
+            MemoryWorkload::SeededRandom => "seeded-random",
+            MemoryWorkload::Latency => "latency",
+            MemoryWorkload::Coherency => "coherency",
+            MemoryWorkload::RowHammer => "row-hammer",
+        }
+    }
+}
+
+impl Workload for MemoryWorkload {
+    const VARIANTS: &'static [Self] = &Self::ALL;
+
+    fn name(&self) -> &'static str {
+        MemoryWorkload::name(self)
+    }
+
+    fn opt_in(&self) -> bool {
+        *self == MemoryWorkload::RowHammer
+    }
+}
+
+/// Pass and failure count of one workload
+#[derive(Debug, Default)]
+struct PatternCount {
+    passes: AtomicU64,
+    failures: AtomicU64,
+}
+
+unsafe impl ProcessShared for PatternCount {}
+
+/// Size and residency of one worker's buffer, overwritten when it restarts
+#[derive(Debug, Default)]
+struct BufferStats {
+    allocated: AtomicU64,
+    locked: AtomicU64,
+    huge: AtomicU64,
+}
+
+unsafe impl ProcessShared for BufferStats {}
+
+/// Latest and best bandwidth (GB/s) and latency (ns) figures
+/// f64 values are stored as bits; non-negative floats order like their bits.
+struct BenchStats {
+    bandwidth_last: [AtomicU64; STREAM_KERNELS],
+    bandwidth_best: [AtomicU64; STREAM_KERNELS],
+    latency_last: [AtomicU64; LATENCY_LEVELS.len()],
+    latency_best: [AtomicU64; LATENCY_LEVELS.len()],
+}
+
+unsafe impl ProcessShared for BenchStats {}
+
+impl Default for BenchStats {
+    fn default() -> Self {
+        Self {
+            bandwidth_last: Default::default(),
+            bandwidth_best: Default::default(),
+            latency_last: Default::default(),
+            latency_best: std::array::from_fn(|_| AtomicU64::new(u64::MAX)),
+        }
+    }
+}
+
+impl BenchStats {
+    fn record_bandwidth(&self, kernel: usize, gbps: f64) {
+        let bits = gbps.to_bits();
+        self.bandwidth_last[kernel].store(bits, Ordering::Relaxed);
+        self.bandwidth_best[kernel].fetch_max(bits, Ordering::Relaxed);
+    }
+
+    fn record_latency(&self, level: usize, ns: f64) {
+        let bits = ns.to_bits();
+        self.latency_last[level].store(bits, Ordering::Relaxed);
+        self.latency_best[level].fetch_min(bits, Ordering::Relaxed);
+    }
+
+    /// Latest (or best) figures; None until something was measured
+    fn snapshot(&self, best: bool) -> Option<MemoryBench> {
+        let (bandwidth, latency) = if best {
+            (&self.bandwidth_best, &self.latency_best)
+        } else {
+            (&self.bandwidth_last, &self.latency_last)
+        };
+        let value = |slot: &AtomicU64| match slot.load(Ordering::Relaxed) {
+            0 | u64::MAX => None,
+            bits => Some(f64::from_bits(bits)),
+        };
+        let bench = MemoryBench {
+            copy_gbps: value(&bandwidth[0]),
+            scale_gbps: value(&bandwidth[1]),
+            add_gbps: value(&bandwidth[2]),
+            triad_gbps: value(&bandwidth[3]),
+            l1_ns: value(&latency[0]),
+            l2_ns: value(&latency[1]),
+            l3_ns: value(&latency[2]),
+            dram_ns: value(&latency[3]),
+        };
+        (bench != MemoryBench::default()).then_some(bench)
+    }
+}
+
+/// STREAM bandwidth per memory worker and pointer-chase latency, measured under load
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryBench {
+    pub copy_gbps: Option<f64>,
+    pub scale_gbps: Option<f64>,
+    pub add_gbps: Option<f64>,
+    pub triad_gbps: Option<f64>,
+    /// Nanoseconds per dependent load with the working set in each level
+    pub l1_ns: Option<f64>,
+    pub l2_ns: Option<f64>,
+    pub l3_ns: Option<f64>,
+    pub dram_ns: Option<f64>,
+}
+
+impl MemoryBench {
+    /// "copy 5.1, scale 4.9, add 5.3, triad 5.2 GB/s", or None if STREAM never ran
+    pub fn bandwidth(&self) -> Option<String> {
+        join_measured(
+            &[
+                ("copy", self.copy_gbps),
+                ("scale", self.scale_gbps),
+                ("add", self.add_gbps),
+                ("triad", self.triad_gbps),
+            ],
+            "GB/s",
+        )
+    }
+
+    /// "L1 1.6, L2 5.2, L3 12.0, DRAM 110.4 ns", or None if latency never ran
+    pub fn latency(&self) -> Option<String> {
+        join_measured(
+            &[
+                ("L1", self.l1_ns),
+                ("L2", self.l2_ns),
+                ("L3", self.l3_ns),
+                ("DRAM", self.dram_ns),
+            ],
+            "ns",
+        )
+    }
+}
+
+fn join_measured(values: &[(&str, Option<f64>)], unit: &str) -> Option<String> {
+    let parts: Vec<String> = values
+        .iter()
+        .filter_map(|(name, value)| value.map(|v| format!("{} {:.1}", name, v)))
+        .collect();
+    (!parts.is_empty()).then(|| format!("{} {}", parts.join(", "), unit))
+}
+
+/// Results shared by all memory workers and read by the monitoring loop
+/// Backed by shared memory so forked worker processes report into it.
+pub struct MemoryShared {
+    pub errors: Shared<AtomicU64>,
+    /// Every mismatching location, the first MAX_ERROR_RECORDS in detail
+    pub error_log: RecordLog<MemoryError>,
+    patterns: SharedSlice<PatternCount>,
+    buffers: SharedSlice<BufferStats>,
+    bench: Shared<BenchStats>,
+}
+
+impl MemoryShared {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            errors: Shared::default(),
+            error_log: RecordLog::new(MAX_ERROR_RECORDS),
+            patterns: SharedSlice::from_fn(MemoryWorkload::ALL.len(), |_| PatternCount::default()),
+            buffers: SharedSlice::from_fn(workers, |_| BufferStats::default()),
+            bench: Shared::default(),
+        }
+    }
+
+    fn record(&self, workload: MemoryWorkload, passed: bool) {
+        let count = &self.patterns[workload as usize];
+        if passed {
+            count.passes.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            count.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Log a mismatch found at `mismatch.offset` in the buffer starting at `base`
+    fn record_mismatch(&self, workload: MemoryWorkload, base: usize, mismatch: Mismatch) {
+        self.error_log
+            .push_with(|| MemoryError::new(workload, base, mismatch));
+    }
+
+    fn record_buffer(
+        &self,
+        worker: usize,
+        allocated: usize,
+        residency: Residency,
+        hugepages: HugePages,
+    ) {
+        if let Some(stats) = self.buffers.get(worker) {
+            stats.allocated.store(allocated as u64, Ordering::Relaxed);
+            stats
+                .locked
+                .store(residency.locked_bytes, Ordering::Relaxed);
+            stats
+                .huge
+                .store(residency.huge_bytes(hugepages), Ordering::Relaxed);
+        }
+    }
+
+    /// Most recent bandwidth and latency figures, for the live display
+    pub fn latest_bench(&self) -> Option<MemoryBench> {
+        self.bench.snapshot(false)
+    }
+
+    /// Highest bandwidth and lowest latency seen during the run
+    pub fn best_bench(&self) -> Option<MemoryBench> {
+        self.bench.snapshot(true)
+    }
+
+    /// Buffer totals over all workers (None until a worker has allocated)
+    pub fn buffer_summary(&self, hugepages: HugePages) -> Option<BufferSummary> {
+        let total = |field: fn(&BufferStats) -> &AtomicU64| {
+            self.buffers
+                .iter()
+                .map(|b| field(b).load(Ordering::Relaxed))
+                .sum::<u64>()
+        };
+        let allocated_bytes = total(|b| &b.allocated);
+        (allocated_bytes > 0).then(|| BufferSummary {
+            hugepages,
+            allocated_bytes,
+            locked_bytes: total(|b| &b.locked),
+            huge_bytes: total(|b| &b.huge),
+        })
+    }
+
+    /// Pass and failure counts of every workload that completed at least once
+    pub fn pattern_summary(&self) -> Vec<PatternResult> {
+        MemoryWorkload::ALL
+            .iter()
+            .map(|&pattern| {
+                let count = &self.patterns[pattern as usize];
+                PatternResult {
+                    pattern,
+                    passes: count.passes.load(Ordering::Relaxed),
+                    failures: count.failures.load(Ordering::Relaxed),
+                }
+            })
+            .filter(|p| p.passes + p.failures > 0)
+            .collect()
+    }
+}
+
+/// Completed passes of one memory workload, for the report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternResult {
+    pub pattern: MemoryWorkload,
+    pub passes: u64,
+    pub failures: u64,
+}
+
+impl fmt::Display for PatternResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.pattern.name(), self.passes)?;
+        if self.failures > 0 {
+            write!(f, " ({} failed)", self.failures)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the kernel granted for the memory workers' buffers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferSummary {
+    /// Huge page mode that was requested
+    pub hugepages: HugePages,
+    pub allocated_bytes: u64,
+    /// Bytes pinned with mlock; less than allocated means some could be swapped
+    pub locked_bytes: u64,
+    /// Bytes backed by huge pages; 0 means the request was not honoured
+    pub huge_bytes: u64,
+}
+
+impl fmt::Display for BufferSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mb = |bytes: u64| bytes / (1024 * 1024);
+        write!(
+            f,
+            "{} MB allocated, {} MB locked",
+            mb(self.allocated_bytes),
+            mb(self.locked_bytes)
+        )?;
+        if self.hugepages != HugePages::Off {
+            write!(f, ", {} MB in huge pages", mb(self.huge_bytes))?;
+        }
+        Ok(())
+    }
+}
+
+/// One location that read back wrong, for the report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryError {
+    pub pattern: MemoryWorkload,
+    /// Byte offset into the worker's buffer
+    pub offset: usize,
+    pub virtual_address: u64,
+    /// From /proc/self/pagemap; only available when running as root
+    pub physical_address: Option<u64>,
+    pub expected: u64,
+    pub actual: u64,
+    /// Positions of the bits that differ (0 = least significant)
+    pub flipped_bits: Vec<u32>,
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+}
+
+impl MemoryError {
+    fn new(pattern: MemoryWorkload, base: usize, mismatch: Mismatch) -> Self {
+        let address = base + mismatch.offset;
+        let diff = mismatch.expected ^ mismatch.actual;
+        Self {
+            pattern,
+            offset: mismatch.offset,
+            virtual_address: address as u64,
+            physical_address: pagemap::physical_address(address),
+            expected: mismatch.expected,
+            actual: mismatch.actual,
+            flipped_bits: (0..64).filter(|bit| diff & (1 << bit) != 0).collect(),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_millis() as u64),
+        }
+    }
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {:#x}", self.pattern.name(), self.virtual_address)?;
+        if let Some(physical) = self.physical_address {
+            write!(f, " (phys {:#x})", physical)?;
+        }
+        let bits: Vec<String> = self.flipped_bits.iter().map(|b| b.to_string()).collect();
+        write!(
+            f,
+            ": expected {:#018x}, got {:#018x}, bits {}",
+            self.expected,
+            self.actual,
+            bits.join(",")
+        )
+    }
+}
+
+/// A location that read back differently from what was written
+/// `offset` is in bytes; byte-wide workloads report byte values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mismatch {
+    offset: usize,
+    expected: u64,
+    actual: u64,
+}
+
+/// A pass found this many locations that read back wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Corrupted(usize);
+
+/// Keeps the heartbeat going between passes and reports every mismatch
+struct Verifier<'a> {
+    running: &'a AtomicBool,
+    heartbeat: &'a Heartbeat,
+    report: &'a dyn Fn(Mismatch),
+}
+
+impl Verifier<'_> {
+    /// Beat, and report whether the run should continue
+    fn tick(&self) -> bool {
+        self.heartbeat.beat();
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Compare one location, reporting it if it differs; returns whether it matched
+    fn compare(&self, offset: usize, expected: u64, actual: u64) -> bool {
+        if actual == expected {
+            return true;
+        }
+        (self.report)(Mismatch {
+            offset,
+            expected,
+            actual,
+        });
+        false
+    }
+}
+
+/// Outcome of a pass that found `bad` mismatching locations
+fn verdict(bad: usize) -> Result<(), Corrupted> {
+    if bad == 0 {
+        Ok(())
+    } else {
+        Err(Corrupted(bad))
+    }
+}
 
 /// Run memory stress test with multiple patterns
-/// allocation_bytes: how much memory this thread should allocate
+/// worker: index of this worker, for its buffer statistics
+/// buffer: how much memory this thread should allocate, and how to back it
+/// rotation: the workloads to cycle through, repeated by weight
 pub fn run_memory_stress(
-    running: Arc<AtomicBool>,
-    errors: Arc<AtomicU64>,
-    allocation_bytes: usize,
+    running: &AtomicBool,
+    shared: &MemoryShared,
+    worker: usize,
+    buffer: BufferSpec,
+    rotation: &[MemoryWorkload],
+    throughput: &WorkerThroughput,
+    heartbeat: &Heartbeat,
 ) {
     let mut iteration: u64 = 0;
 
-    // Allocate memory buffer (at least MIN_CHUNK_SIZE)
-    let alloc_size = allocation_bytes.max(MIN_CHUNK_SIZE);
-    let mut buffer: Vec<u8> = vec![0; alloc_size];
+    // Allocate a locked memory buffer
+    let spec = buffer;
+    let mut buffer = match TestBuffer::new(spec) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            eprintln!(
+                "Failed to allocate {} bytes for memory test: {}",
+                spec.bytes, e
+            );
+            shared.errors.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    };
+    shared.record_buffer(worker, buffer.size(), buffer.residency(), spec.hugepages);
+    let words = buffer.words();
+    let base = words.as_ptr() as usize;
 
     while running.load(Ordering::Relaxed) {
-        // Rotate between different stress methods
-        match iteration % 4 {
-            0 => {
-                if !run_sequential_stress(&mut buffer) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            1 => {
-                if !run_random_access_stress(&mut buffer) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            2 => {
-                if !run_fill_verify_stress(&mut buffer) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            3 => {
-                if !run_stream_stress(&mut buffer) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
+        heartbeat.beat();
+
+        // Rotate between the selected stress methods
+        let workload = rotation[(iteration % rotation.len() as u64) as usize];
+        let report = |mismatch| shared.record_mismatch(workload, base, mismatch);
+        let verifier = Verifier {
+            running,
+            heartbeat,
+            report: &report,
+        };
+        let seed: u64 = rand::random();
+        let started = Instant::now();
+        match run_workload(workload, words, seed, &verifier, &shared.bench) {
+            Err(_) => shared.record(workload, false),
+            // A pattern cut short by the end of the run does not count as a pass
+            Ok(()) if !running.load(Ordering::Relaxed) => break,
+            Ok(()) => {
+                shared.record(workload, true);
+                throughput.record(workload, started.elapsed());
             }
-            _ => unreachable!(),
         }
 
         iteration = iteration.wrapping_add(1);
     }
 }
 
-/// Sequential access pattern - tests bandwidth
-fn run_sequential_stress(buffer: &mut [u8]) -> bool {
-    // Write sequential pattern
-    for (i, byte) in buffer.iter_mut().enumerate() {
-        *byte = (i & 0xFF) as u8;
+fn run_workload(
+    workload: MemoryWorkload,
+    words: &mut [u64],
+    seed: u64,
+    v: &Verifier,
+    bench: &BenchStats,
+) -> Result<(), Corrupted> {
+    match workload {
+        MemoryWorkload::Sequential => run_sequential_stress(as_bytes_mut(words), v),
+        MemoryWorkload::Random => run_random_access_stress(as_bytes_mut(words), seed, v),
+        MemoryWorkload::Fill => run_fill_verify_stress(as_bytes_mut(words), v),
+        MemoryWorkload::Stream => run_stream_stress(words, seed, v, bench),
+        MemoryWorkload::WalkingOnes => run_walking_bits(words, true, v),
+        MemoryWorkload::WalkingZeros => run_walking_bits(words, false, v),
+        MemoryWorkload::Checkerboard => run_checkerboard(words, v),
+        MemoryWorkload::BitSpread => run_bit_spread(words, v),
+        MemoryWorkload::MovingInversions => run_moving_inversions(words, v),
+        MemoryWorkload::Address => run_address_in_address(words, v),
+        MemoryWorkload::SeededRandom => run_seeded_random(words, seed, v),
+        MemoryWorkload::Latency => run_latency(words, seed, v, bench),
+        MemoryWorkload::Coherency => run_coherency(words, seed, v),
+        MemoryWorkload::RowHammer => run_row_hammer(words, seed, v),
     }
+}
 
-    // Verify sequential pattern
-    for (i, &byte) in buffer.iter().enumerate() {
-        if byte != (i & 0xFF) as u8 {
-            return false;
-        }
+/// View a word buffer as bytes
+fn as_bytes_mut(words: &mut [u64]) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8) }
+}
+
+/// SplitMix64 of `x`, so any word of a random fill can be regenerated from its seed
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Write `value(i)` to every word
+/// Volatile so the compiler cannot skip the store and reload.
+fn fill_words(words: &mut [u64], value: impl Fn(usize) -> u64) {
+    for (i, word) in words.iter_mut().enumerate() {
+        unsafe { std::ptr::write_volatile(word, value(i)) };
     }
+}
 
-    true
+/// Compare every word against `expected(i)`; `first` is the word index of
+/// `words[0]` within the buffer
+fn check_words(
+    words: &[u64],
+    first: usize,
+    expected: impl Fn(usize) -> u64,
+    v: &Verifier,
+) -> Result<(), Corrupted> {
+    let mut bad = 0;
+    for (c, chunk) in words.chunks(TICK_BYTES / 8).enumerate() {
+        if !v.tick() {
+            break;
+        }
+        let start = c * TICK_BYTES / 8;
+        bad += chunk
+            .iter()
+            .enumerate()
+            .filter(|&(i, word)| {
+                let actual = unsafe { std::ptr::read_volatile(word) };
+                !v.compare((first + start + i) * 8, expected(start + i), actual)
+            })
+            .count();
+    }
+    verdict(bad)
 }
 
-/// Random access pattern - tests cache behavior and memory latency
-fn run_random_access_stress(buffer: &mut [u8]) -> bool {
-    let mut rng = rand::thread_rng();
-    let len = buffer.len();
+/// Fill, then verify, one pattern over the whole buffer
+fn write_and_check(
+    words: &mut [u64],
+    value: impl Fn(usize) -> u64,
+    v: &Verifier,
+) -> Result<(), Corrupted> {
+    fill_words(words, &value);
+    check_words(words, 0, value, v)
+}
 
-    // Number of random accesses
-    const ITERATIONS: usize = 100_000;
+/// Compare every byte against `expected(i)`
+fn check_bytes(
+    buffer: &[u8],
+    expected: impl Fn(usize) -> u8,
+    v: &Verifier,
+) -> Result<(), Corrupted> {
+    let mut bad = 0;
+    for (c, chunk) in buffer.chunks(TICK_BYTES).enumerate() {
+        if !v.tick() {
+            break;
+        }
+        let start = c * TICK_BYTES;
+        bad += chunk
+            .iter()
+            .enumerate()
+            .filter(|&(i, &byte)| !v.compare(start + i, expected(start + i) as u64, byte as u64))
+            .count();
+    }
+    verdict(bad)
+}
 
-    // Random writes
-    for _ in 0..ITERATIONS {
-        let idx = rng.gen_range(0..len);
-        let value = rng.gen::<u8>();
-        buffer[idx] = value;
+/// Sequential access pattern - tests bandwidth
+fn run_sequential_stress(buffer: &mut [u8], v: &Verifier) -> Result<(), Corrupted> {
+    // Write sequential pattern
+    for (c, chunk) in buffer.chunks_mut(TICK_BYTES).enumerate() {
+        if !v.tick() {
+            return Ok(());
+        }
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = ((c * TICK_BYTES + i) & 0xFF) as u8;
+        }
     }
 
-    // Random reads and verify they're consistent
-    let mut checksum: u64 = 0;
-    for _ in 0..ITERATIONS {
-        let idx = rng.gen_range(0..len);
-        checksum = checksum.wrapping_add(buffer[idx] as u64);
+    // Verify sequential pattern
+    check_bytes(buffer, |i| (i & 0xFF) as u8, v)
+}
+
+/// Random access pattern - tests cache behavior and memory latency
+/// Each written byte depends only on its index, so repeated hits stay consistent.
+fn run_random_access_stress(buffer: &mut [u8], seed: u64, v: &Verifier) -> Result<(), Corrupted> {
+    let len = buffer.len() as u64;
+    let index = |n: usize| (splitmix64(seed.wrapping_add(n as u64)) % len) as usize;
+    let value = |i: usize| splitmix64(!seed ^ i as u64) as u8;
+
+    // Random writes
+    for n in 0..RANDOM_ACCESSES {
+        let i = index(n);
+        buffer[i] = value(i);
     }
 
-    // Just ensure we read something (checksum is used to prevent optimization)
-    checksum > 0 || buffer.iter().all(|&b| b == 0)
+    // Read the same locations back in the same order
+    let bad = (0..RANDOM_ACCESSES)
+        .map(index)
+        .filter(|&i| !v.compare(i, value(i) as u64, buffer[i] as u64))
+        .count();
+    verdict(bad)
 }
 
 /// Fill and verify pattern - detects bit errors
-fn run_fill_verify_stress(buffer: &mut [u8]) -> bool {
+fn run_fill_verify_stress(buffer: &mut [u8], v: &Verifier) -> Result<(), Corrupted> {
     // Test multiple patterns
     let patterns: [u8; 4] = [0x00, 0xFF, 0xAA, 0x55];
 
     for pattern in patterns {
-        // Fill with pattern
+        if !v.tick() {
+            break;
+        }
         buffer.fill(pattern);
+        check_bytes(buffer, |_| pattern, v)?;
+    }
 
-        // Verify pattern
-        for &byte in buffer.iter() {
-            if byte != pattern {
-                return false;
-            }
+    Ok(())
+}
+
+/// STREAM memory bandwidth test
+/// Times copy, scale, add and triad over three thirds of the buffer and
+/// checks every result against values recomputed from the seed.
+fn run_stream_stress(
+    words: &mut [u64],
+    seed: u64,
+    v: &Verifier,
+    bench: &BenchStats,
+) -> Result<(), Corrupted> {
+    const SCALAR: u64 = 3;
+    let n = words.len() / 3;
+    let (a, rest) = words.split_at_mut(n);
+    let (b, c) = rest.split_at_mut(n);
+    let c = &mut c[..n];
+    let x = |i: usize| splitmix64(seed.wrapping_add(i as u64));
+
+    for (i, av) in a.iter_mut().enumerate() {
+        *av = x(i);
+    }
+
+    // GB/s counts every array read and written, as STREAM does
+    let array_bytes = (n * 8) as f64;
+    let timed = |kernel: usize, arrays: f64, f: &mut dyn FnMut()| {
+        let started = Instant::now();
+        f();
+        let secs = started.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            bench.record_bandwidth(kernel, arrays * array_bytes / secs / 1e9);
+        }
+    };
+
+    // Copy: c = a
+    timed(0, 2.0, &mut || c.copy_from_slice(a));
+
+    // Scale: b = scalar * c
+    timed(1, 2.0, &mut || {
+        for (bv, &cv) in b.iter_mut().zip(c.iter()) {
+            *bv = SCALAR.wrapping_mul(cv);
+        }
+    });
+
+    // Add: c = a + b
+    timed(2, 3.0, &mut || {
+        for ((cv, &av), &bv) in c.iter_mut().zip(a.iter()).zip(b.iter()) {
+            *cv = av.wrapping_add(bv);
+        }
+    });
+
+    // Triad: a = b + scalar * c
+    timed(3, 3.0, &mut || {
+        for ((av, &bv), &cv) in a.iter_mut().zip(b.iter()).zip(c.iter()) {
+            *av = bv.wrapping_add(SCALAR.wrapping_mul(cv));
         }
+    });
+
+    let scaled = |i: usize| SCALAR.wrapping_mul(x(i));
+    let added = |i: usize| x(i).wrapping_add(scaled(i));
+    let triad = |i: usize| scaled(i).wrapping_add(SCALAR.wrapping_mul(added(i)));
+    check_words(b, n, scaled, v)?;
+    check_words(c, 2 * n, added, v)?;
+    check_words(a, 0, triad, v)
+}
+
+/// Pointer-chasing latency test at each working-set size that fits the buffer
+/// Links one word per cache line into a single random cycle (Sattolo), so
+/// every load depends on the previous one and prefetchers cannot help. After
+/// timing, every link is checked, which also makes this a memory test.
+fn run_latency(
+    words: &mut [u64],
+    seed: u64,
+    v: &Verifier,
+    bench: &BenchStats,
+) -> Result<(), Corrupted> {
+    for (level, &bytes) in LATENCY_LEVELS.iter().enumerate() {
+        let ws_words = bytes / 8;
+        if ws_words > words.len() || !v.tick() {
+            break;
+        }
+
+        let lines = ws_words / LINE_WORDS;
+        let mut next: Vec<u32> = (0..lines as u32).collect();
+        for i in (1..lines).rev() {
+            let j = (splitmix64(seed.wrapping_add(i as u64)) % i as u64) as usize;
+            next.swap(i, j);
+        }
+        for (line, &to) in next.iter().enumerate() {
+            words[line * LINE_WORDS] = to as u64 * LINE_WORDS as u64;
+        }
+
+        // The mask keeps a corrupted link inside the working set
+        let mask = ws_words - 1;
+        let chain = &words[..ws_words];
+        let started = Instant::now();
+        let mut at = 0usize;
+        for _ in 0..CHASE_STEPS {
+            at = chain[at] as usize & mask;
+        }
+        let elapsed = started.elapsed();
+        std::hint::black_box(at);
+        bench.record_latency(level, elapsed.as_nanos() as f64 / CHASE_STEPS as f64);
+
+        let bad = next
+            .iter()
+            .enumerate()
+            .filter(|&(line, &to)| {
+                let i = line * LINE_WORDS;
+                !v.compare(i * 8, to as u64 * LINE_WORDS as u64, chain[i])
+            })
+            .count();
+        verdict(bad)?;
+    }
+    Ok(())
+}
+
+/// Sequence numbers of the blocks in each slot and how many consumers have checked them
+struct Handoff {
+    published: Vec<AtomicU64>,
+    acks: Vec<AtomicU64>,
+    /// Set by the producer when it has published its last block (or stopped)
+    done: AtomicBool,
+}
+
+/// Value of word `i` in the block published as `seq`; the sequence number
+/// sits in the top half so stale data from an earlier block stands out
+fn handoff_word(seed: u64, seq: u64, i: usize) -> u64 {
+    seq << 32 | (splitmix64(seed.wrapping_add(seq) ^ i as u64) & 0xFFFF_FFFF)
+}
+
+/// Spin until `ready`, yielding the core now and then; false if `stop` came first
+fn wait_until(ready: impl Fn() -> bool, stop: impl Fn() -> bool) -> bool {
+    let mut spins = 0u32;
+    while !ready() {
+        if stop() {
+            return false;
+        }
+        spins += 1;
+        if spins.is_multiple_of(SPINS_BEFORE_YIELD) {
+            std::thread::yield_now();
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+    true
+}
+
+/// Cross-core cache coherency test
+/// A producer thread fills blocks with sequence-tagged data and publishes
+/// each with a release store; consumer threads pinned to other cores pick
+/// them up with acquire loads and verify every word. Any stale or corrupt
+/// word means a store did not reach the other core intact.
+fn run_coherency(words: &mut [u64], seed: u64, v: &Verifier) -> Result<(), Corrupted> {
+    let cores = affinity::allowed_cpus();
+    let first = (seed % cores.len() as u64) as usize;
+    let producer_core = cores[first];
+    let mut consumer_cores: Vec<usize> = (1..cores.len())
+        .map(|k| cores[(first + k) % cores.len()])
+        .take(MAX_CONSUMERS)
+        .collect();
+    if consumer_cores.is_empty() {
+        consumer_cores.push(producer_core);
     }
+    let consumers = consumer_cores.len() as u64;
+
+    let block = HANDOFF_BLOCK_WORDS.min(words.len());
+    let slots = (words.len() / block).min(HANDOFF_SLOTS);
+    // Safety: AtomicU64 has the same layout as u64, and `words` is borrowed mutably
+    let data = unsafe { &*(&mut words[..slots * block] as *mut [u64] as *const [AtomicU64]) };
+    let handoff = Handoff {
+        published: (0..slots).map(|_| AtomicU64::new(0)).collect(),
+        acks: (0..slots).map(|_| AtomicU64::new(0)).collect(),
+        done: AtomicBool::new(false),
+    };
+    let (running, heartbeat, handoff) = (v.running, v.heartbeat, &handoff);
+
+    let results: Vec<(usize, Vec<Mismatch>)> = std::thread::scope(|s| {
+        let checkers: Vec<_> = consumer_cores
+            .iter()
+            .map(|&core| {
+                s.spawn(move || {
+                    affinity::pin_current_thread(core);
+                    let (mut bad, mut found) = (0, Vec::new());
+                    for seq in 1..=HANDOFFS {
+                        let slot = (seq % slots as u64) as usize;
+                        let published = || handoff.published[slot].load(Ordering::Acquire) == seq;
+                        let stopped = || handoff.done.load(Ordering::Acquire) && !published();
+                        if !wait_until(published, stopped) {
+                            break;
+                        }
+                        bad += check_handoff(
+                            &data[slot * block..][..block],
+                            slot * block,
+                            seed,
+                            seq,
+                            &mut found,
+                        );
+                        handoff.acks[slot].fetch_add(1, Ordering::Release);
+                    }
+                    (bad, found)
+                })
+            })
+            .collect();
+
+        s.spawn(move || {
+            affinity::pin_current_thread(producer_core);
+            for seq in 1..=HANDOFFS {
+                let slot = (seq % slots as u64) as usize;
+                let free = || {
+                    seq <= slots as u64 || handoff.acks[slot].load(Ordering::Acquire) == consumers
+                };
+                if !wait_until(free, || !running.load(Ordering::Relaxed)) {
+                    break;
+                }
+                handoff.acks[slot].store(0, Ordering::Relaxed);
+                for (i, word) in data[slot * block..][..block].iter().enumerate() {
+                    word.store(handoff_word(seed, seq, i), Ordering::Relaxed);
+                }
+                handoff.published[slot].store(seq, Ordering::Release);
+                if seq.is_multiple_of(slots as u64) {
+                    heartbeat.beat();
+                }
+            }
+            handoff.done.store(true, Ordering::Release);
+        });
 
-    // Walking ones test on a smaller portion
-    let test_size = 4096.min(buffer.len());
-    for bit in 0..8 {
-        let pattern = 1u8 << bit;
-        for byte in buffer[..test_size].iter_mut() {
-            *byte = pattern;
+        checkers.into_iter().map(|c| c.join().unwrap()).collect()
+    });
+
+    // Report from this thread; consumers only collect
+    let mut bad = 0;
+    for (count, found) in results {
+        bad += count;
+        for m in found {
+            v.compare(m.offset, m.expected, m.actual);
         }
-        for &byte in buffer[..test_size].iter() {
-            if byte != pattern {
-                return false;
+    }
+    verdict(bad)
+}
+
+/// Verify one handed-off block, keeping the first MAX_ERROR_RECORDS mismatches
+/// first: word index of the block within the buffer
+fn check_handoff(
+    block: &[AtomicU64],
+    first: usize,
+    seed: u64,
+    seq: u64,
+    found: &mut Vec<Mismatch>,
+) -> usize {
+    let mut bad = 0;
+    for (i, word) in block.iter().enumerate() {
+        let (expected, actual) = (handoff_word(seed, seq, i), word.load(Ordering::Relaxed));
+        if actual != expected {
+            bad += 1;
+            if found.len() < MAX_ERROR_RECORDS {
+                found.push(Mismatch {
+                    offset: (first + i) * 8,
+                    expected,
+                    actual,
+                });
             }
         }
     }
+    bad
+}
+
+/// Row-hammer disturbance test
+/// Fills the buffer with a victim pattern, then repeatedly reads pairs of
+/// random lines in one 2 MiB region, flushing them from the cache after each
+/// read so every access re-opens a DRAM row. Row mapping is not known, so
+/// random pairs stand in for same-bank aggressors (as in Google's
+/// rowhammer-test). Any flip left in the buffer afterwards is reported.
+fn run_row_hammer(words: &mut [u64], seed: u64, v: &Verifier) -> Result<(), Corrupted> {
+    let pattern = HAMMER_PATTERNS[(seed % HAMMER_PATTERNS.len() as u64) as usize];
+    fill_words(words, |_| pattern);
+
+    let lines = words.len() / LINE_WORDS;
+    let region_lines = (HAMMER_REGION / 64).min(lines);
+    let regions = lines / region_lines;
+    let pairs = regions.clamp(1, HAMMER_PAIRS);
+    for pair in 0..pairs as u64 {
+        if !v.tick() {
+            break;
+        }
+        let pick = |k: u64| splitmix64(seed ^ (pair << 8 | k));
+        let region = (pick(0) % regions as u64) as usize * region_lines;
+        let a = region + (pick(1) % region_lines as u64) as usize;
+        let b = region + (pick(2) % region_lines as u64) as usize;
+        hammer(
+            &words[a * LINE_WORDS],
+            &words[b * LINE_WORDS],
+            HAMMER_WINDOW,
+        );
+    }
 
-    true
+    check_words(words, 0, |_| pattern, v)
 }
 
-/// STREAM-like stress - memory bandwidth test
-fn run_stream_stress(buffer: &mut [u8]) -> bool {
-    // Treat as u64 for better bandwidth
-    let len = buffer.len() / 8;
+/// Alternately read two locations straight from DRAM for `window`
+fn hammer(a: &u64, b: &u64, window: Duration) {
+    let started = Instant::now();
+    while started.elapsed() < window {
+        for _ in 0..HAMMER_BATCH {
+            unsafe {
+                std::ptr::read_volatile(a);
+                std::ptr::read_volatile(b);
+            }
+            flush_line(a);
+            flush_line(b);
+        }
+    }
+}
 
-    // Safety: buffer is aligned and large enough
-    let buffer_u64 =
-        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u64, len) };
+/// Clean and invalidate the cache line holding `p`, so the next read goes to DRAM
+/// Without a user-space flush instruction this is a no-op and hammering only
+/// exercises the cache.
+#[inline(always)]
+fn flush_line(p: &u64) {
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("dc civac, {0}", "dsb ish", in(reg) p, options(nostack, preserves_flags));
+    }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::x86_64::_mm_clflush(p as *const u64 as *const u8);
+    }
+    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+    let _ = p;
+}
 
-    // Copy operation
-    let scalar: u64 = 3;
-    for i in 0..len / 2 {
-        buffer_u64[i + len / 2] = buffer_u64[i];
+/// Walking ones (or zeros): a single set (or cleared) bit that moves one
+/// position per pass and differs between neighbouring words
+fn run_walking_bits(words: &mut [u64], ones: bool, v: &Verifier) -> Result<(), Corrupted> {
+    for bit in 0..64 {
+        if !v.tick() {
+            break;
+        }
+        let walking = |i: usize| {
+            let walker = 1u64 << ((i + bit) % 64);
+            if ones {
+                walker
+            } else {
+                !walker
+            }
+        };
+        write_and_check(words, walking, v)?;
     }
+    Ok(())
+}
 
-    // Scale operation
-    for elem in buffer_u64.iter_mut() {
-        *elem = elem.wrapping_mul(scalar);
+/// Alternating 0101/1010 words, then the inverse
+fn run_checkerboard(words: &mut [u64], v: &Verifier) -> Result<(), Corrupted> {
+    for phase in [CHECKERBOARD, !CHECKERBOARD] {
+        if !v.tick() {
+            break;
+        }
+        write_and_check(words, |i| if i % 2 == 0 { phase } else { !phase }, v)?;
     }
+    Ok(())
+}
 
-    // Add operation
-    for i in 0..len / 2 {
-        buffer_u64[i] = buffer_u64[i].wrapping_add(buffer_u64[i + len / 2]);
+/// Two set bits two positions apart, swept up and back down the word,
+/// alternating with their complement in neighbouring words
+fn run_bit_spread(words: &mut [u64], v: &Verifier) -> Result<(), Corrupted> {
+    for step in 0..124 {
+        if !v.tick() {
+            break;
+        }
+        let k = if step < 62 { step } else { 123 - step };
+        let spread = (1u64 << k) | (1u64 << (k + 2));
+        write_and_check(words, |i| if i % 2 == 0 { spread } else { !spread }, v)?;
+    }
+    Ok(())
+}
+
+/// Moving inversions: fill with a pattern, then invert word by word going
+/// up and restore word by word going down, checking each word before it changes
+fn run_moving_inversions(words: &mut [u64], v: &Verifier) -> Result<(), Corrupted> {
+    let patterns = MOVING_INVERSION_PATTERNS.iter().flat_map(|&p| [p, !p]);
+    for pattern in patterns {
+        let mut bad = 0;
+        for range in tick_chunks(words.len()) {
+            if !v.tick() {
+                return verdict(bad);
+            }
+            fill_words(&mut words[range], |_| pattern);
+        }
+        for range in tick_chunks(words.len()) {
+            if !v.tick() {
+                return verdict(bad);
+            }
+            for i in range {
+                bad += check_and_replace(words, i, pattern, !pattern, v) as usize;
+            }
+        }
+        for range in tick_chunks(words.len()).rev() {
+            if !v.tick() {
+                return verdict(bad);
+            }
+            for i in range.rev() {
+                bad += check_and_replace(words, i, !pattern, pattern, v) as usize;
+            }
+        }
+        verdict(bad)?;
     }
+    Ok(())
+}
+
+/// Word ranges of `TICK_BYTES` each covering `0..len`, for sweeps that must
+/// run in address order
+fn tick_chunks(len: usize) -> impl DoubleEndedIterator<Item = std::ops::Range<usize>> {
+    const TICK_WORDS: usize = TICK_BYTES / 8;
+    (0..len)
+        .step_by(TICK_WORDS)
+        .map(move |start| start..(start + TICK_WORDS).min(len))
+}
+
+/// Verify word `i` holds `expected`, then overwrite it with `next`
+/// Returns true if the word was wrong.
+fn check_and_replace(words: &mut [u64], i: usize, expected: u64, next: u64, v: &Verifier) -> bool {
+    let word = &mut words[i];
+    let actual = unsafe { std::ptr::read_volatile(word) };
+    unsafe { std::ptr::write_volatile(word, next) };
+    !v.compare(i * 8, expected, actual)
+}
 
-    // Triad operation: a = b + scalar * c
-    for i in 0..len / 3 {
-        buffer_u64[i] =
-            buffer_u64[i + len / 3].wrapping_add(scalar.wrapping_mul(buffer_u64[i + 2 * len / 3]));
+/// Each word holds its own virtual address, then the complement, which
+/// catches address lines that alias two locations
+fn run_address_in_address(words: &mut [u64], v: &Verifier) -> Result<(), Corrupted> {
+    let base = words.as_ptr() as u64;
+    for invert in [0, u64::MAX] {
+        if !v.tick() {
+            break;
+        }
+        write_and_check(words, |i| (base + i as u64 * 8) ^ invert, v)?;
     }
+    Ok(())
+}
 
-    // Just verify something was done
-    buffer_u64.iter().any(|&v| v != 0) || buffer_u64.iter().all(|&v| v == 0)
+/// Pseudo-random fill regenerated from the seed for verification
+fn run_seeded_random(words: &mut [u64], seed: u64, v: &Verifier) -> Result<(), Corrupted> {
+    write_and_check(words, |i| splitmix64(seed.wrapping_add(i as u64)), v)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::stress::workload::WorkloadMix;
+    use std::cell::RefCell;
+
+    /// Run `f` with a verifier that never stops; returns its result and
+    /// every reported mismatch
+    fn verify<T>(f: impl FnOnce(&Verifier) -> T) -> (T, Vec<Mismatch>) {
+        let running = AtomicBool::new(true);
+        let heartbeat = Heartbeat::new();
+        let found = RefCell::new(Vec::new());
+        let report = |m| found.borrow_mut().push(m);
+        let result = f(&Verifier {
+            running: &running,
+            heartbeat: &heartbeat,
+            report: &report,
+        });
+        (result, found.into_inner())
+    }
 
     #[test]
     fn test_sequential_stress() {
         let mut buffer = vec![0u8; 1024];
-        assert!(run_sequential_stress(&mut buffer));
+        assert_eq!(verify(|v| run_sequential_stress(&mut buffer, v)).0, Ok(()));
     }
 
     #[test]
     fn test_random_access_stress() {
         let mut buffer = vec![0u8; 1024];
-        assert!(run_random_access_stress(&mut buffer));
+        assert_eq!(
+            verify(|v| run_random_access_stress(&mut buffer, 7, v)).0,
+            Ok(())
+        );
     }
 
     #[test]
     fn test_fill_verify_stress() {
         let mut buffer = vec![0u8; 8192];
-        assert!(run_fill_verify_stress(&mut buffer));
+        assert_eq!(verify(|v| run_fill_verify_stress(&mut buffer, v)).0, Ok(()));
+    }
+
+    #[test]
+    fn test_byte_passes_stop_with_the_run() {
+        let running = AtomicBool::new(false);
+        let heartbeat = Heartbeat::new();
+        let report = |m: Mismatch| panic!("checked after the stop: {:?}", m);
+        let v = Verifier {
+            running: &running,
+            heartbeat: &heartbeat,
+            report: &report,
+        };
+        let mut buffer = vec![0x42u8; 8192];
+        assert_eq!(run_fill_verify_stress(&mut buffer, &v), Ok(()));
+        assert_eq!(run_sequential_stress(&mut buffer, &v), Ok(()));
+        assert_eq!(check_bytes(&buffer, |_| 0, &v), Ok(()));
+        assert!(buffer.iter().all(|&b| b == 0x42));
+    }
+
+    #[test]
+    fn test_moving_inversions_stop_with_the_run() {
+        let running = AtomicBool::new(false);
+        let heartbeat = Heartbeat::new();
+        let report = |m: Mismatch| panic!("checked after the stop: {:?}", m);
+        let v = Verifier {
+            running: &running,
+            heartbeat: &heartbeat,
+            report: &report,
+        };
+        let mut words = vec![0x42u64; 1024];
+        assert_eq!(run_moving_inversions(&mut words, &v), Ok(()));
+        assert!(words.iter().all(|&w| w == 0x42));
+
+        let words = TICK_BYTES / 8;
+        let chunks: Vec<_> = tick_chunks(2 * words + 5).rev().collect();
+        assert_eq!(
+            chunks,
+            [2 * words..2 * words + 5, words..2 * words, 0..words]
+        );
     }
 
     #[test]
     fn test_stream_stress() {
-        let mut buffer = vec![0u8; 8192];
-        assert!(run_stream_stress(&mut buffer));
+        let mut words = vec![0u64; 1024];
+        let bench = BenchStats::default();
+        assert_eq!(
+            verify(|v| run_stream_stress(&mut words, 42, v, &bench)).0,
+            Ok(())
+        );
+        let measured = bench.snapshot(false).unwrap();
+        assert!(measured.triad_gbps.unwrap() > 0.0);
+        assert_eq!(measured.l1_ns, None);
+    }
+
+    #[test]
+    fn test_every_workload_passes_on_good_memory() {
+        let mut words = vec![0u64; 4096];
+        for workload in MemoryWorkload::ALL {
+            let bench = BenchStats::default();
+            let (result, found) = verify(|v| run_workload(workload, &mut words, 1234, v, &bench));
+            assert_eq!(result, Ok(()), "{}", workload.name());
+            assert!(found.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_check_words_reports_every_mismatch() {
+        let mut words = vec![0u64; 64];
+        let expected = |i: usize| splitmix64(9 + i as u64);
+        fill_words(&mut words, expected);
+        words[17] ^= 1 << 5;
+        words[40] ^= 0b11;
+
+        let (result, found) = verify(|v| check_words(&words, 100, expected, v));
+        assert_eq!(result, Err(Corrupted(2)));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].offset, (100 + 17) * 8);
+        assert_eq!(found[0].expected ^ found[0].actual, 1 << 5);
+        assert_eq!(found[1].offset, (100 + 40) * 8);
+    }
+
+    #[test]
+    fn test_memory_error_details() {
+        let mismatch = Mismatch {
+            offset: 0x40,
+            expected: 0xFF00,
+            actual: 0xFF00 ^ (1 << 3) ^ (1 << 60),
+        };
+        let error = MemoryError::new(MemoryWorkload::BitSpread, 0x1000, mismatch);
+        assert_eq!(error.virtual_address, 0x1040);
+        assert_eq!(error.flipped_bits, vec![3, 60]);
+        assert!(error.timestamp_ms > 0);
+        assert!(error.to_string().starts_with("bit-spread at 0x1040"));
+        assert!(error.to_string().ends_with("bits 3,60"));
+    }
+
+    #[test]
+    fn test_mismatches_reach_error_log() {
+        let shared = MemoryShared::new(1);
+        let words = vec![1u64; 8];
+        let running = AtomicBool::new(true);
+        let heartbeat = Heartbeat::new();
+        let base = words.as_ptr() as usize;
+        let report = |m| shared.record_mismatch(MemoryWorkload::SeededRandom, base, m);
+        let v = Verifier {
+            running: &running,
+            heartbeat: &heartbeat,
+            report: &report,
+        };
+
+        assert_eq!(check_words(&words, 0, |_| 0, &v), Err(Corrupted(8)));
+        let records = shared.error_log.records();
+        assert_eq!(records.len(), 8);
+        assert_eq!(records[3].offset, 24);
+        assert_eq!(records[3].flipped_bits, vec![0]);
+    }
+
+    #[test]
+    fn test_stopped_pattern_ends_early() {
+        let running = AtomicBool::new(false);
+        let heartbeat = Heartbeat::new();
+        let v = Verifier {
+            running: &running,
+            heartbeat: &heartbeat,
+            report: &|_| {},
+        };
+        let mut words = vec![7u64; 16];
+        assert_eq!(run_walking_bits(&mut words, true, &v), Ok(()));
+        assert!(words.iter().all(|&w| w == 7));
+    }
+
+    #[test]
+    fn test_pattern_summary_counts() {
+        let shared = MemoryShared::new(1);
+        shared.record(MemoryWorkload::Checkerboard, true);
+        shared.record(MemoryWorkload::Checkerboard, true);
+        shared.record(MemoryWorkload::BitSpread, false);
+
+        let summary = shared.pattern_summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].to_string(), "checkerboard 2");
+        assert_eq!(summary[1].to_string(), "bit-spread 0 (1 failed)");
+        assert_eq!(shared.errors.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_buffer_summary_replaces_restarted_worker() {
+        let shared = MemoryShared::new(2);
+        assert_eq!(shared.buffer_summary(HugePages::Transparent), None);
+
+        let residency = Residency {
+            locked_bytes: 64 << 20,
+            thp_bytes: 32 << 20,
+            hugetlb_bytes: 0,
+        };
+        let thp = HugePages::Transparent;
+        shared.record_buffer(0, 64 << 20, residency, thp);
+        shared.record_buffer(0, 64 << 20, residency, thp);
+        shared.record_buffer(1, 64 << 20, Residency::default(), thp);
+
+        let summary = shared.buffer_summary(HugePages::Transparent).unwrap();
+        assert_eq!(summary.allocated_bytes, 128 << 20);
+        assert_eq!(summary.locked_bytes, 64 << 20);
+        assert_eq!(
+            summary.to_string(),
+            "128 MB allocated, 64 MB locked, 32 MB in huge pages"
+        );
+    }
+
+    #[test]
+    fn test_latency_measures_levels_that_fit() {
+        // Room for the L1 and L2 working sets only
+        let mut words = vec![0u64; (256 << 10) / 8];
+        let bench = BenchStats::default();
+        let (result, found) = verify(|v| run_latency(&mut words, 5, v, &bench));
+        assert_eq!(result, Ok(()));
+        assert!(found.is_empty());
+
+        let measured = bench.snapshot(true).unwrap();
+        assert!(measured.l1_ns.unwrap() > 0.0);
+        assert!(measured.l2_ns.unwrap() > 0.0);
+        assert_eq!(measured.dram_ns, None);
+        assert!(measured.latency().unwrap().starts_with("L1 "));
+        assert_eq!(measured.bandwidth(), None);
+    }
+
+    #[test]
+    fn test_bench_keeps_best_and_latest() {
+        let bench = BenchStats::default();
+        assert_eq!(bench.snapshot(false), None);
+
+        bench.record_bandwidth(3, 6.0);
+        bench.record_bandwidth(3, 4.5);
+        bench.record_latency(3, 120.0);
+        bench.record_latency(3, 100.0);
+
+        let latest = bench.snapshot(false).unwrap();
+        let best = bench.snapshot(true).unwrap();
+        assert_eq!(latest.triad_gbps, Some(4.5));
+        assert_eq!(best.triad_gbps, Some(6.0));
+        assert_eq!(best.dram_ns, Some(100.0));
+        assert_eq!(best.bandwidth().as_deref(), Some("triad 6.0 GB/s"));
+        assert_eq!(best.latency().as_deref(), Some("DRAM 100.0 ns"));
+    }
+
+    #[test]
+    fn test_row_hammer_leaves_good_memory_intact() {
+        // Two regions' worth of buffer, one pass per victim pattern
+        let mut words = vec![0u64; 2 * HAMMER_REGION / 8];
+        for seed in 0..HAMMER_PATTERNS.len() as u64 {
+            let (result, found) = verify(|v| run_row_hammer(&mut words, seed, v));
+            assert_eq!(result, Ok(()));
+            assert!(found.is_empty());
+            assert!(words.iter().all(|&w| w == HAMMER_PATTERNS[seed as usize]));
+        }
+    }
+
+    #[test]
+    fn test_coherency_handoff() {
+        let mut words = vec![0u64; HANDOFF_SLOTS * HANDOFF_BLOCK_WORDS];
+        let (result, found) = verify(|v| run_coherency(&mut words, 3, v));
+        assert_eq!(result, Ok(()));
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_check_handoff_reports_stale_words() {
+        let block: Vec<AtomicU64> = (0..16)
+            .map(|i| AtomicU64::new(handoff_word(1, 7, i)))
+            .collect();
+        // Word 5 still holds the block published before
+        block[5].store(handoff_word(1, 6, 5), Ordering::Relaxed);
+
+        let mut found = Vec::new();
+        assert_eq!(check_handoff(&block, 32, 1, 7, &mut found), 1);
+        assert_eq!(found[0].offset, (32 + 5) * 8);
+        assert_eq!(found[0].actual >> 32, 6);
+    }
+
+    #[test]
+    fn test_row_hammer_is_opt_in() {
+        let default = WorkloadMix::<MemoryWorkload>::all().rotation();
+        assert!(!default.contains(&MemoryWorkload::RowHammer));
+        assert_eq!(default.len(), MemoryWorkload::ALL.len() - 1);
+
+        let mut mix: WorkloadMix<MemoryWorkload> = "stream,row-hammer:2".parse().unwrap();
+        mix.include(MemoryWorkload::RowHammer);
+        assert_eq!(mix.to_string(), "stream,row-hammer:2");
+    }
+
+    #[test]
+    fn test_workload_names_match_serde() {
+        for workload in MemoryWorkload::ALL {
+            let json = serde_json::to_string(&workload).unwrap();
+            assert_eq!(json, format!("\"{}\"", workload.name()));
+        }
     }
 }
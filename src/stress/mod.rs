@@ -1,15 +1,36 @@
+pub mod buffer;
+pub mod coverage;
 pub mod cpu;
+pub mod io_engine;
+pub mod latency;
 pub mod memory;
 pub mod nvme;
+pub mod shared;
+pub mod simd;
+pub mod supervisor;
+pub mod throughput;
+pub mod uring;
 pub mod video;
+pub mod workload;
 
-use crate::detection::{self, nvme::NvmeInfo};
+use crate::detection::{self, ras::RasCounts, storage::StorageDevice};
+use crate::system::affinity;
 use crate::system::monitor::{self, CpuStatSnapshot, FanStatus, ThrottleStatus};
 use serde::{Deserialize, Serialize};
+use shared::Shared;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use supervisor::{Supervisor, WorkerCrash, WorkerHang};
 use tokio::sync::mpsc;
+use workload::WorkloadMix;
+
+/// How long stopped workers get to finish their current iteration
+const WORKER_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Crash and hang records kept for the report (a core that faults on
+/// every iteration would otherwise add one per second)
+const MAX_FAILURE_RECORDS: usize = 64;
 
 #[derive(Debug, Clone)]
 pub struct StressConfig {
@@ -18,8 +39,25 @@ pub struct StressConfig {
     pub nvme: bool,
     pub video: bool,
     pub threads: usize,
+    pub cpu_mode: cpu::CpuMode,
+    /// Shortest and longest square-wave period for load-step mode
+    pub load_step_min: Duration,
+    pub load_step_max: Duration,
+    /// Weighted workload selections rotated by CPU and memory workers
+    pub cpu_workloads: WorkloadMix<cpu::CpuWorkload>,
+    pub memory_workloads: WorkloadMix<memory::MemoryWorkload>,
+    /// Memory to test and the number of workers sharing it, already sized
+    /// against RAM and cgroup limits (set when `memory` is)
+    pub memory_plan: Option<coverage::MemoryCoverage>,
+    /// Huge page backing for memory test buffers
+    pub hugepages: buffer::HugePages,
+    /// Fraction below the first-minute rate at which an unthrottled workload is flagged
+    pub throughput_drop: f64,
+    /// A worker silent for longer than this is declared hung
+    pub hang_timeout: Duration,
     pub duration: Duration,
-    pub nvme_path: Option<String>,
+    /// NVMe test file location, size and queue shape
+    pub nvme_options: nvme::NvmeOptions,
     /// Pre-detected video encoder (detect BEFORE TUI starts)
     pub video_encoder: Option<&'static str>,
 }
@@ -30,15 +68,25 @@ pub struct StressStats {
     pub cpu_temp_c: f32,
     pub cpu_temp_max: f32,
     pub cpu_freq_mhz: u32,
+    /// Current square-wave frequency in load-step mode
+    pub load_step_hz: Option<f32>,
     pub throttle_status: ThrottleStatus,
     pub cpu_usage_per_core: Vec<f32>,
     pub mem_used_mb: u64,
     pub mem_total_mb: u64,
-    pub nvme_temp_c: Option<f32>,
-    pub nvme_temp_max: Option<f32>,
-    pub nvme_test_path: Option<String>,
+    /// Temperature, test file and load of each storage target
+    pub storage: Vec<nvme::DeviceStats>,
     pub io_errors: u32,
     pub cpu_errors: u64,
+    pub cpu_core_errors: Vec<cpu::CoreErrors>,
+    pub linpack: Option<cpu::LinpackSummary>,
+    /// Latest STREAM bandwidth and pointer-chase latency
+    pub memory_bench: Option<memory::MemoryBench>,
+    pub throughput: Vec<throughput::WorkloadRate>,
+    /// Workers declared hung so far
+    pub hung_workers: Vec<WorkerHang>,
+    /// EDAC and kernel RAS errors since the run started
+    pub ras: RasCounts,
     pub memory_errors: u64,
     pub nvme_errors: u64,
     pub video_errors: u64,
@@ -52,6 +100,26 @@ pub struct TestResult {
     pub memory_errors: u64,
     pub nvme_errors: u64,
     pub video_errors: u64,
+    pub cpu_workloads: Option<WorkloadMix<cpu::CpuWorkload>>,
+    pub memory_workloads: Option<WorkloadMix<memory::MemoryWorkload>>,
+    pub memory_patterns: Vec<memory::PatternResult>,
+    /// Mismatching memory locations (first 256 in detail, all counted)
+    pub memory_error_records: Vec<memory::MemoryError>,
+    pub memory_mismatches: usize,
+    /// Locked and huge-page-backed memory actually granted to the memory workers
+    pub memory_buffers: Option<memory::BufferSummary>,
+    pub memory_coverage: Option<coverage::MemoryCoverage>,
+    /// Best STREAM bandwidth and pointer-chase latency seen during the run
+    pub memory_bench: Option<memory::MemoryBench>,
+    /// Storage stress outcome per target device
+    pub storage: Vec<nvme::DeviceReport>,
+    pub cpu_core_errors: Vec<cpu::CoreErrors>,
+    pub cpu_first_error: Option<cpu::CpuMismatch>,
+    pub linpack: Option<cpu::LinpackSummary>,
+    pub throughput: Vec<throughput::WorkloadThroughput>,
+    pub worker_crashes: Vec<WorkerCrash>,
+    pub worker_hangs: Vec<WorkerHang>,
+    pub ras: RasCounts,
     pub throttle_events: u32,
     pub under_voltage_events: u32,
     pub max_cpu_temp: f32,
@@ -69,6 +137,32 @@ pub struct FinalReport {
     pub memory_stress_passed: bool,
     pub nvme_stress_passed: bool,
     pub video_stress_passed: bool,
+    /// Workload selections that ran (None when the subsystem was disabled)
+    pub cpu_workloads: Option<WorkloadMix<cpu::CpuWorkload>>,
+    pub memory_workloads: Option<WorkloadMix<memory::MemoryWorkload>>,
+    pub memory_patterns: Vec<memory::PatternResult>,
+    /// Mismatching memory locations (first 256 in detail, all counted)
+    pub memory_error_records: Vec<memory::MemoryError>,
+    pub memory_mismatches: usize,
+    /// Locked and huge-page-backed memory actually granted to the memory workers
+    pub memory_buffers: Option<memory::BufferSummary>,
+    pub memory_coverage: Option<coverage::MemoryCoverage>,
+    /// Best STREAM bandwidth and pointer-chase latency seen during the run
+    pub memory_bench: Option<memory::MemoryBench>,
+    /// Errors, corrupt blocks, latency and slow I/Os of each storage target
+    pub storage: Vec<nvme::DeviceReport>,
+    pub cpu_core_errors: Vec<cpu::CoreErrors>,
+    pub cpu_first_error: Option<cpu::CpuMismatch>,
+    pub linpack: Option<cpu::LinpackSummary>,
+    pub throughput: Vec<throughput::WorkloadThroughput>,
+    /// A workload slowed down beyond the allowed drop while not throttled
+    pub throughput_degraded: bool,
+    /// Worker processes that died (first MAX_FAILURE_RECORDS), with signal names
+    pub worker_crashes: Vec<WorkerCrash>,
+    /// Workers that stopped making progress (first MAX_FAILURE_RECORDS)
+    pub worker_hangs: Vec<WorkerHang>,
+    /// EDAC and kernel RAS errors during the run; any fails it
+    pub ras: RasCounts,
     pub max_cpu_temp: f32,
     pub avg_cpu_temp: f32,
     pub max_nvme_temp: Option<f32>,
@@ -79,92 +173,233 @@ pub struct FinalReport {
     pub smart_warnings: u32,
 }
 
+/// CPU and memory workers, forked before the async runtime starts, and the
+/// state they share with the monitoring loop
+pub struct Workers {
+    start_time: Instant,
+    supervisor: Supervisor,
+    /// Cleared to stop the worker processes
+    running: Arc<Shared<AtomicBool>>,
+    cpu_shared: Arc<cpu::CpuShared>,
+    core_errors: Vec<Arc<cpu::CoreErrorCounters>>,
+    vote_board: Option<Arc<cpu::VoteBoard>>,
+    load_step: cpu::LoadStepSchedule,
+    memory_shared: Arc<memory::MemoryShared>,
+    memory_plan: Option<coverage::MemoryCoverage>,
+    throughput: throughput::ThroughputTracker,
+}
+
+/// Fork the CPU and memory workers; the run's clock starts with them
+/// Call while this process still has a single thread (see `Supervisor::start`).
+pub fn start_workers(config: &StressConfig) -> Workers {
+    let start_time = Instant::now();
+    let cpu_shared = Arc::new(cpu::CpuShared::default());
+    let memory_plan = config.memory_plan.clone().filter(|_| config.memory);
+    let num_mem_threads = memory_plan.as_ref().map_or(0, |plan| plan.threads);
+    let memory_shared = Arc::new(memory::MemoryShared::new(num_mem_threads));
+    let mut throughput = throughput::ThroughputTracker::new(config.throughput_drop);
+
+    // CPU and memory workers run in forked processes that watch this flag
+    let worker_running = Arc::new(Shared::new(AtomicBool::new(true)));
+    let mut supervisor = Supervisor::new(start_time);
+
+    // Square-wave schedule shared by load-step workers and the stats display
+    let load_step = cpu::LoadStepSchedule::new(
+        start_time,
+        config.duration,
+        config.load_step_min,
+        config.load_step_max,
+    );
+
+    // Start CPU stress workers, one per core (round-robin if threads > cores)
+    let mut core_errors: Vec<Arc<cpu::CoreErrorCounters>> = Vec::new();
+    let mut vote_board: Option<Arc<cpu::VoteBoard>> = None;
+    if config.cpu {
+        // Golden results must exist before any worker compares against them
+        cpu::init_reference();
+
+        let cores = affinity::allowed_cpus();
+        core_errors = cores
+            .iter()
+            .take(config.threads)
+            .map(|&core| Arc::new(cpu::CoreErrorCounters::new(core)))
+            .collect();
+
+        let worker_cores: Vec<_> = (0..config.threads)
+            .map(|i| core_errors[i % core_errors.len()].clone())
+            .collect();
+        let board = Arc::new(cpu::VoteBoard::new(worker_cores.clone()));
+        vote_board = Some(board.clone());
+        let cpu_throughput = Arc::new(throughput::SubsystemThroughput::new::<cpu::CpuWorkload>(
+            "cpu",
+            config.threads,
+        ));
+        throughput.add(cpu_throughput.clone());
+
+        for (i, counters) in worker_cores.into_iter().enumerate() {
+            let running = worker_running.clone();
+            let shared = cpu_shared.clone();
+            let board = board.clone();
+            let mode = config.cpu_mode;
+            let rotation = config.cpu_workloads.rotation();
+            let rates = cpu_throughput.worker(i);
+            let core = counters.core();
+            supervisor.add("cpu", i, Some(core), move |heartbeat| match mode {
+                cpu::CpuMode::Rotate => {
+                    cpu::run_cpu_stress(&rotation, &running, &shared, &counters, &rates, heartbeat)
+                }
+                cpu::CpuMode::Vote => {
+                    cpu::run_vote_stress(i, &rotation, &board, &running, &shared, &rates, heartbeat)
+                }
+                cpu::CpuMode::LoadStep => {
+                    cpu::run_load_step_stress(load_step, &running, &shared, &counters, heartbeat)
+                }
+            });
+        }
+    }
+
+    // Start memory stress workers
+    if let Some(plan) = &memory_plan {
+        let buffer = buffer::BufferSpec {
+            bytes: plan.planned_bytes as usize / num_mem_threads,
+            hugepages: config.hugepages,
+        };
+        let mem_throughput = Arc::new(
+            throughput::SubsystemThroughput::new::<memory::MemoryWorkload>(
+                "memory",
+                num_mem_threads,
+            ),
+        );
+        throughput.add(mem_throughput.clone());
+
+        for i in 0..num_mem_threads {
+            let running = worker_running.clone();
+            let shared = memory_shared.clone();
+            let rotation = config.memory_workloads.rotation();
+            let rates = mem_throughput.worker(i);
+            supervisor.add("memory", i, None, move |heartbeat| {
+                memory::run_memory_stress(
+                    &running, &shared, i, buffer, &rotation, &rates, heartbeat,
+                );
+            });
+        }
+    }
+
+    // Fork the zygote, then every worker from it
+    if let Err(e) = supervisor.start() {
+        eprintln!("Failed to start the worker zygote: {}", e);
+    }
+    for (subsystem, errors) in [
+        ("cpu", &cpu_shared.errors),
+        ("memory", &memory_shared.errors),
+    ] {
+        if let Err(e) = supervisor.respawn(subsystem) {
+            eprintln!("Failed to start {} workers: {}", subsystem, e);
+            errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    Workers {
+        start_time,
+        supervisor,
+        running: worker_running,
+        cpu_shared,
+        core_errors,
+        vote_board,
+        load_step,
+        memory_shared,
+        memory_plan,
+        throughput,
+    }
+}
+
 pub async fn run_stress_test(
     config: StressConfig,
+    workers: Workers,
     running: Arc<AtomicBool>,
     stats_tx: mpsc::Sender<StressStats>,
     _event_tx: mpsc::Sender<String>,
-    nvme_info: Option<NvmeInfo>,
+    storage: Vec<StorageDevice>,
 ) -> TestResult {
-    let start_time = Instant::now();
-    let cpu_errors = Arc::new(AtomicU64::new(0));
-    let memory_errors = Arc::new(AtomicU64::new(0));
-    let nvme_errors = Arc::new(AtomicU64::new(0));
+    let Workers {
+        start_time,
+        mut supervisor,
+        running: worker_running,
+        cpu_shared,
+        core_errors,
+        vote_board,
+        load_step,
+        memory_shared,
+        memory_plan,
+        mut throughput,
+    } = workers;
+    // Only errors raised during the run count
+    let mut ras_monitor = detection::ras::RasMonitor::start();
+    // Devices are monitored even when the storage stress is off
+    let mounts = detection::storage::read_mounts();
+    let mut targets: Vec<nvme::Target> = storage
+        .into_iter()
+        .map(|device| {
+            let io_target = if !config.nvme {
+                None
+            } else if config.nvme_options.raw_device {
+                Some(nvme::IoTarget::Raw(device.clone()))
+            } else {
+                nvme::get_test_file_path(&device, &mounts, config.nvme_options.path.as_deref())
+                    .map(nvme::IoTarget::File)
+            };
+            nvme::Target::new(device, io_target)
+        })
+        .collect();
+    let nvme_workers = config.nvme_options.workers;
     let video_errors = Arc::new(AtomicU64::new(0));
+    let mut worker_crashes: Vec<WorkerCrash> = Vec::new();
+    let mut worker_hangs: Vec<WorkerHang> = Vec::new();
 
     let mut temp_samples: Vec<f32> = Vec::new();
     let mut max_cpu_temp: f32 = 0.0;
-    let mut max_nvme_temp: Option<f32> = None;
     let mut throttle_events: u32 = 0;
     let mut under_voltage_events: u32 = 0;
+    let load_step_active = config.cpu && config.cpu_mode == cpu::CpuMode::LoadStep;
 
-    // Start CPU stress threads
-    let cpu_handles: Vec<_> = if config.cpu {
-        (0..config.threads)
-            .map(|_| {
-                let running = running.clone();
-                let errors = cpu_errors.clone();
-                std::thread::spawn(move || {
-                    cpu::run_cpu_stress(running, errors);
-                })
-            })
-            .collect()
-    } else {
-        Vec::new()
-    };
-
-    // Start memory stress threads
-    // Calculate memory allocation: use 50% of available RAM, divided among threads
-    let mem_handles: Vec<_> = if config.memory {
-        let available_mb = monitor::get_available_memory_mb();
-        let num_mem_threads = 2.min(config.threads);
-        // Use 50% of AVAILABLE memory (not total), divided by number of threads
-        // Leave 50% for OS, app overhead, NVMe test file cache, and safety margin
-        let allocation_per_thread = (available_mb as usize * 1024 * 1024 / 2) / num_mem_threads;
-
-        (0..num_mem_threads)
-            .map(|_| {
-                let running = running.clone();
-                let errors = memory_errors.clone();
-                std::thread::spawn(move || {
-                    memory::run_memory_stress(running, errors, allocation_per_thread);
-                })
-            })
-            .collect()
-    } else {
-        Vec::new()
-    };
-
-    // Start NVMe stress if enabled and available
-    let nvme_test_path = if config.nvme {
-        nvme_info.as_ref().map(|nvme| {
-            nvme::get_test_file_path(nvme, config.nvme_path.as_deref())
-                .to_string_lossy()
-                .to_string()
-        })
-    } else {
-        None
-    };
-
-    let nvme_handle = if let Some(nvme) = nvme_info.as_ref().filter(|_| config.nvme) {
-        let running = running.clone();
-        let errors = nvme_errors.clone();
-        let nvme = nvme.clone();
-        let custom_path = config.nvme_path.clone();
-        Some(tokio::spawn(async move {
-            nvme::run_nvme_stress(running, errors, nvme, custom_path).await;
-        }))
-    } else {
-        None
-    };
+    // Start the storage stress on every target, each with its own workers;
+    // worker i of target t is nvme worker t * nvme_workers + i
+    let mut nvme_handles = Vec::new();
+    if config.nvme && !targets.is_empty() {
+        let nvme_throughput = Arc::new(throughput::SubsystemThroughput::new::<nvme::NvmeWorkload>(
+            "nvme",
+            targets.len() * nvme_workers,
+        ));
+        throughput.add(nvme_throughput.clone());
+        for (t, target) in targets.iter().enumerate() {
+            let Some(io_target) = target.io_target.clone() else {
+                target.shared.skip(format!(
+                    "no writable filesystem on {} (mount one or pass --nvme-path)",
+                    target.device.name
+                ));
+                continue;
+            };
+            let workers = t * nvme_workers..(t + 1) * nvme_workers;
+            let rates = workers.clone().map(|i| nvme_throughput.worker(i)).collect();
+            let heartbeats = workers.map(|i| supervisor.watch("nvme", i)).collect();
+            let running = running.clone();
+            let shared = target.shared.clone();
+            let options = config.nvme_options.clone();
+            nvme_handles.push(std::thread::spawn(move || {
+                nvme::run_nvme_stress(running, shared, rates, heartbeats, io_target, options);
+            }));
+        }
+    }
+    let storage_shared: Vec<_> = targets.iter().map(|t| t.shared.clone()).collect();
 
     // Start video stress if enabled and encoder was pre-detected
     let video_handle = if config.video {
         if let Some(encoder) = config.video_encoder {
             let running = running.clone();
             let errors = video_errors.clone();
+            let heartbeat = supervisor.watch("video", 0);
             Some(std::thread::spawn(move || {
-                video::run_video_stress_with_encoder(running, errors, encoder);
+                video::run_video_stress_with_encoder(running, errors, heartbeat, encoder);
             }))
         } else {
             // No working encoder found during pre-detection
@@ -174,6 +409,17 @@ pub async fn run_stress_test(
         None
     };
 
+    // Crashed and hung workers fail their own subsystem
+    let charge_failure = |subsystem: &str, worker: usize| {
+        let errors: &AtomicU64 = match subsystem {
+            "cpu" => &cpu_shared.errors,
+            "memory" => &memory_shared.errors,
+            "nvme" => &storage_shared[worker / nvme_workers].errors,
+            _ => &video_errors,
+        };
+        errors.fetch_add(1, Ordering::Relaxed);
+    };
+
     // Monitoring loop
     let mut last_throttle_raw: u32 = 0;
     let mut cpu_snapshot = CpuStatSnapshot::read();
@@ -195,17 +441,6 @@ pub async fn run_stress_test(
             max_cpu_temp = monitor_stats.cpu_temp_c;
         }
 
-        // Track NVMe temperature
-        if let Some(ref nvme) = nvme_info {
-            if let Some(temp) = detection::nvme::get_nvme_temp(&nvme.device_path) {
-                match max_nvme_temp {
-                    Some(max) if temp > max => max_nvme_temp = Some(temp),
-                    None => max_nvme_temp = Some(temp),
-                    _ => {}
-                }
-            }
-        }
-
         // Track throttle events (count changes from 0 to non-zero)
         let current_throttle = monitor_stats.throttle_status.raw_value;
         if current_throttle != last_throttle_raw {
@@ -221,25 +456,62 @@ pub async fn run_stress_test(
         }
         last_throttle_raw = current_throttle;
 
+        // Count crashed and hung workers as errors and replace them
+        let hangs = supervisor.check_hangs(config.hang_timeout);
+        let crashes = supervisor.reap();
+        if !crashes.is_empty() || !hangs.is_empty() {
+            let cpu_failed = crashes.iter().any(|c| c.subsystem == "cpu")
+                || hangs.iter().any(|h| h.subsystem == "cpu");
+            for hang in hangs {
+                charge_failure(&hang.subsystem, hang.worker);
+                push_bounded(&mut worker_hangs, hang);
+            }
+            for crash in crashes {
+                charge_failure(&crash.subsystem, crash.worker);
+                push_bounded(&mut worker_crashes, crash);
+            }
+            // A vote round needs every worker, so restart the whole group from round 0
+            if let Some(board) = vote_board
+                .as_ref()
+                .filter(|_| cpu_failed && config.cpu_mode == cpu::CpuMode::Vote)
+            {
+                supervisor.kill("cpu");
+                board.reset();
+            }
+            for subsystem in ["cpu", "memory"] {
+                if let Err(e) = supervisor.respawn(subsystem) {
+                    eprintln!("Failed to restart {} worker: {}", subsystem, e);
+                }
+            }
+        }
+
+        throughput.sample(
+            elapsed,
+            monitor_stats.throttle_status.has_any_current_issue(),
+        );
+
         // Build stats
         let stats = StressStats {
             elapsed_secs: elapsed.as_secs(),
             cpu_temp_c: monitor_stats.cpu_temp_c,
             cpu_temp_max: max_cpu_temp,
             cpu_freq_mhz: monitor_stats.cpu_freq_mhz,
+            load_step_hz: load_step_active.then(|| load_step.frequency_hz(elapsed)),
             throttle_status: monitor_stats.throttle_status,
             cpu_usage_per_core: monitor_stats.cpu_usage_per_core,
             mem_used_mb: monitor_stats.mem_used_mb,
             mem_total_mb: monitor_stats.mem_total_mb,
-            nvme_temp_c: nvme_info
-                .as_ref()
-                .and_then(|n| detection::nvme::get_nvme_temp(&n.device_path)),
-            nvme_temp_max: max_nvme_temp,
-            nvme_test_path: nvme_test_path.clone(),
+            storage: targets.iter_mut().map(nvme::Target::sample).collect(),
             io_errors: detection::errors::count_recent_io_errors(),
-            cpu_errors: cpu_errors.load(Ordering::Relaxed),
-            memory_errors: memory_errors.load(Ordering::Relaxed),
-            nvme_errors: nvme_errors.load(Ordering::Relaxed),
+            cpu_errors: cpu_shared.errors.load(Ordering::Relaxed),
+            cpu_core_errors: core_errors.iter().map(|c| c.snapshot()).collect(),
+            linpack: cpu_shared.linpack.snapshot(),
+            memory_bench: memory_shared.latest_bench(),
+            throughput: throughput.rates(),
+            hung_workers: worker_hangs.clone(),
+            ras: ras_monitor.sample(),
+            memory_errors: memory_shared.errors.load(Ordering::Relaxed),
+            nvme_errors: targets.iter().map(nvme::Target::errors).sum(),
             video_errors: video_errors.load(Ordering::Relaxed),
             progress_percent: (elapsed.as_secs_f32() / config.duration.as_secs_f32()) * 100.0,
             fan_status: monitor_stats.fan_status,
@@ -255,17 +527,19 @@ pub async fn run_stress_test(
     // Signal stop
     running.store(false, Ordering::SeqCst);
 
-    // Wait for threads to finish
-    for handle in cpu_handles {
-        let _ = handle.join();
-    }
-    for handle in mem_handles {
-        let _ = handle.join();
+    // Stop the worker processes; crashes while stopping still count
+    worker_running.store(false, Ordering::SeqCst);
+    for crash in supervisor.shutdown(WORKER_STOP_TIMEOUT) {
+        charge_failure(&crash.subsystem, crash.worker);
+        push_bounded(&mut worker_crashes, crash);
     }
-    if let Some(handle) = nvme_handle {
-        let _ = handle.await;
+    // In-process workers cannot be killed; a hung one is abandoned, not awaited
+    if !supervisor.is_hung("nvme") {
+        for handle in nvme_handles {
+            let _ = handle.join();
+        }
     }
-    if let Some(handle) = video_handle {
+    if let Some(handle) = video_handle.filter(|_| !supervisor.is_hung("video")) {
         let _ = handle.join();
     }
 
@@ -276,21 +550,55 @@ pub async fn run_stress_test(
         temp_samples.iter().sum::<f32>() / temp_samples.len() as f32
     };
 
+    let memory_buffers = memory_shared.buffer_summary(config.hugepages);
     TestResult {
-        cpu_errors: cpu_errors.load(Ordering::Relaxed),
-        memory_errors: memory_errors.load(Ordering::Relaxed),
-        nvme_errors: nvme_errors.load(Ordering::Relaxed),
+        cpu_errors: cpu_shared.errors.load(Ordering::Relaxed),
+        memory_errors: memory_shared.errors.load(Ordering::Relaxed),
+        nvme_errors: targets.iter().map(nvme::Target::errors).sum(),
         video_errors: video_errors.load(Ordering::Relaxed),
+        cpu_workloads: config
+            .cpu
+            .then(|| config.cpu_mode.workloads(&config.cpu_workloads)),
+        memory_workloads: config.memory.then(|| config.memory_workloads.clone()),
+        memory_patterns: memory_shared.pattern_summary(),
+        memory_error_records: memory_shared.error_log.records(),
+        memory_mismatches: memory_shared.error_log.total(),
+        memory_buffers: memory_buffers.clone(),
+        memory_coverage: memory_plan
+            .map(|plan| plan.with_tested(memory_buffers.map_or(0, |b| b.allocated_bytes))),
+        memory_bench: memory_shared.best_bench(),
+        storage: targets
+            .iter()
+            .filter(|_| config.nvme)
+            .map(nvme::Target::report)
+            .collect(),
+        cpu_core_errors: core_errors.iter().map(|c| c.snapshot()).collect(),
+        cpu_first_error: cpu_shared.first_error.records().into_iter().next(),
+        linpack: cpu_shared.linpack.snapshot(),
+        throughput: throughput.summary(),
+        worker_crashes,
+        worker_hangs,
+        ras: ras_monitor.sample(),
         throttle_events,
         under_voltage_events,
         max_cpu_temp,
         avg_cpu_temp,
-        max_nvme_temp,
+        max_nvme_temp: targets
+            .iter()
+            .filter_map(nvme::Target::temp_max)
+            .reduce(f32::max),
         completed: start_time.elapsed() >= config.duration,
         duration_secs: start_time.elapsed().as_secs(),
     }
 }
 
+/// Keep at most MAX_FAILURE_RECORDS records for the report
+fn push_bounded<T>(records: &mut Vec<T>, record: T) {
+    if records.len() < MAX_FAILURE_RECORDS {
+        records.push(record);
+    }
+}
+
 pub fn generate_report(
     result: &TestResult,
     io_errors: &[String],
@@ -305,6 +613,7 @@ pub fn generate_report(
         && memory_passed
         && nvme_passed
         && video_passed
+        && !result.ras.any()
         && result.throttle_events == 0
         && result.under_voltage_events == 0;
 
@@ -315,6 +624,23 @@ pub fn generate_report(
         memory_stress_passed: memory_passed,
         nvme_stress_passed: nvme_passed,
         video_stress_passed: video_passed,
+        cpu_workloads: result.cpu_workloads.clone(),
+        memory_workloads: result.memory_workloads.clone(),
+        memory_patterns: result.memory_patterns.clone(),
+        memory_error_records: result.memory_error_records.clone(),
+        memory_mismatches: result.memory_mismatches,
+        memory_buffers: result.memory_buffers.clone(),
+        memory_coverage: result.memory_coverage.clone(),
+        memory_bench: result.memory_bench.clone(),
+        storage: result.storage.clone(),
+        cpu_core_errors: result.cpu_core_errors.clone(),
+        cpu_first_error: result.cpu_first_error.clone(),
+        linpack: result.linpack.clone(),
+        throughput: result.throughput.clone(),
+        throughput_degraded: result.throughput.iter().any(|t| t.unexplained_drops > 0),
+        worker_crashes: result.worker_crashes.clone(),
+        worker_hangs: result.worker_hangs.clone(),
+        ras: result.ras,
         max_cpu_temp: result.max_cpu_temp,
         avg_cpu_temp: result.avg_cpu_temp,
         max_nvme_temp: result.max_nvme_temp,
@@ -1,14 +1,33 @@
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
+use std::io::{self, Seek, SeekFrom};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::detection::nvme::NvmeInfo;
+use super::io_engine::{AlignedBuffer, IoCompletion, IoEngine, IoKind, IoQueue, IoRequest};
+use super::latency::{format_us, LatencyHistogram, LatencySummary};
+use super::memory::splitmix64;
+use super::supervisor::Heartbeat;
+use super::throughput::WorkerThroughput;
+use super::workload::Workload;
+use crate::detection::storage::{self, StorageDevice, StorageKind};
 
-/// Test file size (8 GB)
-const TEST_FILE_SIZE: u64 = 8 * 1024 * 1024 * 1024;
+const MB: u64 = 1024 * 1024;
+
+/// Default test file size when the drive has room (8 GB)
+const DEFAULT_TEST_FILE_SIZE: u64 = 8 * 1024 * MB;
+
+/// Smallest test file worth running
+const MIN_TEST_FILE_SIZE: u64 = 256 * MB;
+
+/// Free space always left on the target filesystem
+const FREE_SPACE_RESERVE: u64 = 1024 * MB;
 
 /// Block size for 4K random I/O
 const BLOCK_SIZE_4K: usize = 4096;
@@ -16,274 +35,1273 @@ const BLOCK_SIZE_4K: usize = 4096;
 /// Block size for sequential I/O
 const BLOCK_SIZE_SEQ: usize = 128 * 1024;
 
-/// Run NVMe stress test
-pub async fn run_nvme_stress(
-    running: Arc<AtomicBool>,
-    errors: Arc<AtomicU64>,
-    nvme_info: NvmeInfo,
-    custom_path: Option<String>,
-) {
-    // Determine test file path - use a temp file on the NVMe
-    let test_path = get_test_file_path(&nvme_info, custom_path.as_deref());
+/// Largest block size for random I/O; every worker region holds at least one
+pub const MAX_BLOCK_SIZE: usize = 1024 * 1024;
 
-    // Create test file if needed
-    if let Err(e) = create_test_file(&test_path) {
-        eprintln!("Failed to create NVMe test file: {}", e);
-        errors.fetch_add(1, Ordering::Relaxed);
-        return;
+/// Most I/O workers, so each still gets a few megabytes of the smallest test file
+pub const MAX_WORKERS: usize = 64;
+
+/// Most requests in flight per worker
+pub const MAX_QUEUE_DEPTH: usize = 256;
+
+/// Random requests per worker in each pass of a random workload
+const RANDOM_OPS: usize = 1000;
+
+/// First word of every block written by the stress test
+const BLOCK_MAGIC: u64 = u64::from_le_bytes(*b"PIUPBLK1");
+
+/// Header words at the start of each 4K block: magic, offset, generation, seed, checksum
+const HEADER_WORDS: usize = 5;
+
+/// Index of the checksum word, which is zero while the checksum is computed
+const CHECKSUM_WORD: usize = 4;
+
+/// Corrupt blocks and slow requests recorded in detail (all are counted)
+const MAX_DETAIL_RECORDS: usize = 64;
+
+/// NVMe workloads rotated by each I/O worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NvmeWorkload {
+    Random4k,
+    Sequential,
+    Mixed,
+}
+
+/// How the test file is accessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoMode {
+    /// O_DIRECT: every read and write goes to the drive
+    Direct,
+    /// The filesystem refused O_DIRECT; reads may be served from the page cache
+    Buffered,
+}
+
+impl fmt::Display for IoMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoMode::Direct => write!(f, "O_DIRECT"),
+            IoMode::Buffered => write!(f, "buffered"),
+        }
     }
+}
 
-    let mut iteration: u64 = 0;
+/// Test file location and I/O shape for the NVMe stress
+#[derive(Debug, Clone, PartialEq)]
+pub struct NvmeOptions {
+    /// Test file path (None: picked from the mounted NVMe filesystems)
+    pub path: Option<String>,
+    /// Test file size (None: scaled to free space)
+    pub size: Option<u64>,
+    /// Parallel I/O workers, each on its own slice of the test file
+    pub workers: usize,
+    /// Requests each worker keeps in flight
+    pub queue_depth: usize,
+    pub engine: IoEngine,
+    /// Share of reads in the mixed workload, in percent
+    pub read_percent: u8,
+    /// Request size of the random workloads, a multiple of 4K
+    pub block_size: usize,
+    /// Requests taking longer than this are reported as slow
+    pub slow_io: Duration,
+    /// Overwrite the whole block device instead of using a test file
+    pub raw_device: bool,
+}
 
-    while running.load(Ordering::Relaxed) {
-        // Rotate between different stress methods
-        match iteration % 3 {
-            0 => {
-                if !run_random_4k_stress(&test_path, &running) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            1 => {
-                if !run_sequential_stress(&test_path, &running) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            2 => {
-                if !run_mixed_stress(&test_path, &running) {
-                    errors.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-            _ => unreachable!(),
+impl Default for NvmeOptions {
+    fn default() -> Self {
+        Self {
+            path: None,
+            size: None,
+            workers: 4,
+            queue_depth: 8,
+            engine: IoEngine::IoUring,
+            read_percent: 70,
+            block_size: BLOCK_SIZE_4K,
+            slow_io: Duration::from_secs(1),
+            raw_device: false,
         }
+    }
+}
 
-        iteration = iteration.wrapping_add(1);
+/// How the stress I/O was actually issued
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueSetup {
+    /// The engine in use, after any fallback from io_uring
+    pub engine: IoEngine,
+    pub workers: usize,
+    pub queue_depth: usize,
+    pub block_size: usize,
+    pub read_percent: u8,
+}
+
+impl QueueSetup {
+    /// Requests kept in flight over all workers
+    pub fn in_flight(&self) -> usize {
+        self.workers * self.queue_depth
     }
+}
 
-    // Cleanup test file
-    let _ = std::fs::remove_file(&test_path);
+impl fmt::Display for QueueSetup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {} workers × QD {}, {}K random blocks, {}% reads in mixed",
+            self.engine,
+            self.workers,
+            self.queue_depth,
+            self.block_size / 1024,
+            self.read_percent
+        )
+    }
 }
 
-/// Get path for test file on NVMe (public for UI display)
-/// custom_path: User-specified path via --nvme-path flag
-pub fn get_test_file_path(_nvme_info: &NvmeInfo, custom_path: Option<&str>) -> PathBuf {
-    // 1. If user specified custom path, use it
-    if let Some(path) = custom_path {
-        return PathBuf::from(path);
-    }
-
-    // 2. Check if root "/" is on NVMe - if so, use user's home or /var/tmp
-    //    (NOT /tmp which is often tmpfs in RAM!)
-    if let Ok(mounts) = std::fs::read_to_string("/proc/mounts") {
-        for line in mounts.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let device = parts[0];
-                let mount_point = parts[1];
-
-                // Root is on NVMe? Use home dir or /var/tmp (both on NVMe)
-                if mount_point == "/" && device.contains("nvme") {
-                    // Try user's cache directory first
-                    if let Some(home) = std::env::var_os("HOME") {
-                        let mut cache_path = PathBuf::from(home);
-                        cache_path.push(".cache");
-                        cache_path.push("pi-under-pressure");
-                        // Create cache dir if needed
-                        let _ = std::fs::create_dir_all(&cache_path);
-                        cache_path.push("nvme-test");
-                        return cache_path;
-                    }
-                    // Fallback to /var/tmp (persistent, on root fs)
-                    return PathBuf::from("/var/tmp/.pi-under-pressure-nvme-test");
-                }
-            }
+/// Header of a self-describing 4K block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub magic: u64,
+    /// Byte offset of the block in the test file
+    pub offset: u64,
+    /// Bumped on every rewrite of the block
+    pub generation: u64,
+    /// Seed of the block's payload
+    pub seed: u64,
+    /// Over the header (checksum word zeroed) and payload
+    pub checksum: u64,
+}
+
+/// A block that did not read back as last written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCorruption {
+    pub workload: String,
+    pub offset: u64,
+    pub expected: BlockHeader,
+    pub actual: BlockHeader,
+    /// The block's own checksum still matched its contents
+    pub checksum_valid: bool,
+    pub timestamp_ms: u64,
+}
+
+impl BlockCorruption {
+    /// Most likely cause, judged from the header that was read
+    pub fn kind(&self) -> &'static str {
+        if self.actual.magic != BLOCK_MAGIC {
+            "garbage"
+        } else if self.actual.offset != self.expected.offset {
+            "misdirected"
+        } else if self.actual.generation != self.expected.generation && self.checksum_valid {
+            "stale"
+        } else if self.checksum_valid {
+            "wrong header"
+        } else {
+            "checksum mismatch"
         }
+    }
+}
 
-        // 3. Look for non-boot NVMe mount with enough space
-        for line in mounts.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let device = parts[0];
-                let mount_point = parts[1];
-
-                if device.contains("nvme") {
-                    // Skip root and boot partitions
-                    if mount_point == "/"
-                        || mount_point == "/boot"
-                        || mount_point == "/boot/firmware"
-                    {
-                        continue;
-                    }
-                    // Found a separate NVMe data partition
-                    let mut path = PathBuf::from(mount_point);
-                    path.push(".pi-under-pressure-test");
-                    return path;
-                }
+impl fmt::Display for BlockCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at offset {:#x} ({}): expected gen {} seed {:#018x}, read offset {:#x} gen {} seed {:#018x}",
+            self.kind(),
+            self.offset,
+            self.workload,
+            self.expected.generation,
+            self.expected.seed,
+            self.actual.offset,
+            self.actual.generation,
+            self.actual.seed
+        )
+    }
+}
+
+/// A request that took longer than the slow-I/O timeout
+/// A warning rather than an error: the data still verified, but stalls like
+/// this are typical of a marginal PCIe link retraining.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlowIo {
+    pub workload: String,
+    /// "read" or "write"
+    pub kind: String,
+    pub offset: u64,
+    pub bytes: usize,
+    pub latency_us: u64,
+    pub timestamp_ms: u64,
+}
+
+impl fmt::Display for SlowIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {}K at offset {:#x} took {} ({})",
+            self.kind,
+            self.bytes / 1024,
+            self.offset,
+            format_us(self.latency_us),
+            self.workload
+        )
+    }
+}
+
+/// Live NVMe load, streamed through `StressStats`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NvmeLoad {
+    pub iops: f64,
+    pub mb_per_sec: f64,
+    /// Over the run so far
+    pub read_p99_us: Option<u64>,
+    pub write_p99_us: Option<u64>,
+    pub slow_ios: u64,
+}
+
+/// Turns the running request and byte totals into rates between samples
+#[derive(Debug, Default)]
+pub struct LoadMeter {
+    last: Option<(Instant, u64, u64)>,
+}
+
+impl LoadMeter {
+    /// Load since the previous sample; None until the workers have started
+    pub fn sample(&mut self, shared: &NvmeShared) -> Option<NvmeLoad> {
+        shared.queue()?;
+        let now = Instant::now();
+        let ops = shared.read_latency.count() + shared.write_latency.count();
+        let bytes = shared.bytes.load(Ordering::Relaxed);
+        let (iops, mb_per_sec) = match self.last.replace((now, ops, bytes)) {
+            Some((then, last_ops, last_bytes)) => {
+                let secs = now.duration_since(then).as_secs_f64().max(1e-3);
+                (
+                    ops.saturating_sub(last_ops) as f64 / secs,
+                    bytes.saturating_sub(last_bytes) as f64 / MB as f64 / secs,
+                )
             }
+            None => (0.0, 0.0),
+        };
+        Some(NvmeLoad {
+            iops,
+            mb_per_sec,
+            read_p99_us: shared.read_latency.percentile(0.99),
+            write_p99_us: shared.write_latency.percentile(0.99),
+            slow_ios: shared.slow_ios(),
+        })
+    }
+}
+
+/// Error counter and corruption records shared with the stats loop
+#[derive(Default)]
+pub struct NvmeShared {
+    pub errors: AtomicU64,
+    corrupt_blocks: AtomicU64,
+    corruptions: Mutex<Vec<BlockCorruption>>,
+    read_latency: LatencyHistogram,
+    write_latency: LatencyHistogram,
+    /// Bytes moved by completed requests
+    bytes: AtomicU64,
+    slow_ios: AtomicU64,
+    slow_io_records: Mutex<Vec<SlowIo>>,
+    io_mode: OnceLock<IoMode>,
+    queue: OnceLock<QueueSetup>,
+    file_size: OnceLock<u64>,
+    skipped: OnceLock<String>,
+}
+
+impl NvmeShared {
+    /// Count a corrupt block as an error and keep the first records
+    fn record_corruption(&self, corruption: BlockCorruption) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.corrupt_blocks.fetch_add(1, Ordering::Relaxed);
+        let mut corruptions = self.corruptions.lock().unwrap();
+        if corruptions.len() < MAX_DETAIL_RECORDS {
+            corruptions.push(corruption);
         }
     }
 
-    // 4. Fallback to /var/tmp (NOT /tmp which may be tmpfs)
-    PathBuf::from("/var/tmp/.pi-under-pressure-nvme-test")
+    /// Time a completed request, keeping the first records of slow ones
+    fn record_io(
+        &self,
+        request: &IoRequest,
+        latency: Duration,
+        slow_io: Duration,
+        workload: NvmeWorkload,
+    ) {
+        let (histogram, kind) = match request.kind {
+            IoKind::Read => (&self.read_latency, "read"),
+            IoKind::Write => (&self.write_latency, "write"),
+        };
+        histogram.record(latency);
+        self.bytes
+            .fetch_add(request.buffer.len() as u64, Ordering::Relaxed);
+        if latency < slow_io {
+            return;
+        }
+
+        self.slow_ios.fetch_add(1, Ordering::Relaxed);
+        let mut records = self.slow_io_records.lock().unwrap();
+        if records.len() < MAX_DETAIL_RECORDS {
+            records.push(SlowIo {
+                workload: workload.name().to_string(),
+                kind: kind.to_string(),
+                offset: request.offset,
+                bytes: request.buffer.len(),
+                latency_us: latency.as_micros() as u64,
+                timestamp_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as u64),
+            });
+        }
+    }
+
+    pub fn read_latency(&self) -> Option<LatencySummary> {
+        self.read_latency.summary()
+    }
+
+    pub fn write_latency(&self) -> Option<LatencySummary> {
+        self.write_latency.summary()
+    }
+
+    /// Requests over the slow-I/O timeout (warnings, not errors)
+    pub fn slow_ios(&self) -> u64 {
+        self.slow_ios.load(Ordering::Relaxed)
+    }
+
+    pub fn slow_io_records(&self) -> Vec<SlowIo> {
+        self.slow_io_records.lock().unwrap().clone()
+    }
+
+    pub fn corrupt_blocks(&self) -> u64 {
+        self.corrupt_blocks.load(Ordering::Relaxed)
+    }
+
+    pub fn corruptions(&self) -> Vec<BlockCorruption> {
+        self.corruptions.lock().unwrap().clone()
+    }
+
+    /// How the test file is accessed, once it has been created
+    pub fn io_mode(&self) -> Option<IoMode> {
+        self.io_mode.get().copied()
+    }
+
+    /// Engine and queue depth in use, once the workers have started
+    pub fn queue(&self) -> Option<QueueSetup> {
+        self.queue.get().copied()
+    }
+
+    /// Size chosen for the test file by the free-space check
+    pub fn file_size(&self) -> Option<u64> {
+        self.file_size.get().copied()
+    }
+
+    /// Why the NVMe test did not run (not a stability failure)
+    pub fn skipped(&self) -> Option<String> {
+        self.skipped.get().cloned()
+    }
+
+    /// Record why the test will not run; the first reason sticks
+    pub fn skip(&self, reason: String) {
+        let _ = self.skipped.set(reason);
+    }
 }
 
-/// Create test file filled with random data
-fn create_test_file(path: &PathBuf) -> std::io::Result<()> {
-    if path.exists() {
-        // Check if file is correct size
-        if let Ok(metadata) = std::fs::metadata(path) {
-            if metadata.len() == TEST_FILE_SIZE {
-                return Ok(());
-            }
+/// Live state of one storage device, streamed through `StressStats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceStats {
+    pub device: String,
+    pub kind: StorageKind,
+    /// Test file, or the device itself when `raw`
+    pub test_path: Option<String>,
+    pub raw: bool,
+    pub temp_c: Option<f32>,
+    pub temp_max: Option<f32>,
+    /// O_DIRECT or buffered, once the test file exists
+    pub io_mode: Option<IoMode>,
+    /// I/O engine and queue depth, once the workers start
+    pub queue: Option<QueueSetup>,
+    /// IOPS, bandwidth and p99 latency of the workers
+    pub load: Option<NvmeLoad>,
+    /// Why the test is not running here, e.g. not enough free space
+    pub skipped: Option<String>,
+    pub errors: u64,
+}
+
+/// Outcome of the storage stress on one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceReport {
+    pub device: String,
+    pub kind: StorageKind,
+    pub model: String,
+    /// Test file, or the device itself when `raw`
+    pub test_path: Option<String>,
+    /// The whole device was overwritten
+    pub raw: bool,
+    pub errors: u64,
+    /// Blocks that read back wrong (first 64 in detail, all counted)
+    pub corruptions: Vec<BlockCorruption>,
+    pub corrupt_blocks: u64,
+    /// Buffered means reads may have been served from the page cache
+    pub io_mode: Option<IoMode>,
+    pub queue: Option<QueueSetup>,
+    pub read_latency: Option<LatencySummary>,
+    pub write_latency: Option<LatencySummary>,
+    /// Requests over the --nvme-slow-io timeout; a warning, not a failure
+    pub slow_ios: u64,
+    pub slow_io_records: Vec<SlowIo>,
+    pub file_size: Option<u64>,
+    /// Why the test did not run here (not counted as a failure)
+    pub skipped: Option<String>,
+    pub max_temp: Option<f32>,
+}
+
+/// Where the stress I/O of a device goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum IoTarget {
+    /// A test file on one of the device's filesystems
+    File(PathBuf),
+    /// The whole block device, overwritten (--raw-device)
+    Raw(StorageDevice),
+}
+
+impl IoTarget {
+    pub fn path(&self) -> &Path {
+        match self {
+            IoTarget::File(path) => path,
+            IoTarget::Raw(device) => Path::new(&device.device_path),
         }
-        std::fs::remove_file(path)?;
     }
+}
 
-    let mut file = File::create(path)?;
-    let mut rng = rand::thread_rng();
+/// A storage device under test and the state its workers share
+pub struct Target {
+    pub device: StorageDevice,
+    /// None when the device has nowhere to run the stress
+    pub io_target: Option<IoTarget>,
+    pub shared: Arc<NvmeShared>,
+    meter: LoadMeter,
+    temp_max: Option<f32>,
+}
 
-    // Write in chunks
-    let mut buffer = vec![0u8; 1024 * 1024]; // 1 MB buffer
-    let mut written = 0u64;
+impl Target {
+    pub fn new(device: StorageDevice, io_target: Option<IoTarget>) -> Self {
+        Self {
+            device,
+            io_target,
+            shared: Arc::new(NvmeShared::default()),
+            meter: LoadMeter::default(),
+            temp_max: None,
+        }
+    }
 
-    while written < TEST_FILE_SIZE {
-        rng.fill(&mut buffer[..]);
-        file.write_all(&buffer)?;
-        written += buffer.len() as u64;
+    pub fn errors(&self) -> u64 {
+        self.shared.errors.load(Ordering::Relaxed)
     }
 
-    file.sync_all()?;
-    Ok(())
+    fn test_path(&self) -> Option<String> {
+        self.io_target
+            .as_ref()
+            .map(|target| target.path().display().to_string())
+    }
+
+    fn is_raw(&self) -> bool {
+        matches!(self.io_target, Some(IoTarget::Raw(_)))
+    }
+
+    /// Highest drive temperature seen by `sample`
+    pub fn temp_max(&self) -> Option<f32> {
+        self.temp_max
+    }
+
+    /// Current load and temperature; call once per stats update
+    pub fn sample(&mut self) -> DeviceStats {
+        let temp_c = self.device.temperature();
+        if let Some(temp) = temp_c {
+            self.temp_max = Some(self.temp_max.map_or(temp, |max| max.max(temp)));
+        }
+        DeviceStats {
+            device: self.device.name.clone(),
+            kind: self.device.kind,
+            test_path: self.test_path(),
+            raw: self.is_raw(),
+            temp_c,
+            temp_max: self.temp_max,
+            io_mode: self.shared.io_mode(),
+            queue: self.shared.queue(),
+            load: self.meter.sample(&self.shared),
+            skipped: self.shared.skipped(),
+            errors: self.errors(),
+        }
+    }
+
+    pub fn report(&self) -> DeviceReport {
+        let shared = &self.shared;
+        DeviceReport {
+            device: self.device.name.clone(),
+            kind: self.device.kind,
+            model: self.device.model.clone(),
+            test_path: self.test_path(),
+            raw: self.is_raw(),
+            errors: self.errors(),
+            corruptions: shared.corruptions(),
+            corrupt_blocks: shared.corrupt_blocks(),
+            io_mode: shared.io_mode(),
+            queue: shared.queue(),
+            read_latency: shared.read_latency(),
+            write_latency: shared.write_latency(),
+            slow_ios: shared.slow_ios(),
+            slow_io_records: shared.slow_io_records(),
+            file_size: shared.file_size(),
+            skipped: shared.skipped(),
+            max_temp: self.temp_max,
+        }
+    }
 }
 
-/// 4K random read/write stress - IOPS test
-fn run_random_4k_stress(path: &PathBuf, running: &Arc<AtomicBool>) -> bool {
-    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
-        Ok(f) => f,
-        Err(_) => return false,
-    };
+/// The test file shared by all I/O workers
+struct TestFile {
+    path: PathBuf,
+    size: u64,
+    run_seed: u64,
+    io_mode: IoMode,
+}
 
-    let mut rng = rand::thread_rng();
-    let mut buffer = vec![0u8; BLOCK_SIZE_4K];
-    let max_offset = TEST_FILE_SIZE - BLOCK_SIZE_4K as u64;
+impl TestFile {
+    fn new(path: PathBuf, size: u64) -> Self {
+        Self {
+            path,
+            size,
+            run_seed: rand::random(),
+            io_mode: IoMode::Buffered,
+        }
+    }
 
-    // Do 1000 random I/O operations per iteration
-    for _ in 0..1000 {
-        if !running.load(Ordering::Relaxed) {
-            break;
+    fn open(&self) -> io::Result<File> {
+        open_file(&self.path, self.io_mode)
+    }
+}
+
+/// One worker's slice of the test file and the generation each of its 4K
+/// blocks was last written with
+/// Only the owning worker issues requests here, so the generations always
+/// match what the drive was last asked to store. Every write gets a newer
+/// generation than any before it, except that a sequential pass gives all of
+/// its blocks the same one; generations are kept per run of equal blocks, so
+/// a whole raw device costs a few entries for the blocks random I/O touched
+/// since the last full pass.
+struct Region {
+    start: u64,
+    len: u64,
+    run_seed: u64,
+    /// Last generation handed out
+    latest: u64,
+    /// Generation of the blocks from each offset up to the next one
+    runs: BTreeMap<u64, u64>,
+}
+
+impl Region {
+    fn new(start: u64, len: u64, run_seed: u64) -> Self {
+        Self {
+            start,
+            len,
+            run_seed,
+            latest: 0,
+            runs: BTreeMap::from([(start, 0)]),
         }
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.len
+    }
 
-        let offset = rng.gen_range(0..max_offset);
-        let is_read = rng.gen_bool(0.5);
+    /// A generation newer than every block's
+    fn next_generation(&mut self) -> u64 {
+        self.latest += 1;
+        self.latest
+    }
 
-        if file.seek(SeekFrom::Start(offset)).is_err() {
-            return false;
+    /// Payload seed of a block, derived so that only generations need storing
+    fn seed(&self, offset: u64, generation: u64) -> u64 {
+        splitmix64(self.run_seed ^ offset.rotate_left(17) ^ generation)
+    }
+
+    /// Fill `buffer` with `generation` of the blocks starting at `offset`
+    /// Call `written` once the buffer has actually been written.
+    fn prepare(&self, buffer: &mut [u8], offset: u64, generation: u64) {
+        for (i, block) in buffer.chunks_exact_mut(BLOCK_SIZE_4K).enumerate() {
+            let offset = offset + (i * BLOCK_SIZE_4K) as u64;
+            fill_block(block, offset, generation, self.seed(offset, generation));
         }
+    }
+
+    /// Record that `buffer` prepared at `offset` reached the file
+    fn written(&mut self, offset: u64, buffer: &[u8]) {
+        let generation = read_header(buffer).generation;
+        let end = offset + buffer.len() as u64;
+        let after = (end < self.end()).then(|| self.generation(end));
+
+        let replaced: Vec<u64> = self.runs.range(offset..=end).map(|(&at, _)| at).collect();
+        for at in replaced {
+            self.runs.remove(&at);
+        }
+        // Neighbours of the same generation merge, so a pass ends as one run
+        if let Some(after) = after.filter(|&after| after != generation) {
+            self.runs.insert(end, after);
+        }
+        if self.runs.range(..offset).next_back().map(|(_, &g)| g) != Some(generation) {
+            self.runs.insert(offset, generation);
+        }
+    }
 
-        if is_read {
-            if file.read_exact(&mut buffer).is_err() {
-                return false;
+    fn generation(&self, offset: u64) -> u64 {
+        self.runs
+            .range(..=offset)
+            .next_back()
+            .map_or(0, |(_, &g)| g)
+    }
+
+    /// Check blocks read from `offset`, recording every corrupt one
+    /// Returns the number of corrupt blocks.
+    fn verify(
+        &self,
+        buffer: &[u8],
+        offset: u64,
+        workload: NvmeWorkload,
+        shared: &NvmeShared,
+    ) -> usize {
+        let mut bad = 0;
+        for (i, block) in buffer.chunks_exact(BLOCK_SIZE_4K).enumerate() {
+            let offset = offset + (i * BLOCK_SIZE_4K) as u64;
+            let generation = self.generation(offset);
+            let actual = read_header(block);
+            let checksum_valid = block_checksum(block) == actual.checksum;
+            let seed = self.seed(offset, generation);
+            if checksum_valid
+                && actual.magic == BLOCK_MAGIC
+                && (actual.offset, actual.generation, actual.seed) == (offset, generation, seed)
+            {
+                continue;
             }
+
+            let mut expected_block = vec![0u8; BLOCK_SIZE_4K];
+            fill_block(&mut expected_block, offset, generation, seed);
+            shared.record_corruption(BlockCorruption {
+                workload: workload.name().to_string(),
+                offset,
+                expected: read_header(&expected_block),
+                actual,
+                checksum_valid,
+                timestamp_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as u64),
+            });
+            bad += 1;
+        }
+        bad
+    }
+}
+
+/// Split the test file into one contiguous, whole-megabyte region per worker
+/// The last region also takes any 4K blocks past the last whole megabyte,
+/// which only a raw device has.
+fn split_regions(size: u64, workers: usize, run_seed: u64) -> Vec<Region> {
+    let megabytes = size / MB;
+    let boundary = |i: usize| {
+        if i == workers {
+            size / BLOCK_SIZE_4K as u64 * BLOCK_SIZE_4K as u64
         } else {
-            rng.fill(&mut buffer[..]);
-            if file.write_all(&buffer).is_err() {
-                return false;
-            }
+            megabytes * i as u64 / workers as u64 * MB
         }
+    };
+    (0..workers)
+        .map(|i| Region::new(boundary(i), boundary(i + 1) - boundary(i), run_seed))
+        .collect()
+}
+
+fn word(block: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(block[i * 8..][..8].try_into().unwrap())
+}
+
+/// Write a self-describing block: header, then payload words from `seed`
+fn fill_block(block: &mut [u8], offset: u64, generation: u64, seed: u64) {
+    let header: [u64; HEADER_WORDS] = [BLOCK_MAGIC, offset, generation, seed, 0];
+    for (i, chunk) in block.chunks_exact_mut(8).enumerate() {
+        let value = match header.get(i) {
+            Some(&value) => value,
+            None => splitmix64(seed.wrapping_add(i as u64)),
+        };
+        chunk.copy_from_slice(&value.to_le_bytes());
     }
+    let checksum = block_checksum(block);
+    block[CHECKSUM_WORD * 8..][..8].copy_from_slice(&checksum.to_le_bytes());
+}
 
-    // Sync to ensure writes are committed
-    if file.sync_all().is_err() {
-        return false;
+fn read_header(block: &[u8]) -> BlockHeader {
+    BlockHeader {
+        magic: word(block, 0),
+        offset: word(block, 1),
+        generation: word(block, 2),
+        seed: word(block, 3),
+        checksum: word(block, CHECKSUM_WORD),
     }
+}
 
-    true
+/// Hash of every word in the block except the checksum itself
+fn block_checksum(block: &[u8]) -> u64 {
+    block
+        .chunks_exact(8)
+        .enumerate()
+        .filter(|&(i, _)| i != CHECKSUM_WORD)
+        .fold(0xCBF2_9CE4_8422_2325, |hash, (i, chunk)| {
+            let value = u64::from_le_bytes(chunk.try_into().unwrap());
+            (hash ^ value ^ i as u64)
+                .wrapping_mul(0x0000_0100_0000_01B3)
+                .rotate_left(29)
+        })
 }
 
-/// Sequential read/write stress - bandwidth test
-fn run_sequential_stress(path: &PathBuf, running: &Arc<AtomicBool>) -> bool {
-    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
-        Ok(f) => f,
-        Err(_) => return false,
+impl NvmeWorkload {
+    /// All workloads, in rotation order
+    pub const ALL: [NvmeWorkload; 3] = [
+        NvmeWorkload::Random4k,
+        NvmeWorkload::Sequential,
+        NvmeWorkload::Mixed,
+    ];
+}
+
+impl Workload for NvmeWorkload {
+    const VARIANTS: &'static [Self] = &Self::ALL;
+
+    fn name(&self) -> &'static str {
+        match self {
+            NvmeWorkload::Random4k => "random4k",
+            NvmeWorkload::Sequential => "sequential",
+            NvmeWorkload::Mixed => "mixed",
+        }
+    }
+}
+
+/// Run NVMe stress test on one device, on a test file or the raw device
+/// Each worker owns one region of the test file and keeps its own queue of
+/// requests in flight there, so several streams hit the drive at once.
+pub fn run_nvme_stress(
+    running: Arc<AtomicBool>,
+    shared: Arc<NvmeShared>,
+    rates: Vec<Arc<WorkerThroughput>>,
+    heartbeats: Vec<Arc<Heartbeat>>,
+    target: IoTarget,
+    options: NvmeOptions,
+) {
+    // Held until the workers finish, so nothing can mount the raw device meanwhile
+    let mut claim = None;
+    let test_file = match target {
+        IoTarget::File(path) => {
+            // Size the file before writing anything; a full drive is not a stability failure
+            let size = match free_space(&path).and_then(|free| size_test_file(options.size, free)) {
+                Ok(size) => size,
+                Err(reason) => {
+                    shared.skip(reason);
+                    return;
+                }
+            };
+            let mut test_file = TestFile::new(path, size);
+            if let Err(e) = create_test_file(&mut test_file) {
+                eprintln!("Failed to create NVMe test file: {}", e);
+                shared.errors.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            test_file
+        }
+        IoTarget::Raw(device) => match claim_raw_device(&device) {
+            Ok((test_file, file)) => {
+                claim = Some(file);
+                test_file
+            }
+            // A device in use is refused, like a full drive, not failed
+            Err(reason) => {
+                shared.skip(reason);
+                return;
+            }
+        },
     };
+    let size = test_file.size;
+    let _ = shared.file_size.set(size);
+    if test_file.io_mode == IoMode::Buffered {
+        eprintln!(
+            "Warning: {} does not support O_DIRECT, using buffered I/O (reads may come from the page cache)",
+            test_file.path.display()
+        );
+    }
+    let _ = shared.io_mode.set(test_file.io_mode);
+
+    let engine = options.engine.available();
+    if engine != options.engine {
+        eprintln!("Warning: io_uring is not available, using the thread-pool I/O engine");
+    }
+    let options = NvmeOptions { engine, ..options };
+    let _ = shared.queue.set(QueueSetup {
+        engine,
+        workers: heartbeats.len(),
+        queue_depth: options.queue_depth,
+        block_size: options.block_size,
+        read_percent: options.read_percent,
+    });
 
-    let mut rng = rand::thread_rng();
-    let mut buffer = vec![0u8; BLOCK_SIZE_SEQ];
+    let regions = split_regions(size, heartbeats.len(), test_file.run_seed);
+    std::thread::scope(|scope| {
+        for ((region, heartbeat), rates) in regions.into_iter().zip(&heartbeats).zip(&rates) {
+            let (test_file, options, running, shared) = (&test_file, &options, &*running, &*shared);
+            scope.spawn(move || {
+                run_worker(
+                    test_file, region, options, running, heartbeat, rates, shared,
+                )
+            });
+        }
+    });
 
-    // Sequential write
-    if file.seek(SeekFrom::Start(0)).is_err() {
-        return false;
+    // Cleanup test file; a raw device is left holding the test pattern
+    if claim.is_none() {
+        let _ = std::fs::remove_file(&test_file.path);
     }
+}
 
-    let mut written = 0u64;
-    while written < TEST_FILE_SIZE && running.load(Ordering::Relaxed) {
-        rng.fill(&mut buffer[..]);
-        if file.write_all(&buffer).is_err() {
-            return false;
+/// Fill a region, then rotate through the workloads until the run stops
+fn run_worker(
+    test: &TestFile,
+    region: Region,
+    options: &NvmeOptions,
+    running: &AtomicBool,
+    heartbeat: &Heartbeat,
+    throughput: &WorkerThroughput,
+    shared: &NvmeShared,
+) {
+    let (start, end) = (region.start, region.end());
+    let mut worker = match Worker::new(test, region, options, running, heartbeat, shared) {
+        Ok(worker) => worker,
+        Err(e) => {
+            eprintln!(
+                "Failed to open {} for NVMe stress: {}",
+                test.path.display(),
+                e
+            );
+            shared.errors.fetch_add(1, Ordering::Relaxed);
+            return;
         }
-        written += buffer.len() as u64;
+    };
+    if let Err(e) = worker.fill() {
+        eprintln!(
+            "Failed to fill {} at {}..{}: {}",
+            test.path.display(),
+            start,
+            end,
+            e
+        );
+        shared.errors.fetch_add(1, Ordering::Relaxed);
+        return;
     }
 
-    if file.sync_all().is_err() {
-        return false;
+    let mut iteration: u64 = 0;
+    let mut reported = false;
+
+    while running.load(Ordering::Relaxed) {
+        // Rotate between different stress methods
+        let workload = NvmeWorkload::ALL[(iteration % 3) as usize];
+        let started = Instant::now();
+        // Corrupt blocks are counted as they are found
+        match worker.run(workload) {
+            Ok(0) => throughput.record(workload, started.elapsed()),
+            Ok(_) => {}
+            Err(e) => {
+                // A failing drive fails every pass; later ones are only counted
+                if !reported {
+                    eprintln!(
+                        "NVMe {} pass on {} failed: {}",
+                        workload.name(),
+                        test.path.display(),
+                        e
+                    );
+                    reported = true;
+                }
+                shared.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        iteration = iteration.wrapping_add(1);
     }
+}
 
-    // Sequential read
-    if file.seek(SeekFrom::Start(0)).is_err() {
-        return false;
+/// Where to put the test file for `device`, given the mount table
+/// custom_path: User-specified path via --nvme-path flag
+/// None when no writable filesystem of the device is mounted.
+pub fn get_test_file_path(
+    device: &StorageDevice,
+    mounts: &str,
+    custom_path: Option<&str>,
+) -> Option<PathBuf> {
+    // 1. If user specified custom path, use it
+    if let Some(path) = custom_path {
+        return Some(PathBuf::from(path));
     }
 
-    let mut read = 0u64;
-    while read < TEST_FILE_SIZE && running.load(Ordering::Relaxed) {
-        if file.read_exact(&mut buffer).is_err() {
-            return false;
+    let mounts: Vec<_> = storage::mounts_of(device, mounts)
+        .into_iter()
+        .filter(|m| !m.read_only)
+        .collect();
+
+    // 2. Root is on this device? Use home dir or /var/tmp (both on it),
+    //    NOT /tmp which is often tmpfs in RAM!
+    if mounts.iter().any(|m| m.mount_point == "/") {
+        // Try user's cache directory first
+        if let Some(home) = std::env::var_os("HOME") {
+            let mut cache_path = PathBuf::from(home);
+            cache_path.push(".cache");
+            cache_path.push("pi-under-pressure");
+            // Create cache dir if needed
+            let _ = std::fs::create_dir_all(&cache_path);
+            cache_path.push(format!("{}-test", device.name));
+            return Some(cache_path);
         }
-        read += buffer.len() as u64;
+        // Fallback to /var/tmp (persistent, on root fs)
+        return Some(PathBuf::from(format!(
+            "/var/tmp/.pi-under-pressure-{}-test",
+            device.name
+        )));
     }
 
-    true
+    // 3. Otherwise a data partition, skipping the boot partitions
+    mounts
+        .iter()
+        .find(|m| m.mount_point != "/boot" && m.mount_point != "/boot/firmware")
+        .map(|m| Path::new(&m.mount_point).join(".pi-under-pressure-test"))
 }
 
-/// Mixed workload stress - 70/30 read/write ratio
-fn run_mixed_stress(path: &PathBuf, running: &Arc<AtomicBool>) -> bool {
-    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
-        Ok(f) => f,
-        Err(_) => return false,
+/// Free bytes for an unprivileged writer on the filesystem holding `path`
+/// A test file left behind by an earlier run counts as free, since it is replaced.
+fn free_space(path: &Path) -> Result<u64, String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
     };
+    let c_dir = std::ffi::CString::new(dir.as_os_str().as_encoded_bytes())
+        .map_err(|_| format!("Invalid NVMe test path {}", path.display()))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_dir.as_ptr(), &mut stat) } != 0 {
+        return Err(format!(
+            "Cannot check free space in {}: {}",
+            dir.display(),
+            io::Error::last_os_error()
+        ));
+    }
+    let leftover = std::fs::metadata(path).map_or(0, |m| m.len());
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64 + leftover)
+}
 
-    let mut rng = rand::thread_rng();
-    let mut buffer = vec![0u8; BLOCK_SIZE_4K];
-    let max_offset = TEST_FILE_SIZE - BLOCK_SIZE_4K as u64;
+/// Test file size for `free` bytes of free space
+/// The default is half the space above the reserve, capped at 8 GB; an
+/// explicit size must fit above the reserve. Sizes are whole megabytes.
+fn size_test_file(requested: Option<u64>, free: u64) -> Result<u64, String> {
+    let usable = free.saturating_sub(FREE_SPACE_RESERVE);
+    let size = match requested {
+        Some(bytes) if bytes > usable => {
+            return Err(format!(
+                "Drive too small for --nvme-size {} MB: {} MB free, {} MB kept in reserve",
+                bytes / MB,
+                free / MB,
+                FREE_SPACE_RESERVE / MB
+            ))
+        }
+        Some(bytes) => bytes,
+        None => (usable / 2).min(DEFAULT_TEST_FILE_SIZE),
+    };
+    let size = size / MB * MB;
+    if size < MIN_TEST_FILE_SIZE {
+        return Err(format!(
+            "Drive too small: {} MB free, need at least {} MB for the test file plus {} MB reserve",
+            free / MB,
+            MIN_TEST_FILE_SIZE / MB,
+            FREE_SPACE_RESERVE / MB
+        ));
+    }
+    Ok(size)
+}
+
+/// Create the test file at its full size and pick the I/O mode
+/// Any file left behind by an earlier run is replaced, since its blocks
+/// cannot be verified against this run's seed. Uses O_DIRECT if the
+/// filesystem accepts it. The workers write every block before reading any.
+fn create_test_file(test: &mut TestFile) -> io::Result<()> {
+    if test.path.exists() {
+        std::fs::remove_file(&test.path)?;
+    }
 
-    // Do 1000 mixed I/O operations
-    for _ in 0..1000 {
-        if !running.load(Ordering::Relaxed) {
-            break;
+    File::create(&test.path)?.set_len(test.size)?;
+    test.io_mode = if supports_direct_io(&test.path) {
+        IoMode::Direct
+    } else {
+        IoMode::Buffered
+    };
+    Ok(())
+}
+
+/// Take the whole block device for the run
+/// Refuses if anything uses the device, then opens it with O_EXCL, which
+/// the kernel only grants while nothing has it mounted or claimed, and
+/// which keeps it from being mounted until the returned handle is closed.
+/// The size is rounded down to whole 4K blocks.
+fn claim_raw_device(device: &StorageDevice) -> Result<(TestFile, File), String> {
+    let path = &device.device_path;
+    let users = storage::users_of(device);
+    if !users.is_empty() {
+        return Err(format!(
+            "Refusing to overwrite {}: {}",
+            path,
+            users.join(", ")
+        ));
+    }
+    let mut claim = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_EXCL)
+        .open(path)
+        .map_err(|e| format!("Cannot claim {} for exclusive use: {}", path, e))?;
+    let size = claim
+        .seek(SeekFrom::End(0))
+        .map_err(|e| format!("Cannot read the size of {}: {}", path, e))?
+        / BLOCK_SIZE_4K as u64
+        * BLOCK_SIZE_4K as u64;
+    if size < MIN_TEST_FILE_SIZE {
+        return Err(format!(
+            "{} is too small: {} MB, need at least {} MB",
+            path,
+            size / MB,
+            MIN_TEST_FILE_SIZE / MB
+        ));
+    }
+
+    let mut test = TestFile::new(PathBuf::from(path), size);
+    test.io_mode = if supports_direct_io(&test.path) {
+        IoMode::Direct
+    } else {
+        IoMode::Buffered
+    };
+    Ok((test, claim))
+}
+
+/// Whether an aligned O_DIRECT write to the test file works
+/// Some filesystems (tmpfs, some FUSE and network mounts) refuse O_DIRECT at
+/// open, others only fail the first I/O with EINVAL.
+fn supports_direct_io(path: &Path) -> bool {
+    open_file(path, IoMode::Direct)
+        .and_then(|file| file.write_all_at(&AlignedBuffer::new(BLOCK_SIZE_4K), 0))
+        .is_ok()
+}
+
+fn open_file(path: &Path, mode: IoMode) -> io::Result<File> {
+    let flags = match mode {
+        IoMode::Direct => libc::O_DIRECT,
+        IoMode::Buffered => 0,
+    };
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(flags)
+        .open(path)
+}
+
+/// One I/O worker: its region of the test file and the queue it keeps full
+struct Worker<'a> {
+    region: Region,
+    file: Arc<File>,
+    queue: IoQueue,
+    options: &'a NvmeOptions,
+    running: &'a AtomicBool,
+    heartbeat: &'a Heartbeat,
+    shared: &'a NvmeShared,
+}
+
+impl<'a> Worker<'a> {
+    fn new(
+        test: &TestFile,
+        region: Region,
+        options: &'a NvmeOptions,
+        running: &'a AtomicBool,
+        heartbeat: &'a Heartbeat,
+        shared: &'a NvmeShared,
+    ) -> io::Result<Self> {
+        let file = Arc::new(test.open()?);
+        let queue = IoQueue::new(options.engine, file.clone(), options.queue_depth)?;
+        Ok(Self {
+            region,
+            file,
+            queue,
+            options,
+            running,
+            heartbeat,
+            shared,
+        })
+    }
+
+    /// One pass of `workload`; returns the number of corrupt blocks found
+    fn run(&mut self, workload: NvmeWorkload) -> io::Result<usize> {
+        match workload {
+            // 4K random read/write stress - IOPS test
+            NvmeWorkload::Random4k => self.random_io(0.5, workload),
+            NvmeWorkload::Sequential => self.sequential(),
+            // Mixed workload stress - read-heavy by default (70/30)
+            NvmeWorkload::Mixed => {
+                self.random_io(self.options.read_percent as f64 / 100.0, workload)
+            }
         }
+    }
+
+    /// Write every block of the region at one new generation
+    fn fill(&mut self) -> io::Result<()> {
+        self.stream(IoKind::Write, NvmeWorkload::Sequential)?;
+        self.file.sync_all()
+    }
+
+    /// Sequential read/write stress - bandwidth test
+    /// Rewrites the region with a new generation, then reads back and verifies it.
+    fn sequential(&mut self) -> io::Result<usize> {
+        self.fill()?;
+        self.stream(IoKind::Read, NvmeWorkload::Sequential)
+    }
+
+    /// Front-to-back requests over the whole region
+    /// Blocks past the last whole sequential request go 4K at a time.
+    fn stream(&mut self, kind: IoKind, workload: NvmeWorkload) -> io::Result<usize> {
+        let (start, end) = (self.region.start, self.region.end());
+        let tail = end - (end - start) % BLOCK_SIZE_SEQ as u64;
+        let pass = (kind == IoKind::Write).then(|| self.region.next_generation());
+        let mut offsets = (start..tail).step_by(BLOCK_SIZE_SEQ);
+        let mut bad = self.pump(workload, BLOCK_SIZE_SEQ, usize::MAX, pass, |_| {
+            offsets.next().map(|offset| (kind, offset))
+        })?;
+        let mut offsets = (tail..end).step_by(BLOCK_SIZE_4K);
+        bad += self.pump(workload, BLOCK_SIZE_4K, usize::MAX, pass, |_| {
+            offsets.next().map(|offset| (kind, offset))
+        })?;
+        Ok(bad)
+    }
+
+    /// Random reads and writes of the configured block size at aligned offsets
+    fn random_io(&mut self, read_fraction: f64, workload: NvmeWorkload) -> io::Result<usize> {
+        let block_size = self.options.block_size as u64;
+        let (start, slots) = (self.region.start, self.region.len / block_size);
+        let mut rng = rand::thread_rng();
+        let mut remaining = RANDOM_OPS;
+
+        let bad = self.pump(
+            workload,
+            block_size as usize,
+            slots as usize,
+            None,
+            |in_flight| {
+                remaining = remaining.checked_sub(1)?;
+                let kind = if rng.gen_bool(read_fraction) {
+                    IoKind::Read
+                } else {
+                    IoKind::Write
+                };
+                // Never two requests on the same block at once
+                loop {
+                    let offset = start + rng.gen_range(0..slots) * block_size;
+                    if !in_flight.contains(&offset) {
+                        return Some((kind, offset));
+                    }
+                }
+            },
+        )?;
 
-        let offset = rng.gen_range(0..max_offset);
-        let is_read = rng.gen_bool(0.7); // 70% reads
+        // Sync to ensure writes are committed
+        self.file.sync_all()?;
+        Ok(bad)
+    }
 
-        if file.seek(SeekFrom::Start(offset)).is_err() {
-            return false;
+    /// Keep up to `max_in_flight` requests of `len` bytes going, taken from
+    /// `next` until it runs dry or the run stops
+    /// `next` is shown the offsets in flight. Writes of a sequential `pass`
+    /// all carry its generation, other writes a new one each; every read is
+    /// verified. Returns the number of corrupt blocks found.
+    fn pump(
+        &mut self,
+        workload: NvmeWorkload,
+        len: usize,
+        max_in_flight: usize,
+        pass: Option<u64>,
+        mut next: impl FnMut(&HashSet<u64>) -> Option<(IoKind, u64)>,
+    ) -> io::Result<usize> {
+        // Requests left over from a failed pass have the wrong size; writes
+        // among them that completed still moved their blocks on a generation
+        for IoCompletion {
+            request, result, ..
+        } in self.queue.drain()?
+        {
+            if result.is_ok() && request.kind == IoKind::Write {
+                self.region.written(request.offset, &request.buffer);
+            }
         }
 
-        if is_read {
-            if file.read_exact(&mut buffer).is_err() {
-                return false;
+        let depth = self.queue.depth().min(max_in_flight);
+        let mut buffers: Vec<AlignedBuffer> = (0..depth).map(|_| AlignedBuffer::new(len)).collect();
+        let mut in_flight = HashSet::new();
+        let mut bad = 0;
+        let mut error = None;
+        let mut more = true;
+
+        loop {
+            while more && error.is_none() && self.running.load(Ordering::Relaxed) {
+                let Some(mut buffer) = buffers.pop() else {
+                    break;
+                };
+                let Some((kind, offset)) = next(&in_flight) else {
+                    more = false;
+                    break;
+                };
+                if kind == IoKind::Write {
+                    let generation = pass.unwrap_or_else(|| self.region.next_generation());
+                    self.region.prepare(&mut buffer, offset, generation);
+                }
+                in_flight.insert(offset);
+                self.queue.submit(IoRequest {
+                    kind,
+                    offset,
+                    buffer,
+                })?;
             }
-        } else {
-            rng.fill(&mut buffer[..]);
-            if file.write_all(&buffer).is_err() {
-                return false;
+            if self.queue.in_flight() == 0 {
+                break;
+            }
+
+            self.heartbeat.beat();
+            for IoCompletion {
+                request,
+                result,
+                latency,
+            } in self.queue.wait()?
+            {
+                in_flight.remove(&request.offset);
+                if result.is_ok() {
+                    self.shared
+                        .record_io(&request, latency, self.options.slow_io, workload);
+                }
+                match (result, request.kind) {
+                    (Err(e), _) => {
+                        error.get_or_insert(e);
+                    }
+                    (Ok(()), IoKind::Read) => {
+                        bad += self.region.verify(
+                            &request.buffer,
+                            request.offset,
+                            workload,
+                            self.shared,
+                        )
+                    }
+                    (Ok(()), IoKind::Write) => self.region.written(request.offset, &request.buffer),
+                }
+                buffers.push(request.buffer);
             }
         }
-    }
 
-    if file.sync_all().is_err() {
-        return false;
+        error.map_or(Ok(bad), Err)
     }
-
-    true
 }
 
 #[cfg(test)]
@@ -292,17 +1310,316 @@ mod tests {
 
     #[test]
     fn test_get_test_file_path() {
-        let nvme = NvmeInfo {
-            device_path: "/dev/nvme0n1".to_string(),
+        let device = |name: &str| StorageDevice {
+            name: name.to_string(),
+            device_path: format!("/dev/{}", name),
+            kind: StorageKind::Nvme,
             model: "Test".to_string(),
+            size_bytes: 1 << 40,
             pcie_gen: Some(3),
         };
+        let mounts = "\
+/dev/nvme0n1p2 / ext4 rw,noatime 0 0
+/dev/nvme0n1p1 /boot/firmware vfat rw 0 0
+/dev/nvme1n1p1 /boot/firmware vfat rw 0 0
+/dev/nvme1n1p2 /mnt/data ext4 rw 0 0
+/dev/nvme2n1p1 /mnt/archive ext4 ro 0 0
+";
         // Test with auto-detection (None)
-        let path = get_test_file_path(&nvme, None);
+        let path = get_test_file_path(&device("nvme0n1"), mounts, None).unwrap();
         assert!(path.to_string_lossy().contains("pi-under-pressure"));
+        assert!(path.to_string_lossy().ends_with("nvme0n1-test"));
+        assert_eq!(
+            get_test_file_path(&device("nvme1n1"), mounts, None),
+            Some(PathBuf::from("/mnt/data/.pi-under-pressure-test"))
+        );
+        // Read-only or unmounted: nowhere to put the file
+        assert_eq!(get_test_file_path(&device("nvme2n1"), mounts, None), None);
+        assert_eq!(get_test_file_path(&device("nvme3n1"), mounts, None), None);
 
         // Test with custom path
-        let custom_path = get_test_file_path(&nvme, Some("/custom/path/test"));
-        assert_eq!(custom_path.to_string_lossy(), "/custom/path/test");
+        let custom_path = get_test_file_path(&device("nvme3n1"), mounts, Some("/custom/path/test"));
+        assert_eq!(custom_path, Some(PathBuf::from("/custom/path/test")));
+    }
+
+    fn scratch_file(name: &str, blocks: u64) -> TestFile {
+        let path = std::env::temp_dir().join(format!("piup-nvme-{}-{}", name, std::process::id()));
+        TestFile::new(path, blocks * BLOCK_SIZE_4K as u64)
+    }
+
+    #[test]
+    fn test_blocks_describe_themselves() {
+        let mut region = Region::new(0, 4 * BLOCK_SIZE_4K as u64, 42);
+        let mut buffer = vec![0u8; 2 * BLOCK_SIZE_4K];
+        let generation = region.next_generation();
+        region.prepare(&mut buffer, BLOCK_SIZE_4K as u64, generation);
+
+        let header = read_header(&buffer[BLOCK_SIZE_4K..]);
+        assert_eq!(header.magic, BLOCK_MAGIC);
+        assert_eq!(header.offset, 2 * BLOCK_SIZE_4K as u64);
+        assert_eq!(header.generation, 1);
+        assert_eq!(header.checksum, block_checksum(&buffer[BLOCK_SIZE_4K..]));
+    }
+
+    #[test]
+    fn test_split_regions() {
+        let regions = split_regions(1000 * MB, 3, 42);
+        let bounds: Vec<_> = regions.iter().map(|r| (r.start, r.end())).collect();
+        assert_eq!(
+            bounds,
+            [(0, 333 * MB), (333 * MB, 666 * MB), (666 * MB, 1000 * MB)]
+        );
+        assert_eq!(regions[1].generation(400 * MB), 0);
+
+        // A raw device's tail past the last megabyte goes to the last region
+        let regions = split_regions(1000 * MB + 3 * 4096 + 512, 3, 42);
+        assert_eq!(regions[1].end(), 666 * MB);
+        assert_eq!(regions[2].end(), 1000 * MB + 3 * 4096);
+    }
+
+    #[test]
+    fn test_generations_are_kept_per_run() {
+        const BLOCK: u64 = BLOCK_SIZE_4K as u64;
+        let mut region = Region::new(0, 16 * BLOCK, 42);
+        let write = |region: &mut Region, offset: u64, blocks: usize, generation| {
+            let mut buffer = vec![0u8; blocks * BLOCK_SIZE_4K];
+            region.prepare(&mut buffer, offset, generation);
+            region.written(offset, &buffer);
+        };
+
+        // Random writes split the region into runs
+        for offset in [3 * BLOCK, 4 * BLOCK, 10 * BLOCK] {
+            let generation = region.next_generation();
+            write(&mut region, offset, 1, generation);
+        }
+        let generations: Vec<_> = (0..16).map(|i| region.generation(i * BLOCK)).collect();
+        assert_eq!(
+            generations,
+            [0, 0, 0, 1, 2, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(region.runs.len(), 6);
+
+        // A sequential pass, completing out of order, collapses them again
+        let pass = region.next_generation();
+        for offset in [8 * BLOCK, 0, 12 * BLOCK, 4 * BLOCK] {
+            write(&mut region, offset, 4, pass);
+        }
+        assert_eq!(region.runs, BTreeMap::from([(0, 4)]));
+    }
+
+    #[test]
+    fn test_size_test_file() {
+        // Default: half the space above the reserve, capped at 8 GB
+        assert_eq!(
+            size_test_file(None, 100 * 1024 * MB),
+            Ok(DEFAULT_TEST_FILE_SIZE)
+        );
+        assert_eq!(size_test_file(None, 3 * 1024 * MB), Ok(1024 * MB));
+        assert_eq!(
+            size_test_file(Some(1536 * MB + 5), 4096 * MB),
+            Ok(1536 * MB)
+        );
+
+        let too_small = size_test_file(None, 1200 * MB).unwrap_err();
+        assert!(too_small.starts_with("Drive too small: 1200 MB free"));
+        let too_big = size_test_file(Some(4096 * MB), 4096 * MB).unwrap_err();
+        assert!(too_big.contains("--nvme-size 4096 MB"));
+        assert!(size_test_file(Some(100 * MB), 4096 * MB).is_err());
+    }
+
+    #[test]
+    fn test_free_space_of_temp_dir() {
+        let path = std::env::temp_dir().join("piup-nvme-free-space-probe");
+        assert!(free_space(&path).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_verify_reports_corrupt_blocks() {
+        let mut region = Region::new(0, 4 * BLOCK_SIZE_4K as u64, 42);
+        let shared = NvmeShared::default();
+        let mut buffer = vec![0u8; 4 * BLOCK_SIZE_4K];
+        let generation = region.next_generation();
+        region.prepare(&mut buffer, 0, generation);
+        region.written(0, &buffer);
+        assert_eq!(
+            region.verify(&buffer, 0, NvmeWorkload::Sequential, &shared),
+            0
+        );
+
+        // Block 1: one flipped payload bit
+        buffer[BLOCK_SIZE_4K + 100] ^= 0x10;
+        // Block 3: the previous generation came back (a lost write)
+        let stale = 3 * BLOCK_SIZE_4K;
+        fill_block(
+            &mut buffer[stale..],
+            stale as u64,
+            0,
+            region.seed(stale as u64, 0),
+        );
+
+        assert_eq!(
+            region.verify(&buffer, 0, NvmeWorkload::Sequential, &shared),
+            2
+        );
+        assert_eq!(shared.errors.load(Ordering::Relaxed), 2);
+        let records = shared.corruptions();
+        assert_eq!(records[0].offset, BLOCK_SIZE_4K as u64);
+        assert_eq!(records[0].kind(), "checksum mismatch");
+        assert_eq!(records[1].kind(), "stale");
+        assert_eq!(records[1].expected.generation, 1);
+        assert_eq!(records[1].actual.generation, 0);
+        assert!(records[1].to_string().starts_with("stale at offset 0x3000"));
+    }
+
+    #[test]
+    fn test_slow_io_is_a_recorded_warning() {
+        let shared = NvmeShared::default();
+        let mut meter = LoadMeter::default();
+        assert_eq!(meter.sample(&shared), None);
+        let _ = shared.queue.set(QueueSetup {
+            engine: IoEngine::Threads,
+            workers: 1,
+            queue_depth: 1,
+            block_size: BLOCK_SIZE_4K,
+            read_percent: 70,
+        });
+
+        let request = IoRequest {
+            kind: IoKind::Write,
+            offset: 0x20000,
+            buffer: AlignedBuffer::new(BLOCK_SIZE_4K),
+        };
+        let timeout = Duration::from_secs(1);
+        shared.record_io(
+            &request,
+            Duration::from_micros(250),
+            timeout,
+            NvmeWorkload::Mixed,
+        );
+        shared.record_io(
+            &request,
+            Duration::from_millis(1800),
+            timeout,
+            NvmeWorkload::Mixed,
+        );
+
+        assert_eq!(shared.errors.load(Ordering::Relaxed), 0);
+        assert_eq!(shared.slow_ios(), 1);
+        assert_eq!(
+            shared.slow_io_records()[0].to_string(),
+            "write of 4K at offset 0x20000 took 1.80 s (mixed)"
+        );
+        let latency = shared.write_latency().unwrap();
+        assert_eq!((latency.count, latency.max_us), (2, 1_800_000));
+        assert_eq!(shared.read_latency(), None);
+
+        let load = meter.sample(&shared).unwrap();
+        assert_eq!((load.slow_ios, load.read_p99_us), (1, None));
+        assert_eq!(load.write_p99_us, Some(1_800_000));
+    }
+
+    #[test]
+    fn test_leftover_writes_are_tracked() {
+        let mut test = scratch_file("leftover", 64);
+        let options = NvmeOptions {
+            engine: IoEngine::Threads,
+            ..NvmeOptions::default()
+        };
+        let shared = NvmeShared::default();
+        let running = AtomicBool::new(true);
+        let heartbeat = Heartbeat::new();
+        create_test_file(&mut test).unwrap();
+        let region = Region::new(0, test.size, test.run_seed);
+        let mut worker =
+            Worker::new(&test, region, &options, &running, &heartbeat, &shared).unwrap();
+        worker.fill().unwrap();
+
+        // A write still in flight when the previous pass bailed out
+        let mut buffer = AlignedBuffer::new(BLOCK_SIZE_4K);
+        let generation = worker.region.next_generation();
+        worker.region.prepare(&mut buffer, 0, generation);
+        worker
+            .queue
+            .submit(IoRequest {
+                kind: IoKind::Write,
+                offset: 0,
+                buffer,
+            })
+            .unwrap();
+
+        let bad = worker.stream(IoKind::Read, NvmeWorkload::Sequential);
+        drop(worker);
+        std::fs::remove_file(&test.path).ok();
+        assert_eq!(bad.unwrap(), 0);
+        assert_eq!(shared.corrupt_blocks(), 0);
+    }
+
+    /// Two workers with four requests each in flight, on their own regions,
+    /// the second with a few 4K blocks past its last sequential request
+    fn run_workers(engine: IoEngine) {
+        let mut test = scratch_file(&format!("workloads-{}", engine), 515);
+        let options = NvmeOptions {
+            engine,
+            workers: 2,
+            queue_depth: 4,
+            block_size: 2 * BLOCK_SIZE_4K,
+            ..NvmeOptions::default()
+        };
+        let shared = NvmeShared::default();
+        let running = AtomicBool::new(true);
+        let heartbeat = Heartbeat::new();
+        create_test_file(&mut test).unwrap();
+
+        let regions = split_regions(test.size, options.workers, test.run_seed);
+        let finished: Vec<io::Result<(usize, Region)>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = regions
+                .into_iter()
+                .map(|region| {
+                    let (test, options, running, heartbeat, shared) =
+                        (&test, &options, &running, &heartbeat, &shared);
+                    scope.spawn(move || {
+                        let mut worker =
+                            Worker::new(test, region, options, running, heartbeat, shared)?;
+                        worker.fill()?;
+                        let mut bad = 0;
+                        for workload in NvmeWorkload::ALL {
+                            bad += worker.run(workload)?;
+                        }
+                        Ok((bad, worker.region))
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        // Whichever mode the file was written in, buffered reads verify it too
+        test.io_mode = IoMode::Buffered;
+        let mut results = Vec::new();
+        for result in finished {
+            let (bad, region) = result.unwrap();
+            results.push(Ok(bad));
+            results.push(
+                Worker::new(&test, region, &options, &running, &heartbeat, &shared)
+                    .and_then(|mut worker| worker.run(NvmeWorkload::Mixed)),
+            );
+        }
+        std::fs::remove_file(&test.path).ok();
+
+        for result in results {
+            assert_eq!(result.unwrap(), 0);
+        }
+        assert_eq!(shared.corrupt_blocks(), 0);
+        // Every block is written at least by the fill and read by the sequential pass
+        assert!(shared.write_latency().unwrap().count >= 512);
+        assert!(shared.read_latency().unwrap().count >= 512);
+    }
+
+    #[test]
+    fn test_workloads_verify_written_data() {
+        run_workers(IoEngine::Threads);
+        // Kernels and sandboxes without io_uring only run the thread pool
+        if IoEngine::IoUring.available() == IoEngine::IoUring {
+            run_workers(IoEngine::IoUring);
+        }
     }
 }
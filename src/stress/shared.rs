@@ -0,0 +1,283 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+/// Types that can live in memory shared with forked worker processes
+///
+/// # Safety
+/// Implementors must not own heap memory or other process-local resources
+/// (no `Vec`, `String`, `Arc` or `Mutex`): only atomics and plain integers,
+/// which stay valid when read and written from several processes.
+pub unsafe trait ProcessShared: Sync {}
+
+unsafe impl ProcessShared for AtomicBool {}
+unsafe impl ProcessShared for AtomicU8 {}
+unsafe impl ProcessShared for AtomicU32 {}
+unsafe impl ProcessShared for AtomicU64 {}
+unsafe impl ProcessShared for AtomicUsize {}
+unsafe impl<T: ProcessShared, const N: usize> ProcessShared for [T; N] {}
+
+/// Anonymous `MAP_SHARED` mapping, inherited by every forked child
+struct Mapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl Mapping {
+    /// Map `len` zeroed bytes; panics if the kernel refuses, like a failed allocation
+    fn new(len: usize) -> Self {
+        let len = len.max(1);
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            panic!(
+                "failed to map {} bytes of shared memory: {}",
+                len,
+                std::io::Error::last_os_error()
+            );
+        }
+        Self {
+            ptr: NonNull::new(ptr as *mut u8).expect("mmap returned null"),
+            len,
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+/// A value stored in shared memory, visible to the supervisor and all workers
+pub struct Shared<T: ProcessShared> {
+    map: Mapping,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ProcessShared> Shared<T> {
+    pub fn new(value: T) -> Self {
+        let map = Mapping::new(std::mem::size_of::<T>());
+        // Page alignment satisfies any alignment T can ask for
+        unsafe { std::ptr::write(map.ptr.as_ptr() as *mut T, value) };
+        Self {
+            map,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ProcessShared + Default> Default for Shared<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ProcessShared> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(self.map.ptr.as_ptr() as *const T) }
+    }
+}
+
+impl<T: ProcessShared> Drop for Shared<T> {
+    fn drop(&mut self) {
+        unsafe { std::ptr::drop_in_place(self.map.ptr.as_ptr() as *mut T) };
+    }
+}
+
+/// A fixed-length slice stored in shared memory
+pub struct SharedSlice<T: ProcessShared> {
+    map: Mapping,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ProcessShared> SharedSlice<T> {
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> T) -> Self {
+        let map = Mapping::new(len * std::mem::size_of::<T>());
+        let base = map.ptr.as_ptr() as *mut T;
+        for i in 0..len {
+            unsafe { std::ptr::write(base.add(i), f(i)) };
+        }
+        Self {
+            map,
+            len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ProcessShared> Deref for SharedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.map.ptr.as_ptr() as *const T, self.len) }
+    }
+}
+
+impl<T: ProcessShared> Drop for SharedSlice<T> {
+    fn drop(&mut self) {
+        unsafe {
+            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                self.map.ptr.as_ptr() as *mut T,
+                self.len,
+            ))
+        };
+    }
+}
+
+/// Largest serialized record a `RecordLog` slot can hold
+const RECORD_BYTES: usize = 1024;
+
+/// One write-once record slot
+struct RecordSlot {
+    ready: AtomicBool,
+    len: AtomicUsize,
+    bytes: UnsafeCell<[u8; RECORD_BYTES]>,
+}
+
+// Each slot is written by exactly one claimant before `ready` is published
+unsafe impl Sync for RecordSlot {}
+unsafe impl ProcessShared for RecordSlot {}
+
+/// Bounded, append-only list of records that any worker process can push to
+/// Records are stored as JSON; pushes beyond capacity are only counted.
+pub struct RecordLog<T> {
+    slots: SharedSlice<RecordSlot>,
+    pushed: Shared<AtomicUsize>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> RecordLog<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: SharedSlice::from_fn(capacity, |_| RecordSlot {
+                ready: AtomicBool::new(false),
+                len: AtomicUsize::new(0),
+                bytes: UnsafeCell::new([0; RECORD_BYTES]),
+            }),
+            pushed: Shared::new(AtomicUsize::new(0)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Append a record; returns false if the log is full or the record too large
+    pub fn push(&self, record: &T) -> bool {
+        self.claim().is_some_and(|slot| Self::write(slot, record))
+    }
+
+    /// Like `push`, but only builds the record when a slot is free
+    /// For records that are costly to assemble and may arrive in bulk.
+    pub fn push_with(&self, build: impl FnOnce() -> T) -> bool {
+        self.claim().is_some_and(|slot| Self::write(slot, &build()))
+    }
+
+    /// Reserve the next slot, if any remain
+    fn claim(&self) -> Option<&RecordSlot> {
+        let index = self.pushed.fetch_add(1, Ordering::AcqRel);
+        self.slots.get(index)
+    }
+
+    fn write(slot: &RecordSlot, record: &T) -> bool {
+        let Ok(json) = serde_json::to_vec(record) else {
+            return false;
+        };
+        if json.len() > RECORD_BYTES {
+            return false;
+        }
+
+        // The fetch_add in claim() gave this caller exclusive ownership of the slot
+        unsafe { (&mut *slot.bytes.get())[..json.len()].copy_from_slice(&json) };
+        slot.len.store(json.len(), Ordering::Relaxed);
+        slot.ready.store(true, Ordering::Release);
+        true
+    }
+
+    /// Records published so far, in push order
+    pub fn records(&self) -> Vec<T> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.ready.load(Ordering::Acquire))
+            .filter_map(|slot| {
+                let len = slot.len.load(Ordering::Relaxed);
+                let bytes = unsafe { &(&*slot.bytes.get())[..len] };
+                serde_json::from_slice(bytes).ok()
+            })
+            .collect()
+    }
+
+    /// Number of push attempts, including those dropped for lack of space
+    pub fn total(&self) -> usize {
+        self.pushed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_visible_across_fork() {
+        let counter = Shared::new(AtomicU64::new(0));
+        let slice = SharedSlice::from_fn(4, |i| AtomicU64::new(i as u64));
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            counter.fetch_add(41, Ordering::Relaxed);
+            slice[3].store(99, Ordering::Relaxed);
+            unsafe { libc::_exit(0) };
+        }
+
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert_eq!(counter.load(Ordering::Relaxed), 41);
+        assert_eq!(slice[2].load(Ordering::Relaxed), 2);
+        assert_eq!(slice[3].load(Ordering::Relaxed), 99);
+    }
+
+    #[test]
+    fn test_record_log_capacity() {
+        let log: RecordLog<String> = RecordLog::new(2);
+        assert!(log.push(&"first".to_string()));
+        assert!(log.push(&"second".to_string()));
+        assert!(!log.push(&"third".to_string()));
+        assert_eq!(log.records(), vec!["first", "second"]);
+        assert_eq!(log.total(), 3);
+    }
+
+    #[test]
+    fn test_push_with_skips_build_when_full() {
+        let log: RecordLog<u32> = RecordLog::new(1);
+        assert!(log.push_with(|| 1));
+        assert!(!log.push_with(|| unreachable!("log is full")));
+        assert_eq!(log.records(), vec![1]);
+        assert_eq!(log.total(), 2);
+    }
+
+    #[test]
+    fn test_record_log_rejects_oversized() {
+        let log: RecordLog<String> = RecordLog::new(1);
+        assert!(!log.push(&"x".repeat(RECORD_BYTES)));
+        assert!(log.records().is_empty());
+    }
+}
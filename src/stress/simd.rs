@@ -0,0 +1,179 @@
+/// Lanes processed per inner-loop step (four 128-bit vectors of f32)
+pub const F32_BLOCK: usize = 16;
+
+/// Lanes processed per inner-loop step (four 128-bit vectors of f64)
+pub const F64_BLOCK: usize = 8;
+
+/// Evaluate the polynomial `coeffs` at every `x` with Horner's rule (scalar FMA)
+pub fn horner_f32_scalar(x: &[f32], coeffs: &[f32], out: &mut [f32]) {
+    for (out, &x) in out.iter_mut().zip(x) {
+        let mut acc = 0.0f32;
+        for &c in coeffs {
+            acc = acc.mul_add(x, c);
+        }
+        *out = acc;
+    }
+}
+
+/// Evaluate the polynomial `coeffs` at every `x` with Horner's rule (scalar FMA)
+pub fn horner_f64_scalar(x: &[f64], coeffs: &[f64], out: &mut [f64]) {
+    for (out, &x) in out.iter_mut().zip(x) {
+        let mut acc = 0.0f64;
+        for &c in coeffs {
+            acc = acc.mul_add(x, c);
+        }
+        *out = acc;
+    }
+}
+
+/// NEON version of `horner_f32_scalar`, four independent FMA chains per block
+/// `x.len()` must be a multiple of F32_BLOCK
+#[cfg(target_arch = "aarch64")]
+pub fn horner_f32_vector(x: &[f32], coeffs: &[f32], out: &mut [f32]) {
+    use core::arch::aarch64::*;
+
+    assert!(x.len().is_multiple_of(F32_BLOCK) && out.len() == x.len());
+
+    // Safety: NEON is mandatory on aarch64 and all loads/stores stay within
+    // the slices (length checked above)
+    unsafe {
+        for base in (0..x.len()).step_by(F32_BLOCK) {
+            let src = x.as_ptr().add(base);
+            let x0 = vld1q_f32(src);
+            let x1 = vld1q_f32(src.add(4));
+            let x2 = vld1q_f32(src.add(8));
+            let x3 = vld1q_f32(src.add(12));
+
+            let mut a0 = vdupq_n_f32(0.0);
+            let mut a1 = a0;
+            let mut a2 = a0;
+            let mut a3 = a0;
+
+            for &c in coeffs {
+                let cv = vdupq_n_f32(c);
+                a0 = vfmaq_f32(cv, a0, x0);
+                a1 = vfmaq_f32(cv, a1, x1);
+                a2 = vfmaq_f32(cv, a2, x2);
+                a3 = vfmaq_f32(cv, a3, x3);
+            }
+
+            let dst = out.as_mut_ptr().add(base);
+            vst1q_f32(dst, a0);
+            vst1q_f32(dst.add(4), a1);
+            vst1q_f32(dst.add(8), a2);
+            vst1q_f32(dst.add(12), a3);
+        }
+    }
+}
+
+/// NEON version of `horner_f64_scalar`, four independent FMA chains per block
+/// `x.len()` must be a multiple of F64_BLOCK
+#[cfg(target_arch = "aarch64")]
+pub fn horner_f64_vector(x: &[f64], coeffs: &[f64], out: &mut [f64]) {
+    use core::arch::aarch64::*;
+
+    assert!(x.len().is_multiple_of(F64_BLOCK) && out.len() == x.len());
+
+    // Safety: see horner_f32_vector
+    unsafe {
+        for base in (0..x.len()).step_by(F64_BLOCK) {
+            let src = x.as_ptr().add(base);
+            let x0 = vld1q_f64(src);
+            let x1 = vld1q_f64(src.add(2));
+            let x2 = vld1q_f64(src.add(4));
+            let x3 = vld1q_f64(src.add(6));
+
+            let mut a0 = vdupq_n_f64(0.0);
+            let mut a1 = a0;
+            let mut a2 = a0;
+            let mut a3 = a0;
+
+            for &c in coeffs {
+                let cv = vdupq_n_f64(c);
+                a0 = vfmaq_f64(cv, a0, x0);
+                a1 = vfmaq_f64(cv, a1, x1);
+                a2 = vfmaq_f64(cv, a2, x2);
+                a3 = vfmaq_f64(cv, a3, x3);
+            }
+
+            let dst = out.as_mut_ptr().add(base);
+            vst1q_f64(dst, a0);
+            vst1q_f64(dst.add(2), a1);
+            vst1q_f64(dst.add(4), a2);
+            vst1q_f64(dst.add(6), a3);
+        }
+    }
+}
+
+/// Portable fallback: lane arrays the compiler may map onto SSE/AVX
+#[cfg(not(target_arch = "aarch64"))]
+pub fn horner_f32_vector(x: &[f32], coeffs: &[f32], out: &mut [f32]) {
+    assert!(x.len().is_multiple_of(F32_BLOCK) && out.len() == x.len());
+
+    for (x, out) in x
+        .chunks_exact(F32_BLOCK)
+        .zip(out.chunks_exact_mut(F32_BLOCK))
+    {
+        let mut acc = [0.0f32; F32_BLOCK];
+        for &c in coeffs {
+            for (a, &x) in acc.iter_mut().zip(x) {
+                *a = a.mul_add(x, c);
+            }
+        }
+        out.copy_from_slice(&acc);
+    }
+}
+
+/// Portable fallback: lane arrays the compiler may map onto SSE/AVX
+#[cfg(not(target_arch = "aarch64"))]
+pub fn horner_f64_vector(x: &[f64], coeffs: &[f64], out: &mut [f64]) {
+    assert!(x.len().is_multiple_of(F64_BLOCK) && out.len() == x.len());
+
+    for (x, out) in x
+        .chunks_exact(F64_BLOCK)
+        .zip(out.chunks_exact_mut(F64_BLOCK))
+    {
+        let mut acc = [0.0f64; F64_BLOCK];
+        for &c in coeffs {
+            for (a, &x) in acc.iter_mut().zip(x) {
+                *a = a.mul_add(x, c);
+            }
+        }
+        out.copy_from_slice(&acc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_horner_f32_vector_matches_scalar() {
+        let x: Vec<f32> = (0..64).map(|i| i as f32 / 64.0 - 0.5).collect();
+        let coeffs: Vec<f32> = (0..32).map(|k| (k % 5) as f32 / 8.0 - 0.25).collect();
+        let mut vector = vec![0.0; x.len()];
+        let mut scalar = vec![0.0; x.len()];
+
+        horner_f32_vector(&x, &coeffs, &mut vector);
+        horner_f32_scalar(&x, &coeffs, &mut scalar);
+
+        for (v, s) in vector.iter().zip(&scalar) {
+            assert_eq!(v.to_bits(), s.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_horner_f64_vector_matches_scalar() {
+        let x: Vec<f64> = (0..64).map(|i| i as f64 / 64.0 - 0.5).collect();
+        let coeffs: Vec<f64> = (0..32).map(|k| (k % 5) as f64 / 8.0 - 0.25).collect();
+        let mut vector = vec![0.0; x.len()];
+        let mut scalar = vec![0.0; x.len()];
+
+        horner_f64_vector(&x, &coeffs, &mut vector);
+        horner_f64_scalar(&x, &coeffs, &mut scalar);
+
+        for (v, s) in vector.iter().zip(&scalar) {
+            assert_eq!(v.to_bits(), s.to_bits());
+        }
+    }
+}
@@ -0,0 +1,645 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::shared::Shared;
+
+/// Exit status used by a worker whose body panicked
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// A worker process that died instead of exiting cleanly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerCrash {
+    pub subsystem: String,
+    pub worker: usize,
+    /// Core the worker was pinned to, if any
+    pub core: Option<usize>,
+    /// Signal name (e.g. "SIGSEGV") if the worker was killed by a signal
+    pub signal: Option<String>,
+    /// Exit status if the worker exited on its own with a non-zero code
+    pub exit_code: Option<i32>,
+    /// Seconds into the run at which the crash was noticed
+    pub elapsed_secs: u64,
+}
+
+impl fmt::Display for WorkerCrash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} worker {}", self.subsystem, self.worker)?;
+        if let Some(core) = self.core {
+            write!(f, " (core {})", core)?;
+        }
+        match (&self.signal, self.exit_code) {
+            (Some(signal), _) => write!(f, " killed by {}", signal)?,
+            (None, Some(code)) => write!(f, " exited with status {}", code)?,
+            (None, None) => write!(f, " exited abnormally")?,
+        }
+        write!(f, " after {}s", self.elapsed_secs)
+    }
+}
+
+/// A worker that stopped reporting progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerHang {
+    pub subsystem: String,
+    pub worker: usize,
+    /// Core the worker was pinned to, if any
+    pub core: Option<usize>,
+    /// How long the worker had been silent when it was declared hung
+    pub silent_secs: u64,
+    /// Seconds into the run at which the hang was detected
+    pub elapsed_secs: u64,
+}
+
+impl fmt::Display for WorkerHang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} worker {}", self.subsystem, self.worker)?;
+        if let Some(core) = self.core {
+            write!(f, " (core {})", core)?;
+        }
+        write!(
+            f,
+            " hung: silent for {}s at {}s",
+            self.silent_secs, self.elapsed_secs
+        )
+    }
+}
+
+/// Progress marker a worker updates at least once per iteration
+/// Kept in shared memory so beats from forked workers reach the supervisor.
+pub struct Heartbeat {
+    origin: Instant,
+    last_ms: Shared<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            last_ms: Shared::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn beat(&self) {
+        let now = self.origin.elapsed().as_millis() as u64;
+        self.last_ms.store(now, Ordering::Relaxed);
+    }
+
+    /// Time since the last beat
+    pub fn silent_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_ms.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last)
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Name of a signal number, e.g. "SIGSEGV"
+pub fn signal_name(signal: i32) -> String {
+    match signal {
+        libc::SIGSEGV => "SIGSEGV".to_string(),
+        libc::SIGILL => "SIGILL".to_string(),
+        libc::SIGBUS => "SIGBUS".to_string(),
+        libc::SIGFPE => "SIGFPE".to_string(),
+        libc::SIGABRT => "SIGABRT".to_string(),
+        libc::SIGTRAP => "SIGTRAP".to_string(),
+        libc::SIGSYS => "SIGSYS".to_string(),
+        libc::SIGKILL => "SIGKILL".to_string(),
+        libc::SIGTERM => "SIGTERM".to_string(),
+        libc::SIGINT => "SIGINT".to_string(),
+        other => format!("signal {}", other),
+    }
+}
+
+type Body = Arc<dyn Fn(&Heartbeat) + Send + Sync>;
+
+struct Worker {
+    subsystem: &'static str,
+    index: usize,
+    core: Option<usize>,
+    heartbeat: Arc<Heartbeat>,
+    /// Declared hung and not yet replaced
+    hung: bool,
+    /// Code a forked worker runs (None for watched in-process workers)
+    body: Option<Body>,
+    /// Process id while a forked worker is alive
+    pid: Option<libc::pid_t>,
+}
+
+/// Single-threaded process that forks the workers
+/// A fork copies locks other threads hold (malloc, stdio, the async
+/// runtime) into a child where nothing will ever release them. The zygote
+/// is forked before any other thread starts and never starts one, so every
+/// worker forked from it starts with all locks free.
+struct Zygote {
+    pid: libc::pid_t,
+    /// Slot of each worker to fork
+    requests: File,
+    /// Result of each fork, in request order
+    replies: File,
+}
+
+impl Zygote {
+    /// Fork the zygote, which can fork any of `workers`
+    fn start(workers: &[Worker]) -> io::Result<Self> {
+        let (requests_rx, requests_tx) = pipe()?;
+        let (replies_rx, replies_tx) = pipe()?;
+        let supervisor = unsafe { libc::getpid() };
+
+        match unsafe { libc::fork() } {
+            -1 => Err(io::Error::last_os_error()),
+            0 => {
+                drop((requests_tx, replies_rx));
+                unsafe { die_with(supervisor) };
+                serve(workers, requests_rx, replies_tx, supervisor);
+                unsafe { libc::_exit(0) }
+            }
+            pid => Ok(Self {
+                pid,
+                requests: requests_tx,
+                replies: replies_rx,
+            }),
+        }
+    }
+
+    /// Have the zygote fork the worker in `slot`
+    fn fork(&mut self, slot: usize) -> io::Result<libc::pid_t> {
+        self.requests.write_all(&(slot as u64).to_le_bytes())?;
+        read_pid(&mut self.replies)
+    }
+}
+
+impl Drop for Zygote {
+    fn drop(&mut self) {
+        kill_process(self.pid);
+    }
+}
+
+/// Runs stress workers in forked child processes
+/// A worker that faults (SIGSEGV, SIGILL, SIGBUS, ...) on an unstable core
+/// takes down only its own process; the supervisor notices the exit and can
+/// fork a replacement, so the run and its report survive. Every worker
+/// also carries a heartbeat; one that stays silent too long is declared hung.
+pub struct Supervisor {
+    start: Instant,
+    workers: Vec<Worker>,
+    zygote: Option<Zygote>,
+}
+
+impl Supervisor {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            workers: Vec::new(),
+            zygote: None,
+        }
+    }
+
+    /// Add a worker process running `body`, forked by `respawn` after `start`
+    /// The body must beat the heartbeat it is given at least once per
+    /// iteration, and return once the workers' shared stop flag is cleared.
+    pub fn add(
+        &mut self,
+        subsystem: &'static str,
+        index: usize,
+        core: Option<usize>,
+        body: impl Fn(&Heartbeat) + Send + Sync + 'static,
+    ) {
+        self.workers.push(Worker {
+            subsystem,
+            index,
+            core,
+            heartbeat: Arc::new(Heartbeat::new()),
+            hung: false,
+            body: Some(Arc::new(body)),
+            pid: None,
+        });
+    }
+
+    /// Fork the zygote that forks every worker added so far
+    /// Call while this process still has a single thread, before the async
+    /// runtime starts: the zygote is a copy of it, locks and all. Workers
+    /// are made children of this process, so it can reap them.
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.workers.iter().all(|w| w.body.is_none()) {
+            return Ok(());
+        }
+        if unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.zygote = Some(Zygote::start(&self.workers)?);
+        Ok(())
+    }
+
+    /// Watch the heartbeat of a worker running inside this process
+    /// Such workers cannot be killed or replaced, only reported as hung.
+    pub fn watch(&mut self, subsystem: &'static str, index: usize) -> Arc<Heartbeat> {
+        let heartbeat = Arc::new(Heartbeat::new());
+        heartbeat.beat();
+        self.workers.push(Worker {
+            subsystem,
+            index,
+            core: None,
+            heartbeat: heartbeat.clone(),
+            hung: false,
+            body: None,
+            pid: None,
+        });
+        heartbeat
+    }
+
+    /// Declare workers silent for longer than `timeout` as hung
+    /// Hung worker processes are killed and reaped here, so `respawn` can
+    /// replace them in the same tick.
+    pub fn check_hangs(&mut self, timeout: Duration) -> Vec<WorkerHang> {
+        let elapsed_secs = self.start.elapsed().as_secs();
+        let mut hangs = Vec::new();
+
+        for worker in &mut self.workers {
+            let alive = worker.pid.is_some() || worker.body.is_none();
+            let silent = worker.heartbeat.silent_for();
+            if worker.hung || !alive || silent <= timeout {
+                continue;
+            }
+
+            worker.hung = true;
+            if let Some(pid) = worker.pid.take() {
+                kill_process(pid);
+            }
+            hangs.push(WorkerHang {
+                subsystem: worker.subsystem.to_string(),
+                worker: worker.index,
+                core: worker.core,
+                silent_secs: silent.as_secs(),
+                elapsed_secs,
+            });
+        }
+
+        hangs
+    }
+
+    /// Whether any worker of `subsystem` is currently declared hung
+    pub fn is_hung(&self, subsystem: &str) -> bool {
+        self.workers
+            .iter()
+            .any(|w| w.subsystem == subsystem && w.hung)
+    }
+
+    /// Collect workers that exited abnormally since the last call
+    pub fn reap(&mut self) -> Vec<WorkerCrash> {
+        let elapsed_secs = self.start.elapsed().as_secs();
+        let mut crashes = Vec::new();
+
+        for worker in &mut self.workers {
+            let Some(pid) = worker.pid else { continue };
+            let mut status = 0;
+            if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } != pid {
+                continue;
+            }
+            worker.pid = None;
+            if let Some(crash) = crash_from_status(worker, status, elapsed_secs) {
+                crashes.push(crash);
+            }
+        }
+
+        crashes
+    }
+
+    /// Fork every worker of `subsystem` that is not running: all of them
+    /// right after `start`, later the replacements
+    pub fn respawn(&mut self, subsystem: &str) -> io::Result<()> {
+        for (slot, worker) in self.workers.iter_mut().enumerate() {
+            if worker.subsystem != subsystem || worker.pid.is_some() || worker.body.is_none() {
+                continue;
+            }
+            let zygote = self
+                .zygote
+                .as_mut()
+                .ok_or_else(|| io::Error::other("worker zygote is not running"))?;
+            worker.heartbeat.beat();
+            worker.pid = Some(zygote.fork(slot)?);
+            worker.hung = false;
+        }
+        Ok(())
+    }
+
+    /// Kill every running worker of `subsystem` and wait for them to exit
+    pub fn kill(&mut self, subsystem: &str) {
+        for worker in self.workers.iter_mut().filter(|w| w.subsystem == subsystem) {
+            if let Some(pid) = worker.pid.take() {
+                kill_process(pid);
+            }
+            worker.hung = false;
+        }
+    }
+
+    /// Wait for all workers to exit after the stop flag was cleared
+    /// Workers still running after `timeout` are killed without being
+    /// counted as crashes. Returns crashes that happened while stopping.
+    pub fn shutdown(&mut self, timeout: Duration) -> Vec<WorkerCrash> {
+        let deadline = Instant::now() + timeout;
+        let mut crashes = Vec::new();
+
+        while self.workers.iter().any(|w| w.pid.is_some()) && Instant::now() < deadline {
+            crashes.extend(self.reap());
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        crashes.extend(self.reap());
+
+        for worker in &mut self.workers {
+            if let Some(pid) = worker.pid.take() {
+                kill_process(pid);
+            }
+        }
+
+        crashes
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        for worker in &mut self.workers {
+            if let Some(pid) = worker.pid.take() {
+                kill_process(pid);
+            }
+        }
+    }
+}
+
+/// SIGKILL a worker and reap it, giving up after a second
+/// A process stuck in uninterruptible I/O cannot be reaped until the I/O
+/// completes, and must not hang the supervisor with it.
+fn kill_process(pid: libc::pid_t) {
+    unsafe { libc::kill(pid, libc::SIGKILL) };
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while Instant::now() < deadline {
+        if unsafe { libc::waitpid(pid, std::ptr::null_mut(), libc::WNOHANG) } != 0 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Translate a waitpid status into a crash record (None for a clean exit)
+fn crash_from_status(worker: &Worker, status: i32, elapsed_secs: u64) -> Option<WorkerCrash> {
+    let (signal, exit_code) = if libc::WIFSIGNALED(status) {
+        (Some(signal_name(libc::WTERMSIG(status))), None)
+    } else if libc::WIFEXITED(status) && libc::WEXITSTATUS(status) != 0 {
+        (None, Some(libc::WEXITSTATUS(status)))
+    } else {
+        return None;
+    };
+
+    Some(WorkerCrash {
+        subsystem: worker.subsystem.to_string(),
+        worker: worker.index,
+        core: worker.core,
+        signal,
+        exit_code,
+        elapsed_secs,
+    })
+}
+
+/// The zygote's loop: fork each requested worker until the supervisor hangs up
+fn serve(workers: &[Worker], mut requests: File, mut replies: File, supervisor: libc::pid_t) {
+    let inherited = [requests.as_raw_fd(), replies.as_raw_fd()];
+    let mut request = [0; 8];
+    while requests.read_exact(&mut request).is_ok() {
+        let worker = workers.get(u64::from_le_bytes(request) as usize);
+        let pid = match worker.and_then(|w| w.body.as_ref().map(|body| (body, &w.heartbeat))) {
+            Some((body, heartbeat)) => fork_worker(body, heartbeat, supervisor, &inherited),
+            None => Err(io::Error::from_raw_os_error(libc::EINVAL)),
+        };
+        if write_pid(&mut replies, pid).is_err() {
+            break;
+        }
+    }
+}
+
+/// Fork a worker running `body` as a child of `supervisor`
+/// An intermediate process forks the worker and exits at once, which hands
+/// the orphan to the supervisor, a child subreaper.
+fn fork_worker(
+    body: &Body,
+    heartbeat: &Heartbeat,
+    supervisor: libc::pid_t,
+    inherited: &[RawFd],
+) -> io::Result<libc::pid_t> {
+    let (mut pid_rx, mut pid_tx) = pipe()?;
+
+    let intermediate = match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => {
+            let parent = unsafe { libc::getpid() };
+            let pid = match unsafe { libc::fork() } {
+                -1 => Err(io::Error::last_os_error()),
+                0 => {
+                    for &fd in inherited
+                        .iter()
+                        .chain(&[pid_rx.as_raw_fd(), pid_tx.as_raw_fd()])
+                    {
+                        unsafe { libc::close(fd) };
+                    }
+                    run_worker(body, heartbeat, supervisor, parent)
+                }
+                pid => Ok(pid),
+            };
+            let _ = write_pid(&mut pid_tx, pid);
+            unsafe { libc::_exit(0) }
+        }
+        pid => pid,
+    };
+
+    drop(pid_tx);
+    // Once the intermediate is reaped, the worker belongs to the supervisor
+    unsafe { libc::waitpid(intermediate, std::ptr::null_mut(), 0) };
+    read_pid(&mut pid_rx)
+}
+
+/// Run `body` in a freshly forked worker and exit with its outcome
+fn run_worker(
+    body: &Body,
+    heartbeat: &Heartbeat,
+    supervisor: libc::pid_t,
+    intermediate: libc::pid_t,
+) -> ! {
+    unsafe {
+        while libc::getppid() == intermediate {
+            libc::sched_yield();
+        }
+        die_with(supervisor);
+    }
+
+    let code = match panic::catch_unwind(AssertUnwindSafe(|| body(heartbeat))) {
+        Ok(()) => 0,
+        Err(_) => PANIC_EXIT_CODE,
+    };
+    unsafe { libc::_exit(code) }
+}
+
+/// Die with the supervisor, or now if it is already gone, and leave Ctrl+C
+/// handling to it
+unsafe fn die_with(supervisor: libc::pid_t) {
+    libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+    if libc::getppid() != supervisor {
+        libc::_exit(0);
+    }
+    libc::signal(libc::SIGINT, libc::SIG_IGN);
+}
+
+/// A close-on-exec pipe, as (read end, write end)
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+/// Send the result of a fork: the pid, or the negated errno
+fn write_pid(pipe: &mut File, pid: io::Result<libc::pid_t>) -> io::Result<()> {
+    let reply = match pid {
+        Ok(pid) => pid as i64,
+        Err(e) => -(e.raw_os_error().unwrap_or(libc::EIO) as i64),
+    };
+    pipe.write_all(&reply.to_le_bytes())
+}
+
+fn read_pid(pipe: &mut File) -> io::Result<libc::pid_t> {
+    let mut reply = [0; 8];
+    pipe.read_exact(&mut reply)?;
+    match i64::from_le_bytes(reply) {
+        pid if pid > 0 => Ok(pid as libc::pid_t),
+        errno => Err(io::Error::from_raw_os_error(-errno as i32)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_crash(supervisor: &mut Supervisor) -> WorkerCrash {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if let Some(crash) = supervisor.reap().pop() {
+                return crash;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("worker did not exit");
+    }
+
+    /// A supervisor running a single worker
+    fn start_one(
+        subsystem: &'static str,
+        index: usize,
+        core: Option<usize>,
+        body: impl Fn(&Heartbeat) + Send + Sync + 'static,
+    ) -> Supervisor {
+        let mut supervisor = Supervisor::new(Instant::now());
+        supervisor.add(subsystem, index, core, body);
+        supervisor.start().unwrap();
+        supervisor.respawn(subsystem).unwrap();
+        supervisor
+    }
+
+    #[test]
+    fn test_signal_name() {
+        assert_eq!(signal_name(libc::SIGSEGV), "SIGSEGV");
+        assert_eq!(signal_name(libc::SIGBUS), "SIGBUS");
+        assert_eq!(signal_name(libc::SIGILL), "SIGILL");
+        assert_eq!(signal_name(64), "signal 64");
+    }
+
+    #[test]
+    fn test_crash_is_reported_and_respawned() {
+        let mut supervisor = start_one("cpu", 3, Some(1), |_| unsafe {
+            // A real fault, like a corrupted pointer on an unstable core
+            std::ptr::null_mut::<u64>().write_volatile(1);
+        });
+
+        let crash = wait_for_crash(&mut supervisor);
+        assert_eq!(crash.subsystem, "cpu");
+        assert_eq!(crash.worker, 3);
+        assert_eq!(crash.signal.as_deref(), Some("SIGSEGV"));
+        assert!(crash
+            .to_string()
+            .starts_with("cpu worker 3 (core 1) killed by SIGSEGV"));
+
+        supervisor.respawn("cpu").unwrap();
+        let crash = wait_for_crash(&mut supervisor);
+        assert_eq!(crash.signal.as_deref(), Some("SIGSEGV"));
+    }
+
+    #[test]
+    fn test_clean_exit_is_not_a_crash() {
+        let mut supervisor = start_one("memory", 0, None, |_| {});
+        assert!(supervisor.shutdown(Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_workers_are_children_of_the_supervisor() {
+        let parent = unsafe { libc::getpid() };
+        let mut supervisor = start_one("cpu", 0, None, move |_| {
+            let code = if unsafe { libc::getppid() } == parent {
+                7
+            } else {
+                8
+            };
+            unsafe { libc::_exit(code) }
+        });
+        assert_eq!(wait_for_crash(&mut supervisor).exit_code, Some(7));
+    }
+
+    #[test]
+    fn test_silent_worker_is_declared_hung() {
+        let mut supervisor = start_one("cpu", 0, Some(0), |heartbeat| {
+            heartbeat.beat();
+            std::thread::sleep(Duration::from_secs(30));
+        });
+        let watched = supervisor.watch("nvme", 0);
+
+        std::thread::sleep(Duration::from_millis(300));
+        watched.beat();
+        let hangs = supervisor.check_hangs(Duration::from_millis(200));
+        assert_eq!(hangs.len(), 1);
+        assert_eq!(hangs[0].subsystem, "cpu");
+        assert!(supervisor.is_hung("cpu"));
+        assert!(!supervisor.is_hung("nvme"));
+
+        // Reported once, and the kill that follows is not a crash
+        assert!(supervisor
+            .check_hangs(Duration::from_millis(200))
+            .is_empty());
+        assert!(supervisor.shutdown(Duration::from_secs(5)).is_empty());
+    }
+
+    #[test]
+    fn test_hung_worker_is_replaced() {
+        let mut supervisor = start_one("memory", 0, None, |heartbeat| {
+            heartbeat.beat();
+            std::thread::sleep(Duration::from_secs(30));
+        });
+        let first = supervisor.workers[0].pid;
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(supervisor.check_hangs(Duration::from_millis(200)).len(), 1);
+        assert!(supervisor.reap().is_empty());
+        supervisor.respawn("memory").unwrap();
+
+        let second = supervisor.workers[0].pid;
+        assert!(second.is_some());
+        assert_ne!(second, first);
+        assert!(!supervisor.is_hung("memory"));
+        supervisor.kill("memory");
+    }
+}
@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::shared::{ProcessShared, SharedSlice};
+use super::workload::Workload;
+
+/// Wall-clock span used to establish each workload's baseline rate
+pub const BASELINE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Busy time a workload must accumulate before its rate is re-evaluated
+/// (long enough to smooth over slow workloads that only run every few seconds)
+const SAMPLE_BUSY_TIME: Duration = Duration::from_secs(10);
+
+/// Iteration count and busy time of one workload
+#[derive(Debug, Default)]
+struct Counter {
+    iterations: AtomicU64,
+    busy_nanos: AtomicU64,
+}
+
+unsafe impl ProcessShared for Counter {}
+
+/// Iterations and busy time per workload for one worker
+/// Backed by shared memory so forked worker processes can record into it.
+pub struct WorkerThroughput {
+    counters: SharedSlice<Counter>,
+}
+
+impl WorkerThroughput {
+    fn new(workloads: usize) -> Self {
+        Self {
+            counters: SharedSlice::from_fn(workloads, |_| Counter::default()),
+        }
+    }
+
+    /// Record one completed iteration of `workload` that took `elapsed`
+    pub fn record<W: Workload>(&self, workload: W, elapsed: Duration) {
+        if let Some(index) = W::VARIANTS.iter().position(|&w| w == workload) {
+            let counter = &self.counters[index];
+            counter.iterations.fetch_add(1, Ordering::Relaxed);
+            counter
+                .busy_nanos
+                .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Throughput counters for every worker of one subsystem
+pub struct SubsystemThroughput {
+    subsystem: &'static str,
+    workloads: Vec<&'static str>,
+    workers: Vec<Arc<WorkerThroughput>>,
+}
+
+impl SubsystemThroughput {
+    pub fn new<W: Workload>(subsystem: &'static str, workers: usize) -> Self {
+        Self {
+            subsystem,
+            workloads: W::VARIANTS.iter().map(|w| w.name()).collect(),
+            workers: (0..workers)
+                .map(|_| Arc::new(WorkerThroughput::new(W::VARIANTS.len())))
+                .collect(),
+        }
+    }
+
+    /// Counters for worker `index`
+    pub fn worker(&self, index: usize) -> Arc<WorkerThroughput> {
+        self.workers[index].clone()
+    }
+
+    /// Iterations and busy seconds of workload `index`, summed over all workers
+    fn totals(&self, index: usize) -> (u64, f64) {
+        self.workers.iter().fold((0, 0.0), |(iterations, busy), w| {
+            let counter = &w.counters[index];
+            (
+                iterations + counter.iterations.load(Ordering::Relaxed),
+                busy + counter.busy_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            )
+        })
+    }
+}
+
+/// Current rate of one workload, streamed through `StressStats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadRate {
+    pub subsystem: String,
+    pub workload: String,
+    /// Iterations per busy second per worker
+    pub per_sec: f64,
+}
+
+/// Throughput history of one workload, recorded in the final report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadThroughput {
+    pub subsystem: String,
+    pub workload: String,
+    pub iterations: u64,
+    /// Rate over the first minute of the run
+    pub baseline_per_sec: Option<f64>,
+    pub last_per_sec: Option<f64>,
+    pub min_per_sec: Option<f64>,
+    /// Largest drop below baseline, as a fraction of baseline
+    pub max_drop: f64,
+    /// Samples that fell below the allowed drop while no throttle bit was set
+    pub unexplained_drops: u32,
+}
+
+/// Rate bookkeeping for one workload of one subsystem
+struct Tracked {
+    counters: Arc<SubsystemThroughput>,
+    index: usize,
+    baseline: Option<f64>,
+    /// Totals at the start of the current sample
+    sample_start: (u64, f64),
+    /// Whether any throttle bit was set during the current sample
+    sample_throttled: bool,
+    last: Option<f64>,
+    min: Option<f64>,
+    max_drop: f64,
+    unexplained_drops: u32,
+}
+
+/// Compares each workload's rate against its first-minute baseline
+/// A drop larger than `max_drop` is only excused when the firmware
+/// reported throttling at some point during the sample.
+pub struct ThroughputTracker {
+    max_drop: f64,
+    tracked: Vec<Tracked>,
+}
+
+impl ThroughputTracker {
+    pub fn new(max_drop: f64) -> Self {
+        Self {
+            max_drop,
+            tracked: Vec::new(),
+        }
+    }
+
+    /// Start tracking every workload of a subsystem
+    pub fn add(&mut self, counters: Arc<SubsystemThroughput>) {
+        for index in 0..counters.workloads.len() {
+            self.tracked.push(Tracked {
+                counters: counters.clone(),
+                index,
+                baseline: None,
+                sample_start: (0, 0.0),
+                sample_throttled: false,
+                last: None,
+                min: None,
+                max_drop: 0.0,
+                unexplained_drops: 0,
+            });
+        }
+    }
+
+    /// Update rates; call once per monitoring tick
+    /// `throttled` is whether any throttle bit is currently set.
+    pub fn sample(&mut self, elapsed: Duration, throttled: bool) {
+        for t in &mut self.tracked {
+            let (iterations, busy) = t.counters.totals(t.index);
+            t.sample_throttled |= throttled;
+
+            if t.baseline.is_none() {
+                // Extend the baseline past the first minute until the workload has run
+                if elapsed >= BASELINE_WINDOW && iterations > 0 && busy > 0.0 {
+                    let rate = iterations as f64 / busy;
+                    t.baseline = Some(rate);
+                    t.last = Some(rate);
+                    t.min = Some(rate);
+                    t.sample_start = (iterations, busy);
+                    t.sample_throttled = false;
+                }
+                continue;
+            }
+
+            let delta_iterations = iterations - t.sample_start.0;
+            let delta_busy = busy - t.sample_start.1;
+            if delta_iterations == 0 || delta_busy < SAMPLE_BUSY_TIME.as_secs_f64() {
+                continue;
+            }
+
+            let rate = delta_iterations as f64 / delta_busy;
+            let baseline = t.baseline.unwrap_or(rate);
+            let drop = (1.0 - rate / baseline).max(0.0);
+
+            t.last = Some(rate);
+            t.min = Some(t.min.map_or(rate, |m| m.min(rate)));
+            t.max_drop = t.max_drop.max(drop);
+            if drop > self.max_drop && !t.sample_throttled {
+                t.unexplained_drops += 1;
+            }
+
+            t.sample_start = (iterations, busy);
+            t.sample_throttled = false;
+        }
+    }
+
+    /// Latest rates of workloads that have run, for the live display
+    pub fn rates(&self) -> Vec<WorkloadRate> {
+        self.tracked
+            .iter()
+            .filter_map(|t| {
+                let per_sec = t.last.or_else(|| {
+                    let (iterations, busy) = t.counters.totals(t.index);
+                    (iterations > 0 && busy > 0.0).then(|| iterations as f64 / busy)
+                })?;
+                Some(WorkloadRate {
+                    subsystem: t.counters.subsystem.to_string(),
+                    workload: t.counters.workloads[t.index].to_string(),
+                    per_sec,
+                })
+            })
+            .collect()
+    }
+
+    /// Per-workload history of workloads that ran at least once
+    pub fn summary(&self) -> Vec<WorkloadThroughput> {
+        self.tracked
+            .iter()
+            .filter_map(|t| {
+                let (iterations, _) = t.counters.totals(t.index);
+                (iterations > 0).then(|| WorkloadThroughput {
+                    subsystem: t.counters.subsystem.to_string(),
+                    workload: t.counters.workloads[t.index].to_string(),
+                    iterations,
+                    baseline_per_sec: t.baseline,
+                    last_per_sec: t.last,
+                    min_per_sec: t.min,
+                    max_drop: t.max_drop,
+                    unexplained_drops: t.unexplained_drops,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::cpu::CpuWorkload;
+
+    fn run(worker: &WorkerThroughput, workload: CpuWorkload, count: usize, each: Duration) {
+        for _ in 0..count {
+            worker.record(workload, each);
+        }
+    }
+
+    #[test]
+    fn test_unexplained_drop_is_flagged() {
+        let counters = Arc::new(SubsystemThroughput::new::<CpuWorkload>("cpu", 1));
+        let worker = counters.worker(0);
+        let mut tracker = ThroughputTracker::new(0.1);
+        tracker.add(counters);
+
+        // Baseline: 10 iterations per second
+        run(&worker, CpuWorkload::Dft, 600, Duration::from_millis(100));
+        tracker.sample(BASELINE_WINDOW, false);
+
+        // 25% slower without throttling
+        run(&worker, CpuWorkload::Dft, 80, Duration::from_millis(133));
+        tracker.sample(BASELINE_WINDOW * 2, false);
+
+        let summary = tracker.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].workload, "dft");
+        assert!((summary[0].baseline_per_sec.unwrap() - 10.0).abs() < 1e-9);
+        assert!(summary[0].max_drop > 0.2);
+        assert_eq!(summary[0].unexplained_drops, 1);
+    }
+
+    #[test]
+    fn test_throttled_drop_is_excused() {
+        let counters = Arc::new(SubsystemThroughput::new::<CpuWorkload>("cpu", 2));
+        let mut tracker = ThroughputTracker::new(0.1);
+        tracker.add(counters.clone());
+
+        for worker in 0..2 {
+            run(
+                &counters.worker(worker),
+                CpuWorkload::Aes,
+                600,
+                Duration::from_millis(100),
+            );
+        }
+        tracker.sample(BASELINE_WINDOW, false);
+
+        tracker.sample(BASELINE_WINDOW + Duration::from_secs(1), true);
+        run(
+            &counters.worker(0),
+            CpuWorkload::Aes,
+            50,
+            Duration::from_millis(200),
+        );
+        tracker.sample(BASELINE_WINDOW * 2, false);
+
+        let summary = tracker.summary();
+        assert!(summary[0].max_drop > 0.4);
+        assert_eq!(summary[0].unexplained_drops, 0);
+    }
+
+    #[test]
+    fn test_no_baseline_before_first_minute() {
+        let counters = Arc::new(SubsystemThroughput::new::<CpuWorkload>("cpu", 1));
+        let mut tracker = ThroughputTracker::new(0.1);
+        tracker.add(counters.clone());
+
+        run(
+            &counters.worker(0),
+            CpuWorkload::Prime,
+            10,
+            Duration::from_millis(10),
+        );
+        tracker.sample(Duration::from_secs(5), false);
+
+        let rates = tracker.rates();
+        assert_eq!(rates.len(), 1);
+        assert!((rates[0].per_sec - 100.0).abs() < 1e-6);
+        assert_eq!(tracker.summary()[0].baseline_per_sec, None);
+    }
+}
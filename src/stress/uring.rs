@@ -0,0 +1,328 @@
+use std::io;
+use std::mem::size_of;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// mmap offsets of the submission ring, completion ring and SQE array
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x0800_0000;
+const IORING_OFF_SQES: libc::off_t = 0x1000_0000;
+
+/// Both rings share one mapping
+const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+
+/// Added in Linux 5.6 together with IORING_OP_READ and IORING_OP_WRITE
+const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+
+const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+
+const IORING_OP_READ: u8 = 22;
+const IORING_OP_WRITE: u8 = 23;
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct SqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct CqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+/// struct io_uring_params
+#[repr(C)]
+#[derive(Debug, Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqRingOffsets,
+    cq_off: CqRingOffsets,
+}
+
+/// struct io_uring_sqe, as used for plain reads and writes
+#[repr(C)]
+#[derive(Debug, Default)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+/// struct io_uring_cqe
+#[repr(C)]
+#[derive(Debug)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+/// One of the ring mappings shared with the kernel
+struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mapping {
+    fn new(fd: &OwnedFd, len: usize, offset: libc::off_t) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd.as_raw_fd(),
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    /// Pointer to the field at byte `offset` into the mapping
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { self.ptr.add(offset as usize) as *mut T }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+/// A minimal io_uring instance for reads and writes, driven by one thread
+/// Talks to the kernel through the raw syscalls and ring mappings, so no
+/// liburing is needed. The caller keeps every buffer alive and unmoved until
+/// its completion has been reaped.
+pub struct Ring {
+    // Ring mappings, only held to keep the pointers below valid; declared
+    // before the fd so they are unmapped first
+    _sq: Mapping,
+    _cq: Option<Mapping>,
+    sqes: Mapping,
+    fd: OwnedFd,
+    sq_head: *const AtomicU32,
+    sq_tail: *const AtomicU32,
+    sq_mask: u32,
+    sq_entries: u32,
+    sq_array: *mut u32,
+    cq_head: *const AtomicU32,
+    cq_tail: *const AtomicU32,
+    cq_mask: u32,
+    cqes: *const Cqe,
+    /// Queued with `push` but not yet handed to the kernel
+    unsubmitted: u32,
+}
+
+impl Ring {
+    /// Set up a ring with room for `entries` requests in flight
+    /// Fails on kernels without io_uring (or before 5.6), and where it is
+    /// disabled by sysctl or a seccomp filter.
+    pub fn new(entries: u32) -> io::Result<Self> {
+        let mut params = Params::default();
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_setup,
+                entries,
+                &mut params as *mut Params,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+        if params.features & IORING_FEAT_RW_CUR_POS == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "io_uring without IORING_OP_READ/WRITE (kernel older than 5.6)",
+            ));
+        }
+
+        let sq_len = params.sq_off.array as usize + params.sq_entries as usize * size_of::<u32>();
+        let cq_len = params.cq_off.cqes as usize + params.cq_entries as usize * size_of::<Cqe>();
+        let single = params.features & IORING_FEAT_SINGLE_MMAP != 0;
+        let sq = Mapping::new(
+            &fd,
+            if single { sq_len.max(cq_len) } else { sq_len },
+            IORING_OFF_SQ_RING,
+        )?;
+        let cq = if single {
+            None
+        } else {
+            Some(Mapping::new(&fd, cq_len, IORING_OFF_CQ_RING)?)
+        };
+        let sqes = Mapping::new(
+            &fd,
+            params.sq_entries as usize * size_of::<Sqe>(),
+            IORING_OFF_SQES,
+        )?;
+
+        let cq_ring = cq.as_ref().unwrap_or(&sq);
+        let (sq_off, cq_off) = (&params.sq_off, &params.cq_off);
+        Ok(Self {
+            sq_head: sq.at(sq_off.head),
+            sq_tail: sq.at(sq_off.tail),
+            sq_mask: unsafe { *sq.at::<u32>(sq_off.ring_mask) },
+            sq_entries: params.sq_entries,
+            sq_array: sq.at(sq_off.array),
+            cq_head: cq_ring.at(cq_off.head),
+            cq_tail: cq_ring.at(cq_off.tail),
+            cq_mask: unsafe { *cq_ring.at::<u32>(cq_off.ring_mask) },
+            cqes: cq_ring.at(cq_off.cqes),
+            unsubmitted: 0,
+            _sq: sq,
+            _cq: cq,
+            sqes,
+            fd,
+        })
+    }
+
+    /// Queue a read into `buf`; returns false if the submission ring is full
+    pub fn push_read(&mut self, fd: i32, buf: &mut [u8], offset: u64, user_data: u64) -> bool {
+        self.push(
+            IORING_OP_READ,
+            fd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            offset,
+            user_data,
+        )
+    }
+
+    /// Queue a write of `buf`; returns false if the submission ring is full
+    pub fn push_write(&mut self, fd: i32, buf: &[u8], offset: u64, user_data: u64) -> bool {
+        self.push(
+            IORING_OP_WRITE,
+            fd,
+            buf.as_ptr() as *mut u8,
+            buf.len(),
+            offset,
+            user_data,
+        )
+    }
+
+    fn push(
+        &mut self,
+        opcode: u8,
+        fd: i32,
+        addr: *mut u8,
+        len: usize,
+        offset: u64,
+        user_data: u64,
+    ) -> bool {
+        let head = unsafe { (*self.sq_head).load(Ordering::Acquire) };
+        let tail = unsafe { (*self.sq_tail).load(Ordering::Relaxed) };
+        if tail.wrapping_sub(head) >= self.sq_entries {
+            return false;
+        }
+        let index = tail & self.sq_mask;
+        let sqe = Sqe {
+            opcode,
+            fd,
+            off: offset,
+            addr: addr as u64,
+            len: len as u32,
+            user_data,
+            ..Sqe::default()
+        };
+        unsafe {
+            (self.sqes.ptr as *mut Sqe).add(index as usize).write(sqe);
+            self.sq_array.add(index as usize).write(index);
+            (*self.sq_tail).store(tail.wrapping_add(1), Ordering::Release);
+        }
+        self.unsubmitted += 1;
+        true
+    }
+
+    /// Submit everything queued and block until at least `min_complete`
+    /// requests have completed
+    pub fn submit_and_wait(&mut self, min_complete: u32) -> io::Result<()> {
+        loop {
+            let submitted = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd.as_raw_fd(),
+                    self.unsubmitted,
+                    min_complete,
+                    IORING_ENTER_GETEVENTS,
+                    std::ptr::null::<libc::sigset_t>(),
+                    0usize,
+                )
+            };
+            if submitted >= 0 {
+                self.unsubmitted -= submitted as u32;
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Hand every available completion to `f` as (user_data, result)
+    /// The result is the byte count, or a negated errno.
+    pub fn complete(&mut self, mut f: impl FnMut(u64, i32)) {
+        let mut head = unsafe { (*self.cq_head).load(Ordering::Relaxed) };
+        let tail = unsafe { (*self.cq_tail).load(Ordering::Acquire) };
+        while head != tail {
+            let cqe = unsafe { &*self.cqes.add((head & self.cq_mask) as usize) };
+            f(cqe.user_data, cqe.res);
+            head = head.wrapping_add(1);
+        }
+        unsafe { (*self.cq_head).store(head, Ordering::Release) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abi_sizes() {
+        assert_eq!(size_of::<Params>(), 120);
+        assert_eq!(size_of::<Sqe>(), 64);
+        assert_eq!(size_of::<Cqe>(), 16);
+    }
+}
@@ -4,6 +4,8 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use super::supervisor::Heartbeat;
+
 /// Pre-detect working encoder before starting stress test
 /// Call this BEFORE TUI starts to avoid terminal corruption from V4L2 driver
 pub fn detect_encoder() -> Option<&'static str> {
@@ -18,6 +20,7 @@ pub fn detect_encoder() -> Option<&'static str> {
 pub fn run_video_stress_with_encoder(
     running: Arc<AtomicBool>,
     errors: Arc<AtomicU64>,
+    heartbeat: Arc<Heartbeat>,
     encoder: &'static str,
 ) {
     // Create test input if needed
@@ -28,6 +31,7 @@ pub fn run_video_stress_with_encoder(
     }
 
     while running.load(Ordering::Relaxed) {
+        heartbeat.beat();
         if !run_encode_cycle(test_input, encoder) {
             errors.fetch_add(1, Ordering::Relaxed);
         }
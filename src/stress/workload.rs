@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Workload kinds that can be selected by name on the command line
+pub trait Workload: Copy + PartialEq + 'static {
+    /// Every workload of this kind, in default rotation order
+    const VARIANTS: &'static [Self];
+
+    fn name(&self) -> &'static str;
+
+    /// Left out of the default rotation; runs only when selected
+    fn opt_in(&self) -> bool {
+        false
+    }
+}
+
+/// Largest weight of one workload; the rotation holds this many slots for it
+pub const MAX_WEIGHT: u32 = 1000;
+
+/// One selected workload and how many rotation slots it gets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadWeight<W> {
+    pub workload: W,
+    pub weight: u32,
+}
+
+/// Weighted workload selection, parsed from e.g. "dft:2,matrix,aes"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WorkloadMix<W>(pub Vec<WorkloadWeight<W>>);
+
+impl<W: Workload> WorkloadMix<W> {
+    /// Every workload that is not opt-in, with weight 1 (the default rotation)
+    pub fn all() -> Self {
+        Self(
+            W::VARIANTS
+                .iter()
+                .filter(|w| !w.opt_in())
+                .map(|&workload| WorkloadWeight {
+                    workload,
+                    weight: 1,
+                })
+                .collect(),
+        )
+    }
+
+    /// Add `workload` with weight 1 unless it is already selected
+    pub fn include(&mut self, workload: W) {
+        if !self.0.iter().any(|w| w.workload == workload) {
+            self.0.push(WorkloadWeight {
+                workload,
+                weight: 1,
+            });
+        }
+    }
+
+    /// Rotation order, each workload repeated according to its weight
+    pub fn rotation(&self) -> Vec<W> {
+        self.0
+            .iter()
+            .flat_map(|w| std::iter::repeat_n(w.workload, w.weight as usize))
+            .collect()
+    }
+}
+
+impl<W: Workload> Default for WorkloadMix<W> {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl<W: Workload> FromStr for WorkloadMix<W> {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut selected: Vec<WorkloadWeight<W>> = Vec::new();
+
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, weight) = match item.split_once(':') {
+                Some((name, weight)) => {
+                    let weight = weight
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|w| (1..=MAX_WEIGHT).contains(w))
+                        .ok_or_else(|| {
+                            format!("Invalid weight in '{}' (1-{})", item, MAX_WEIGHT)
+                        })?;
+                    (name.trim(), weight)
+                }
+                None => (item, 1),
+            };
+
+            let workload = W::VARIANTS
+                .iter()
+                .copied()
+                .find(|w| w.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    let valid: Vec<&str> = W::VARIANTS.iter().map(|w| w.name()).collect();
+                    format!("Unknown workload '{}' (valid: {})", name, valid.join(", "))
+                })?;
+
+            if selected.iter().any(|w| w.workload == workload) {
+                return Err(format!("Workload '{}' listed twice", name));
+            }
+            selected.push(WorkloadWeight { workload, weight });
+        }
+
+        if selected.is_empty() {
+            return Err("No workloads selected".to_string());
+        }
+
+        Ok(Self(selected))
+    }
+}
+
+impl<W: Workload> fmt::Display for WorkloadMix<W> {
+    /// Formats as the command-line syntax, e.g. "dft:2,matrix"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|w| match w.weight {
+                1 => w.workload.name().to_string(),
+                weight => format!("{}:{}", w.workload.name(), weight),
+            })
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::cpu::CpuWorkload;
+
+    #[test]
+    fn test_parse_workload_mix() {
+        let mix: WorkloadMix<CpuWorkload> = "dft:2, matrix,AES".parse().unwrap();
+        assert_eq!(
+            mix.rotation(),
+            vec![
+                CpuWorkload::Dft,
+                CpuWorkload::Dft,
+                CpuWorkload::Matrix,
+                CpuWorkload::Aes
+            ]
+        );
+        assert_eq!(mix.to_string(), "dft:2,matrix,aes");
+    }
+
+    #[test]
+    fn test_parse_workload_mix_errors() {
+        assert!("".parse::<WorkloadMix<CpuWorkload>>().is_err());
+        assert!("bogus".parse::<WorkloadMix<CpuWorkload>>().is_err());
+        assert!("dft:0".parse::<WorkloadMix<CpuWorkload>>().is_err());
+        assert!("dft:1001".parse::<WorkloadMix<CpuWorkload>>().is_err());
+        assert!("dft:4000000000"
+            .parse::<WorkloadMix<CpuWorkload>>()
+            .is_err());
+        assert!("dft:1000".parse::<WorkloadMix<CpuWorkload>>().is_ok());
+        assert!("dft,dft".parse::<WorkloadMix<CpuWorkload>>().is_err());
+    }
+
+    #[test]
+    fn test_default_mix_is_all() {
+        let mix = WorkloadMix::<CpuWorkload>::default();
+        assert_eq!(mix.rotation(), CpuWorkload::ALL.to_vec());
+    }
+}
@@ -0,0 +1,56 @@
+use std::mem;
+
+/// Get the CPUs this process may run on (respects taskset and cpusets)
+pub fn allowed_cpus() -> Vec<usize> {
+    // Safety: cpu_set_t is a plain bitmask, zeroed is a valid empty set
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    let ret = unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) };
+
+    if ret == 0 {
+        let cpus: Vec<usize> = (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect();
+        if !cpus.is_empty() {
+            return cpus;
+        }
+    }
+
+    // Fallback: assume CPUs 0..N are all usable
+    let count = std::thread::available_parallelism()
+        .map(|p| p.get())
+        .unwrap_or(4);
+    (0..count).collect()
+}
+
+/// Pin the calling thread to a single CPU
+/// Returns false if the kernel refused (e.g. CPU offline or outside cpuset)
+pub fn pin_current_thread(cpu: usize) -> bool {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return false;
+    }
+
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    unsafe {
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_cpus() {
+        let cpus = allowed_cpus();
+        assert!(!cpus.is_empty());
+    }
+
+    #[test]
+    fn test_pin_current_thread() {
+        let cpu = allowed_cpus()[0];
+        let handle = std::thread::spawn(move || pin_current_thread(cpu));
+        assert!(handle.join().unwrap());
+        assert!(!pin_current_thread(libc::CPU_SETSIZE as usize));
+    }
+}
@@ -345,10 +345,11 @@ mod tests {
 
     #[test]
     fn test_voltage_offset() {
-        let mut config = OcConfig::default();
-
         // Test over_voltage_delta (in µV)
-        config.over_voltage_delta = Some(50000);
+        let mut config = OcConfig {
+            over_voltage_delta: Some(50000),
+            ..Default::default()
+        };
         assert_eq!(config.voltage_offset_mv(), Some(50.0));
 
         // Test over_voltage (legacy)
@@ -260,8 +260,8 @@ mod tests {
     #[test]
     fn test_collect_system_info() {
         let info = collect_system_info();
-        // Basic sanity checks
-        assert!(info.cpu_cores > 0 || info.cpu_cores == 0); // May be 0 on non-Pi systems
+        // Falls back to the available parallelism off a Pi
+        assert!(info.cpu_cores > 0);
     }
 
     #[test]
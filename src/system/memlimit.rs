@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// RAM, swap and cgroup limits that bound how much memory can be tested
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryLimits {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub swap_bytes: u64,
+    /// Room left under the tightest cgroup memory limit, if one is set
+    pub cgroup_headroom: Option<u64>,
+}
+
+impl MemoryLimits {
+    pub fn read() -> Self {
+        Self::read_from(Path::new("/proc"), Path::new("/sys/fs/cgroup"))
+    }
+
+    /// Read from alternative /proc and cgroup mount points (for tests)
+    fn read_from(proc_root: &Path, cgroup_root: &Path) -> Self {
+        let meminfo = fs::read_to_string(proc_root.join("meminfo")).unwrap_or_default();
+        let field = |name: &str| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(|value| value.trim().strip_suffix("kB"))
+                .and_then(|kb| kb.trim().parse::<u64>().ok())
+                .map_or(0, |kb| kb * 1024)
+        };
+        let cgroups = fs::read_to_string(proc_root.join("self/cgroup")).unwrap_or_default();
+
+        Self {
+            total_bytes: field("MemTotal"),
+            available_bytes: field("MemAvailable"),
+            swap_bytes: field("SwapTotal"),
+            cgroup_headroom: cgroup_headroom(&cgroups, cgroup_root),
+        }
+    }
+}
+
+/// Smallest (limit - usage) over this process's memory cgroup and its ancestors
+/// Handles cgroup v2 (memory.max) and the v1 memory controller.
+fn cgroup_headroom(cgroups: &str, root: &Path) -> Option<u64> {
+    let mut headroom: Option<u64> = None;
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        let (base, limit_file, usage_file) = if controllers.is_empty() {
+            // Hybrid systems mount the v2 hierarchy under "unified"
+            let unified = root.join("unified");
+            let base = if unified.is_dir() {
+                unified
+            } else {
+                root.to_path_buf()
+            };
+            (base, "memory.max", "memory.current")
+        } else if controllers.split(',').any(|c| c == "memory") {
+            (
+                root.join("memory"),
+                "memory.limit_in_bytes",
+                "memory.usage_in_bytes",
+            )
+        } else {
+            continue;
+        };
+
+        let mut dir: PathBuf = base.join(path.trim_start_matches('/'));
+        loop {
+            // "max" (v2) fails to parse and means no limit at this level
+            if let (Some(limit), Some(usage)) = (
+                read_u64(&dir.join(limit_file)),
+                read_u64(&dir.join(usage_file)),
+            ) {
+                let room = limit.saturating_sub(usage);
+                headroom = Some(headroom.map_or(room, |h| h.min(room)));
+            }
+            if dir == base || !dir.pop() {
+                break;
+            }
+        }
+    }
+    headroom
+}
+
+/// A number from a one-value procfs, sysfs or cgroup file
+pub fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_root(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        crate::testing::fake_tree(&format!("memlimit-{}", name), files)
+    }
+
+    const MEMINFO: &str = "MemTotal:        8000000 kB\n\
+                           MemFree:         1000000 kB\n\
+                           MemAvailable:    6000000 kB\n\
+                           SwapTotal:        512000 kB\n";
+
+    #[test]
+    fn test_cgroup_v2_takes_tightest_ancestor() {
+        let root = fake_root(
+            "v2",
+            &[
+                ("proc/meminfo", MEMINFO),
+                ("proc/self/cgroup", "0::/user.slice/run.scope\n"),
+                ("cgroup/user.slice/memory.max", "2000000000\n"),
+                ("cgroup/user.slice/memory.current", "500000000\n"),
+                ("cgroup/user.slice/run.scope/memory.max", "max\n"),
+                ("cgroup/user.slice/run.scope/memory.current", "100000000\n"),
+            ],
+        );
+        let limits = MemoryLimits::read_from(&root.join("proc"), &root.join("cgroup"));
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(limits.total_bytes, 8_000_000 * 1024);
+        assert_eq!(limits.available_bytes, 6_000_000 * 1024);
+        assert_eq!(limits.swap_bytes, 512_000 * 1024);
+        assert_eq!(limits.cgroup_headroom, Some(1_500_000_000));
+    }
+
+    #[test]
+    fn test_cgroup_v1_memory_controller() {
+        let root = fake_root(
+            "v1",
+            &[
+                ("proc/meminfo", MEMINFO),
+                ("proc/self/cgroup", "4:memory:/jobs/42\n1:cpu:/\n0::/\n"),
+                (
+                    "cgroup/memory/jobs/42/memory.limit_in_bytes",
+                    "1073741824\n",
+                ),
+                ("cgroup/memory/jobs/42/memory.usage_in_bytes", "73741824\n"),
+            ],
+        );
+        let limits = MemoryLimits::read_from(&root.join("proc"), &root.join("cgroup"));
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(limits.cgroup_headroom, Some(1_000_000_000));
+    }
+
+    #[test]
+    fn test_no_cgroup_limit() {
+        let root = fake_root(
+            "none",
+            &[("proc/meminfo", MEMINFO), ("proc/self/cgroup", "0::/\n")],
+        );
+        let limits = MemoryLimits::read_from(&root.join("proc"), &root.join("cgroup"));
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(limits.cgroup_headroom, None);
+    }
+}
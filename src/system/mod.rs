@@ -1,3 +1,6 @@
+pub mod affinity;
 pub mod config;
 pub mod info;
+pub mod memlimit;
 pub mod monitor;
+pub mod pagemap;
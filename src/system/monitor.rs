@@ -363,8 +363,8 @@ pub fn get_fan_status() -> FanStatus {
                         if let (Ok(cur), Ok(max)) =
                             (cur_str.trim().parse::<u32>(), max_str.trim().parse::<u32>())
                         {
-                            if max > 0 {
-                                status.speed_percent = Some(((cur * 100) / max).min(100) as u8);
+                            if let Some(pct) = (cur * 100).checked_div(max) {
+                                status.speed_percent = Some(pct.min(100) as u8);
                                 break;
                             }
                         }
@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+/// Page frame number bits of a pagemap entry
+const PFN_MASK: u64 = (1 << 55) - 1;
+
+/// Set when the page is present in RAM
+const PAGE_PRESENT: u64 = 1 << 63;
+
+/// Resolve a virtual address of this process to a physical address
+/// Uses /proc/self/pagemap; the kernel only reveals frame numbers to root
+/// (CAP_SYS_ADMIN), so this returns None for unprivileged runs.
+pub fn physical_address(virtual_address: usize) -> Option<u64> {
+    let page_size = page_size();
+    let file = File::open("/proc/self/pagemap").ok()?;
+    let mut entry = [0u8; 8];
+    let index = (virtual_address / page_size) as u64;
+    file.read_exact_at(&mut entry, index * 8).ok()?;
+    decode_entry(u64::from_le_bytes(entry), virtual_address, page_size)
+}
+
+/// Physical address from one pagemap entry, if the page is present and
+/// the frame number was not hidden
+fn decode_entry(entry: u64, virtual_address: usize, page_size: usize) -> Option<u64> {
+    let pfn = entry & PFN_MASK;
+    if entry & PAGE_PRESENT == 0 || pfn == 0 {
+        return None;
+    }
+    Some(pfn * page_size as u64 + (virtual_address % page_size) as u64)
+}
+
+/// Base page size of the running kernel (4K, or 16K on some Pi 5 kernels)
+pub fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_present_page() {
+        let entry = PAGE_PRESENT | 0x1234;
+        assert_eq!(
+            decode_entry(entry, 0x7f00_0000_0abc, 4096),
+            Some(0x1234 * 4096 + 0xabc)
+        );
+    }
+
+    #[test]
+    fn test_decode_hidden_or_missing_page() {
+        // Unprivileged readers see the present bit with a zeroed frame number
+        assert_eq!(decode_entry(PAGE_PRESENT, 0x1000, 4096), None);
+        assert_eq!(decode_entry(0x1234, 0x1000, 4096), None);
+    }
+}
@@ -0,0 +1,16 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A fresh temporary directory holding `files`, each a path relative to
+/// the directory and its contents; stands in for /proc, /sys or cgroupfs
+pub fn fake_tree<P: AsRef<Path>>(name: &str, files: &[(P, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("piup-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    root
+}
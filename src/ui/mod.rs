@@ -1,7 +1,7 @@
 pub mod simple;
 pub mod tui;
 
-use crate::detection::nvme::NvmeInfo;
+use crate::detection::storage::StorageDevice;
 use crate::stress::FinalReport;
 use crate::system::config::OcConfig;
 use crate::system::info::SystemInfo;
@@ -16,17 +16,17 @@ pub enum UiMode {
 pub fn display_system_info(
     sys_info: &SystemInfo,
     oc_config: &OcConfig,
-    nvme_info: &Option<NvmeInfo>,
+    storage: &[StorageDevice],
     mode: UiMode,
     no_color: bool,
 ) {
     match mode {
         UiMode::Tui => {
             // For TUI mode, we'll show this briefly before starting the TUI
-            simple::display_system_info(sys_info, oc_config, nvme_info, no_color);
+            simple::display_system_info(sys_info, oc_config, storage, no_color);
         }
         UiMode::Simple => {
-            simple::display_system_info(sys_info, oc_config, nvme_info, no_color);
+            simple::display_system_info(sys_info, oc_config, storage, no_color);
         }
     }
 }
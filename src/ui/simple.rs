@@ -1,4 +1,6 @@
-use crate::detection::nvme::NvmeInfo;
+use crate::detection::storage::{self, StorageDevice};
+use crate::stress::buffer::HugePages;
+use crate::stress::nvme::{self, DeviceReport, IoMode};
 use crate::stress::{FinalReport, StressStats};
 use crate::system::config::OcConfig;
 use crate::system::info::SystemInfo;
@@ -17,7 +19,7 @@ const DIM: &str = "\x1b[2m";
 pub fn display_system_info(
     sys_info: &SystemInfo,
     oc_config: &OcConfig,
-    nvme_info: &Option<NvmeInfo>,
+    storage: &[StorageDevice],
     no_color: bool,
 ) {
     let (bold, reset, cyan, dim) = if no_color {
@@ -172,43 +174,47 @@ pub fn display_system_info(
     println!("{}│  CPU Governor:       {}{}", cyan, governor, reset);
 
     // Storage section
-    if let Some(nvme) = nvme_info {
+    if !storage.is_empty() {
         println!(
             "{}├──────────────────────────────────────────────────────────────{}",
             cyan, reset
         );
         println!("{}│  {}STORAGE{}{}", cyan, bold, reset, reset);
+    }
+    let mounts = storage::read_mounts();
+    for device in storage {
         println!(
-            "{}│  NVMe Detected:      {}{}",
+            "{}│  {:<20}{}{}",
             cyan,
-            truncate_str(&nvme.model, 45),
+            format!("{}:", device.name),
+            truncate_str(&device.to_string(), 45),
             reset
         );
 
-        if let Some(gen) = nvme.pcie_gen {
+        if let Some(gen) = device.pcie_gen {
             let speed = match gen {
                 3 => "~900 MB/s",
                 2 => "~450 MB/s",
                 _ => "~250 MB/s",
             };
             println!(
-                "{}│  PCIe Generation:    Gen {}.0 x1 ({}){}",
+                "{}│    PCIe Generation:  Gen {}.0 x1 ({}){}",
                 cyan, gen, speed, reset
             );
         }
 
-        if let Some(temp) = crate::detection::nvme::get_nvme_temp(&nvme.device_path) {
-            println!("{}│  NVMe Temperature:   {:.1}°C{}", cyan, temp, reset);
+        if let Some(temp) = device.temperature() {
+            println!("{}│    Temperature:      {:.1}°C{}", cyan, temp, reset);
         }
 
-        // Show NVMe test file path (auto-detected, user can override with --nvme-path)
-        let test_path = crate::stress::nvme::get_test_file_path(nvme, None);
-        println!(
-            "{}│  NVMe Test File:     {}{}",
-            cyan,
-            test_path.display(),
-            reset
-        );
+        // Show test file path (auto-detected, user can override with --nvme-path)
+        match nvme::get_test_file_path(device, &mounts, None) {
+            Some(path) => println!("{}│    Test File:        {}{}", cyan, path.display(), reset),
+            None => println!(
+                "{}│    Test File:        {}no writable filesystem mounted{}",
+                cyan, dim, reset
+            ),
+        }
     }
 
     println!(
@@ -275,10 +281,10 @@ pub fn display_stats(stats: &StressStats, duration_secs: u64, no_color: bool) {
 
 /// Display final report
 pub fn display_final_report(report: &FinalReport, no_color: bool) {
-    let (bold, reset, green, red) = if no_color {
-        ("", "", "", "")
+    let (bold, reset, green, yellow, red) = if no_color {
+        ("", "", "", "", "")
     } else {
-        (BOLD, RESET, GREEN, RED)
+        (BOLD, RESET, GREEN, YELLOW, RED)
     };
 
     let check = if no_color { "[OK]" } else { "✓" };
@@ -319,6 +325,22 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
             "Computation errors detected"
         }
     );
+    if let Some(workloads) = &report.cpu_workloads {
+        println!("    Workloads: {}", workloads);
+    }
+    for core in report.cpu_core_errors.iter().filter(|c| c.errors > 0) {
+        println!("    {}→ {}{}", red, core, reset);
+    }
+    if let Some(mismatch) = &report.cpu_first_error {
+        println!("    {}→ First mismatch: {}{}", red, mismatch, reset);
+    }
+    print_worker_failures(report, "cpu", red, reset);
+    if let Some(linpack) = &report.linpack {
+        println!(
+            "    LINPACK: {:.2} GFLOPS (min {:.2}, max {:.2}, {} runs)",
+            linpack.last_gflops, linpack.min_gflops, linpack.max_gflops, linpack.iterations
+        );
+    }
     println!(
         "  Memory Stress:     {} {}",
         if report.memory_stress_passed {
@@ -332,19 +354,75 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
             "Memory errors detected"
         }
     );
+    if let Some(workloads) = &report.memory_workloads {
+        println!("    Workloads: {}", workloads);
+    }
+    if !report.memory_patterns.is_empty() {
+        let patterns: Vec<String> = report
+            .memory_patterns
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        println!("    Passes: {}", patterns.join(", "));
+    }
+    if let Some(coverage) = &report.memory_coverage {
+        println!("    Coverage: {}", coverage);
+        if let Some(reason) = &coverage.limited_by {
+            println!(
+                "    {}→ Reduced from {} ({} MB): limited by {}{}",
+                yellow,
+                coverage.requested,
+                coverage.requested_bytes / (1024 * 1024),
+                reason,
+                reset
+            );
+        }
+    }
+    if let Some(buffers) = &report.memory_buffers {
+        println!("    Buffers: {}", buffers);
+        if buffers.locked_bytes < buffers.allocated_bytes {
+            println!(
+                "    {}→ Not all test memory could be locked (run as root){}",
+                yellow, reset
+            );
+        }
+        if buffers.hugepages != HugePages::Off && buffers.huge_bytes == 0 {
+            println!(
+                "    {}→ Kernel did not provide the requested huge pages{}",
+                yellow, reset
+            );
+        }
+    }
+    if let Some(bench) = &report.memory_bench {
+        if let Some(bandwidth) = bench.bandwidth() {
+            println!("    Bandwidth: {}", bandwidth);
+        }
+        if let Some(latency) = bench.latency() {
+            println!("    Latency: {}", latency);
+        }
+    }
+    print_memory_errors(report, red, reset);
+    print_worker_failures(report, "memory", red, reset);
+    let storage_run = report.storage.iter().any(|d| d.skipped.is_none());
     println!(
-        "  NVMe Stress:       {} {}",
+        "  Storage Stress:    {} {}",
         if report.nvme_stress_passed {
             format!("{}{}{}", green, check, reset)
         } else {
             format!("{}{}{}", red, cross, reset)
         },
-        if report.nvme_stress_passed {
+        if !storage_run {
+            "Not run"
+        } else if report.nvme_stress_passed {
             "No I/O errors"
         } else {
             "I/O errors detected"
         }
     );
+    for device in &report.storage {
+        print_storage_device(device, (green, yellow, red, reset));
+    }
+    print_worker_failures(report, "nvme", red, reset);
     println!(
         "  Video Stress:      {} {}",
         if report.video_stress_passed {
@@ -358,6 +436,7 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
             "Video decode errors detected"
         }
     );
+    print_worker_failures(report, "video", red, reset);
     println!();
 
     println!("Temperature Stats:");
@@ -366,8 +445,10 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
         report.max_cpu_temp
     );
     println!("  CPU Avg:         {:.1}°C", report.avg_cpu_temp);
-    if let Some(nvme_max) = report.max_nvme_temp {
-        println!("  NVMe Max:        {:.1}°C", nvme_max);
+    for device in &report.storage {
+        if let Some(max) = device.max_temp {
+            println!("  {:<15}{:.1}°C", format!("{} Max:", device.device), max);
+        }
     }
     println!();
 
@@ -403,6 +484,9 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
         println!("    {}→ {}{}", red, truncate_str(error, 70), reset);
     }
 
+    let ras_color = if report.ras.any() { red } else { green };
+    println!("  RAS Errors:      {}{}{}", ras_color, report.ras, reset);
+
     let smart_color = if report.smart_warnings > 0 {
         red
     } else {
@@ -413,10 +497,152 @@ pub fn display_final_report(report: &FinalReport, no_color: bool) {
         smart_color, report.smart_warnings, reset
     );
 
+    let slowdown_color = if report.throughput_degraded {
+        yellow
+    } else {
+        green
+    };
+    let slowdowns = report
+        .throughput
+        .iter()
+        .filter(|t| t.unexplained_drops > 0)
+        .count();
+    println!(
+        "  Slowdowns:       {}{}{}",
+        slowdown_color, slowdowns, reset
+    );
+    for t in report.throughput.iter().filter(|t| t.unexplained_drops > 0) {
+        println!(
+            "    {}→ {} {}: {:.0}% below first-minute rate without throttling{}",
+            yellow,
+            t.subsystem,
+            t.workload,
+            t.max_drop * 100.0,
+            reset
+        );
+    }
+
     println!("═══════════════════════════════════════════════════════════════");
     println!();
 }
 
+/// Memory mismatch and corrupt NVMe block records shown in the summary;
+/// the JSON report has all of them
+const ERROR_RECORDS_SHOWN: usize = 5;
+
+/// Print the first memory mismatches and how often each bit lane flipped
+fn print_memory_errors(report: &FinalReport, red: &str, reset: &str) {
+    if report.memory_mismatches == 0 {
+        return;
+    }
+    println!(
+        "    {}→ {} mismatching locations{}",
+        red, report.memory_mismatches, reset
+    );
+    for error in report.memory_error_records.iter().take(ERROR_RECORDS_SHOWN) {
+        println!("    {}→ {}{}", red, error, reset);
+    }
+
+    let mut lanes = [0usize; 64];
+    for error in &report.memory_error_records {
+        for &bit in &error.flipped_bits {
+            lanes[bit as usize] += 1;
+        }
+    }
+    let mut flipped: Vec<(usize, usize)> = lanes
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(bit, &count)| (bit, count))
+        .collect();
+    flipped.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let flipped: Vec<String> = flipped
+        .iter()
+        .map(|(bit, count)| format!("{}×{}", bit, count))
+        .collect();
+    println!("    Flipped bits: {}", flipped.join(", "));
+}
+
+/// Print the outcome on one storage device
+fn print_storage_device(device: &DeviceReport, colors: (&str, &str, &str, &str)) {
+    let (green, yellow, red, reset) = colors;
+    let (color, status) = match (&device.skipped, device.errors) {
+        (Some(_), _) => (yellow, "not run".to_string()),
+        (None, 0) => (green, "no I/O errors".to_string()),
+        (None, errors) => (red, format!("{} errors", errors)),
+    };
+    println!(
+        "    {} ({}, {}): {}{}{}",
+        device.device, device.kind, device.model, color, status, reset
+    );
+    if let Some(reason) = &device.skipped {
+        println!("      {}→ Skipped: {}{}", yellow, reason, reset);
+    }
+    match device.file_size {
+        Some(size) if device.raw => println!(
+            "      {}→ Raw device: {} MB overwritten with the test pattern{}",
+            yellow,
+            size / (1024 * 1024),
+            reset
+        ),
+        Some(size) => println!("      Test file: {} MB", size / (1024 * 1024)),
+        None => {}
+    }
+    match device.io_mode {
+        Some(IoMode::Direct) => println!("      I/O mode: O_DIRECT"),
+        Some(IoMode::Buffered) => println!(
+            "      {}→ I/O mode: buffered (O_DIRECT refused, reads may have come from page cache){}",
+            yellow, reset
+        ),
+        None => {}
+    }
+    if let Some(queue) = device.queue {
+        println!("      Queue: {}", queue);
+    }
+    if let Some(latency) = device.read_latency {
+        println!("      Read latency:  {}", latency);
+    }
+    if let Some(latency) = device.write_latency {
+        println!("      Write latency: {}", latency);
+    }
+    if device.slow_ios > 0 {
+        println!(
+            "      {}→ {} slow I/Os (warning: link or controller stalls){}",
+            yellow, device.slow_ios, reset
+        );
+        for slow in device.slow_io_records.iter().take(ERROR_RECORDS_SHOWN) {
+            println!("      {}→ {}{}", yellow, slow, reset);
+        }
+    }
+    if device.corrupt_blocks > 0 {
+        println!(
+            "      {}→ {} corrupt blocks{}",
+            red, device.corrupt_blocks, reset
+        );
+        for corruption in device.corruptions.iter().take(ERROR_RECORDS_SHOWN) {
+            println!("      {}→ {}{}", red, corruption, reset);
+        }
+    }
+}
+
+/// Print the crashed and hung workers of one subsystem
+fn print_worker_failures(report: &FinalReport, subsystem: &str, red: &str, reset: &str) {
+    for crash in report
+        .worker_crashes
+        .iter()
+        .filter(|c| c.subsystem == subsystem)
+    {
+        println!("    {}→ {}{}", red, crash, reset);
+    }
+    for hang in report
+        .worker_hangs
+        .iter()
+        .filter(|h| h.subsystem == subsystem)
+    {
+        println!("    {}→ {}{}", red, hang, reset);
+    }
+}
+
 /// Truncate string to max length with ellipsis
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
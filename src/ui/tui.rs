@@ -19,7 +19,11 @@ use ratatui::{
 use tokio::sync::mpsc;
 
 use super::format_duration;
-use crate::stress::StressStats;
+use crate::stress::{
+    latency::format_us,
+    nvme::{DeviceStats, IoMode},
+    StressStats,
+};
 
 /// ASCII art title - "Pi Under Pressure" in Fire Font-s style
 const ASCII_TITLE: &[&str] = &[
@@ -35,7 +39,7 @@ const ASCII_TITLE: &[&str] = &[
 
 /// Height constants for layout
 const TITLE_HEIGHT: u16 = 11; // ASCII (8) + timer line (1) + borders (2)
-const MEM_HEIGHT: u16 = 8; // Memory section (6 lines + 2 border)
+const MEM_HEIGHT: u16 = 8; // Memory section (6 lines + 2 border), plus storage lines
 const PROGRESS_HEIGHT: u16 = 3; // Progress bar section (1 content + 2 border)
 const FOOTER_HEIGHT: u16 = 7; // Footer (4 content + 2 border + 1 padding)
 
@@ -130,6 +134,8 @@ fn render_ui(frame: &mut Frame, stats: &StressStats, total_secs: u64) {
     // Calculate CPU stats height based on number of cores (2 header lines + per-core bars + temp bar + borders)
     let num_cores = stats.cpu_usage_per_core.len().max(4);
     let cpu_height = (2 + num_cores + 1 + 2) as u16; // +1 for temp bar, +2 for borders
+                                                     // Two lines per storage device, or one "N/A" line
+    let mem_height = MEM_HEIGHT + (2 * stats.storage.len()).max(1) as u16;
 
     // Create main layout with 5 sections (vertical stacking)
     let chunks = Layout::default()
@@ -138,7 +144,7 @@ fn render_ui(frame: &mut Frame, stats: &StressStats, total_secs: u64) {
         .constraints([
             Constraint::Length(TITLE_HEIGHT),    // ASCII art title
             Constraint::Length(cpu_height),      // CPU stats
-            Constraint::Length(MEM_HEIGHT),      // Memory & Storage
+            Constraint::Length(mem_height),      // Memory & Storage
             Constraint::Length(PROGRESS_HEIGHT), // Progress
             Constraint::Length(FOOTER_HEIGHT),   // Footer
         ])
@@ -263,6 +269,18 @@ fn render_cpu_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
                 format!("{} MHz", stats.cpu_freq_mhz),
                 Style::default().fg(Color::Cyan),
             ),
+            Span::raw(if stats.load_step_hz.is_some() {
+                "  Step: "
+            } else {
+                ""
+            }),
+            Span::styled(
+                stats
+                    .load_step_hz
+                    .map(|hz| format!("{:.1} Hz", hz))
+                    .unwrap_or_default(),
+                Style::default().fg(Color::Magenta),
+            ),
         ]),
         Line::from(vec![
             Span::raw("  Throttling: "),
@@ -278,6 +296,19 @@ fn render_cpu_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
             ),
             Span::raw("  Fan: "),
             Span::styled(fan_str, Style::default().fg(Color::Cyan)),
+            Span::raw(if stats.linpack.is_some() {
+                "  LINPACK: "
+            } else {
+                ""
+            }),
+            Span::styled(
+                stats
+                    .linpack
+                    .as_ref()
+                    .map(|l| format!("{:.2} GFLOPS", l.last_gflops))
+                    .unwrap_or_default(),
+                Style::default().fg(Color::Cyan),
+            ),
         ]),
     ];
 
@@ -293,7 +324,7 @@ fn render_cpu_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
             Color::Red
         };
 
-        cpu_lines.push(Line::from(vec![
+        let mut core_line = vec![
             Span::raw(format!("  CPU{}: [", i)),
             Span::styled(
                 "━".repeat(filled.min(bar_width)),
@@ -305,7 +336,21 @@ fn render_cpu_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
             ),
             Span::raw("] "),
             Span::styled(format!("{:5.1}%", usage), Style::default().fg(bar_color)),
-        ]));
+        ];
+
+        // Per-core error attribution (e.g. "3 matrix errors")
+        if let Some(core) = stats
+            .cpu_core_errors
+            .iter()
+            .find(|c| c.core == i && c.errors > 0)
+        {
+            core_line.push(Span::styled(
+                format!("  {}", core.summary()),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        cpu_lines.push(Line::from(core_line));
     }
 
     // Add CPU temperature bar
@@ -343,18 +388,111 @@ fn render_cpu_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
     frame.render_widget(cpu_info, area);
 }
 
-fn render_memory_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
-    let nvme_temp_str = match stats.nvme_temp_c {
-        Some(temp) => format!("{:.1}°C", temp),
-        None => "N/A".to_string(),
+/// Test file and live I/O of one storage device
+fn storage_lines(device: &DeviceStats) -> [Line<'static>; 2] {
+    let test_path_str = match (&device.skipped, &device.test_path) {
+        (Some(reason), _) => format!("skipped: {}", reason),
+        (None, Some(path)) if device.raw => format!("RAW {}", path),
+        (None, Some(path)) => path.clone(),
+        (None, None) => "N/A".to_string(),
     };
 
-    let nvme_test_path_str = match &stats.nvme_test_path {
-        Some(path) => path.clone(),
+    let load_str = match device.load {
+        Some(load) => {
+            let p99 = |us: Option<u64>| us.map_or("-".to_string(), format_us);
+            format!(
+                "{} IOPS | {:.1} MB/s | p99 read {} / write {}",
+                if load.iops >= 1000.0 {
+                    format!("{:.1}k", load.iops / 1000.0)
+                } else {
+                    format!("{:.0}", load.iops)
+                },
+                load.mb_per_sec,
+                p99(load.read_p99_us),
+                p99(load.write_p99_us)
+            )
+        }
         None => "N/A".to_string(),
     };
+    let slow_ios = device.load.map_or(0, |load| load.slow_ios);
+
+    [
+        Line::from(vec![
+            Span::raw(format!(
+                "  {:<18}",
+                format!("{} ({}):", device.device, device.kind)
+            )),
+            Span::styled(
+                test_path_str,
+                Style::default().fg(if device.raw { Color::Red } else { Color::Cyan }),
+            ),
+            match device.io_mode {
+                Some(IoMode::Direct) => {
+                    Span::styled(" (O_DIRECT)", Style::default().fg(Color::Green))
+                }
+                Some(IoMode::Buffered) => Span::styled(
+                    " (buffered: page cache)",
+                    Style::default().fg(Color::Yellow),
+                ),
+                None => Span::raw(""),
+            },
+            match device.queue {
+                Some(queue) => Span::styled(
+                    format!(" {} QD {}", queue.engine, queue.in_flight()),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                None => Span::raw(""),
+            },
+        ]),
+        Line::from(vec![
+            Span::raw("    I/O:            "),
+            Span::styled(load_str, Style::default().fg(Color::Cyan)),
+            match device.temp_c {
+                Some(temp) => Span::styled(
+                    format!(" | {:.1}°C", temp),
+                    Style::default().fg(Color::Cyan),
+                ),
+                None => Span::raw(""),
+            },
+            if slow_ios > 0 {
+                Span::styled(
+                    format!(" | {} slow", slow_ios),
+                    Style::default().fg(Color::Yellow),
+                )
+            } else {
+                Span::raw("")
+            },
+            if device.errors > 0 {
+                Span::styled(
+                    format!(" | {} errors", device.errors),
+                    Style::default().fg(Color::Red),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+    ]
+}
+
+fn render_memory_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
+    // Triad bandwidth and DRAM latency summarise the full STREAM/latency figures
+    let bench = stats.memory_bench.as_ref();
+    let bench_str = match (
+        bench.and_then(|b| b.triad_gbps),
+        bench.and_then(|b| b.dram_ns),
+    ) {
+        (None, None) => "N/A".to_string(),
+        (triad, dram) => [
+            triad.map(|gbps| format!("triad {:.1} GB/s", gbps)),
+            dram.map(|ns| format!("DRAM {:.0} ns", ns)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" | "),
+    };
 
-    let mem_info = Paragraph::new(vec![
+    let mut lines = vec![
         Line::from(vec![
             Span::raw("  RAM Usage:        "),
             Span::styled(
@@ -373,6 +511,23 @@ fn render_memory_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
                 }),
             ),
         ]),
+        Line::from(vec![
+            Span::raw("  Memory Bench:     "),
+            Span::styled(bench_str, Style::default().fg(Color::Cyan)),
+        ]),
+        Line::from(vec![
+            Span::raw("  ECC (EDAC):       "),
+            Span::styled(
+                stats.ras.edac_summary(),
+                Style::default().fg(if stats.ras.any() {
+                    Color::Red
+                } else if stats.ras.corrected.is_none() {
+                    Color::DarkGray
+                } else {
+                    Color::Green
+                }),
+            ),
+        ]),
         Line::from(vec![
             Span::raw("  I/O Errors:       "),
             Span::styled(
@@ -384,14 +539,6 @@ fn render_memory_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
                 }),
             ),
         ]),
-        Line::from(vec![
-            Span::raw("  NVMe Temperature: "),
-            Span::styled(nvme_temp_str, Style::default().fg(Color::Cyan)),
-        ]),
-        Line::from(vec![
-            Span::raw("  NVMe Test File:   "),
-            Span::styled(nvme_test_path_str, Style::default().fg(Color::Cyan)),
-        ]),
         Line::from(vec![
             Span::raw("  Video Errors:     "),
             Span::styled(
@@ -403,8 +550,16 @@ fn render_memory_stats(frame: &mut Frame, area: Rect, stats: &StressStats) {
                 }),
             ),
         ]),
-    ])
-    .block(
+    ];
+    if stats.storage.is_empty() {
+        lines.push(Line::from(vec![
+            Span::raw("  Storage:          "),
+            Span::styled("N/A", Style::default().fg(Color::Cyan)),
+        ]));
+    }
+    lines.extend(stats.storage.iter().flat_map(storage_lines));
+
+    let mem_info = Paragraph::new(lines).block(
         Block::default()
             .title(" Memory & Storage ")
             .borders(Borders::ALL)
@@ -419,8 +574,20 @@ fn render_progress(frame: &mut Frame, area: Rect, stats: &StressStats, total_sec
     let remaining_str = format_duration(remaining_secs);
 
     // Render the block/border on full area
+    let mut title = vec![Span::raw(" Progress ")];
+    if !stats.hung_workers.is_empty() {
+        let hung: Vec<String> = stats
+            .hung_workers
+            .iter()
+            .map(|h| format!("{} {}", h.subsystem, h.worker))
+            .collect();
+        title.push(Span::styled(
+            format!("HUNG: {} ", hung.join(", ")),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
     let block = Block::default()
-        .title(" Progress ")
+        .title(Line::from(title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
     frame.render_widget(block, area);